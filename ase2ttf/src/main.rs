@@ -1,11 +1,70 @@
 use std::{
+    env,
     fs::{self, File},
     io::Write,
     path::Path,
+    process,
 };
 
-use ase2ttf_core::{Params, generate_ttf};
-use clap::Parser;
+use ase2ttf_core::{
+    EmbeddingLevel, Error, Params, Warning, WarningKind, analyze, export_glyphs_svg, font_metrics,
+    generate_fnt, generate_sfd, generate_svg, generate_ttf_with_warnings, generate_woff,
+    generate_woff2, generation_warnings, read_ase_file,
+};
+use clap::{Parser, ValueEnum};
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Ttf,
+    Woff,
+    Woff2,
+    Sfd,
+    Svg,
+    Fnt,
+}
+
+impl Format {
+    fn from_extension(extension: &str) -> Format {
+        match extension.to_lowercase().as_str() {
+            "woff" => Format::Woff,
+            "woff2" => Format::Woff2,
+            "sfd" => Format::Sfd,
+            "svg" => Format::Svg,
+            "fnt" => Format::Fnt,
+            _ => Format::Ttf,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Ttf => "ttf",
+            Format::Woff => "woff",
+            Format::Woff2 => "woff2",
+            Format::Sfd => "sfd",
+            Format::Svg => "svg",
+            Format::Fnt => "fnt",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Embedding {
+    Installable,
+    Restricted,
+    PreviewAndPrint,
+    Editable,
+}
+
+impl From<Embedding> for EmbeddingLevel {
+    fn from(embedding: Embedding) -> EmbeddingLevel {
+        match embedding {
+            Embedding::Installable => EmbeddingLevel::Installable,
+            Embedding::Restricted => EmbeddingLevel::Restricted,
+            Embedding::PreviewAndPrint => EmbeddingLevel::PreviewAndPrint,
+            Embedding::Editable => EmbeddingLevel::Editable,
+        }
+    }
+}
 
 #[derive(Debug, Parser)]
 #[command(version = "0.1.0", about, long_about = None)]
@@ -15,6 +74,9 @@ struct Args {
     #[arg(short, long)]
     output: Option<String>,
 
+    #[arg(long, value_enum)]
+    format: Option<Format>,
+
     #[arg(long)]
     copyright: Option<String>,
 
@@ -43,56 +105,381 @@ struct Args {
     trim_pad: u32,
 
     #[arg(long, require_equals = true, default_value_t = 0)]
-    line_gap: u8,
+    line_gap: i16,
 
     #[arg(long, require_equals = true)]
     spacing: Option<u32>,
 
-    #[arg(long, require_equals = true, default_value_t = 2)]
-    baseline: i16,
+    #[arg(long, require_equals = true)]
+    baseline: Option<i16>,
 
     #[arg(long, require_equals = true, default_value_t = 0)]
     underline_position: i16,
 
     #[arg(long, require_equals = true, default_value_t = 1)]
     underline_thickness: i16,
+
+    #[arg(long, default_value_t = false)]
+    use_tags: bool,
+
+    #[arg(long)]
+    config: Option<String>,
+
+    #[arg(long, default_value_t = false)]
+    web_optimized: bool,
+
+    /// Design size in pixels, recorded in points at 96 dpi
+    #[arg(long, require_equals = true)]
+    pixel_size: Option<f32>,
+
+    #[arg(long, require_equals = true, default_value_t = 0)]
+    margin_top: u32,
+
+    #[arg(long, require_equals = true, default_value_t = 0)]
+    margin_bottom: u32,
+
+    #[arg(long, default_value_t = false)]
+    mirror: bool,
+
+    #[arg(long, default_value_t = false)]
+    mirror_pua: bool,
+
+    #[arg(long, require_equals = true)]
+    family_class: Option<i16>,
+
+    #[arg(long, require_equals = true, value_parser = parse_codepoint)]
+    cap_height_reference: Option<u32>,
+
+    #[arg(long, require_equals = true)]
+    downscale: Option<u32>,
+
+    #[arg(long, require_equals = true, value_delimiter = ',')]
+    name_ids: Option<Vec<u16>>,
+
+    #[arg(long, require_equals = true, value_delimiter = ',', num_args = 0..)]
+    device_metrics: Option<Vec<u8>>,
+
+    #[arg(long, default_value_t = false)]
+    print_metrics: bool,
+
+    #[arg(long, require_equals = true, allow_negative_numbers = true)]
+    italic_angle: Option<f32>,
+
+    #[arg(long, require_equals = true)]
+    max_points: Option<u16>,
+
+    #[arg(long, require_equals = true)]
+    bleed: Option<u32>,
+
+    #[arg(long, default_value_t = false)]
+    clamp_trim_scale: bool,
+
+    #[arg(long, default_value_t = false)]
+    variable: bool,
+
+    #[arg(long, require_equals = true)]
+    max_weight: Option<u16>,
+
+    #[arg(long, default_value_t = false)]
+    keep_trailing_space: bool,
+
+    #[arg(long, default_value_t = false)]
+    force: bool,
+
+    #[arg(long, default_value_t = false)]
+    emit_blank_glyphs: bool,
+
+    #[arg(long, require_equals = true)]
+    units_per_em: Option<u16>,
+
+    #[arg(long, require_equals = true)]
+    corner_radius: Option<f64>,
+
+    #[arg(long, require_equals = true, allow_negative_numbers = true)]
+    created_timestamp: Option<i64>,
+
+    #[arg(long, require_equals = true)]
+    ascent: Option<i16>,
+
+    #[arg(long, require_equals = true)]
+    descent: Option<i16>,
+
+    #[arg(long, require_equals = true)]
+    kerning_file: Option<String>,
+
+    #[arg(long, require_equals = true, default_value_t = 0)]
+    composite_threshold: u16,
+
+    #[arg(long, default_value_t = false)]
+    color: bool,
+
+    #[arg(long, default_value_t = false)]
+    embed_bitmaps: bool,
+
+    #[arg(long, require_equals = true)]
+    space_width: Option<u32>,
+
+    #[arg(long, require_equals = true)]
+    advances_file: Option<String>,
+
+    #[arg(long, default_value_t = false)]
+    analyze: bool,
+
+    #[arg(long, require_equals = true)]
+    charset: Option<String>,
+
+    #[arg(long, require_equals = true)]
+    alpha_threshold: Option<u8>,
+
+    #[arg(long, default_value_t = false)]
+    include_hidden: bool,
+
+    #[arg(long, require_equals = true)]
+    cell_margin: Option<u32>,
+
+    #[arg(long, require_equals = true)]
+    vendor_id: Option<String>,
+
+    #[arg(long, value_enum)]
+    embedding: Option<Embedding>,
+
+    #[arg(long, require_equals = true)]
+    postscript_name: Option<String>,
+
+    #[arg(long, require_equals = true)]
+    debug_svg: Option<String>,
+
+    #[arg(long, default_value_t = false)]
+    share_glyphs: bool,
+
+    #[arg(long, require_equals = true)]
+    synthetic_bold: Option<u32>,
+}
+
+fn parse_codepoint(value: &str) -> Result<u32, String> {
+    let hex = value
+        .strip_prefix("U+")
+        .or_else(|| value.strip_prefix("u+"))
+        .unwrap_or(value);
+    u32::from_str_radix(hex, 16)
+        .map_err(|_| format!("'{}' is not a codepoint such as U+0048", value))
+}
+
+/// Generates an output format that isn't returned with its warnings, which are collected from a
+/// second build of the font.
+fn with_warnings(
+    ase_bytes: &[u8],
+    params: Params,
+    generate: impl FnOnce(&[u8], Params) -> Result<Vec<u8>, Error>,
+) -> Result<(Vec<u8>, Vec<Warning>), Error> {
+    let warnings = generation_warnings(ase_bytes, &params)?;
+    Ok((generate(ase_bytes, params)?, warnings))
 }
 
 fn main() {
     let args = Args::parse();
+    // errors are printed as one line, as the CLI is often run from build scripts
+    if let Err(message) = run(args) {
+        eprintln!("error: {}", message);
+        process::exit(1);
+    }
+}
+
+fn run(args: Args) -> Result<(), String> {
     let path = Path::new(&args.path);
 
-    let ase_bytes = fs::read(path).unwrap();
-    let ttf_bytes = generate_ttf(
-        &ase_bytes,
-        Params {
-            file_path: args.path.clone(),
-            copyright: args.copyright,
-            family: args.family,
-            subfamily: args.subfamily,
-            font_version: args.font_version,
-            font_weight: args.font_weight,
-            glyph_width: Some(args.glyph_width),
-            glyph_height: Some(args.glyph_height),
-            trim: Some(args.trim),
-            trim_pad: Some(args.trim_pad),
-            line_gap: Some(args.line_gap),
-            baseline: Some(args.baseline),
-            underline_position: Some(args.underline_position),
-            underline_thickness: Some(args.underline_thickness),
-            spacing: args.spacing,
-        },
-    )
-    .unwrap();
-
-    let file_stem = Path::new(&args.path)
+    let config = args
+        .config
+        .as_ref()
+        .map(|path| {
+            fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read config file {}: {}", path, e))
+        })
+        .transpose()?;
+    let kerning = args
+        .kerning_file
+        .as_ref()
+        .map(|path| {
+            fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read kerning file {}: {}", path, e))
+        })
+        .transpose()?;
+    let advances = args
+        .advances_file
+        .as_ref()
+        .map(|path| {
+            fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read advances file {}: {}", path, e))
+        })
+        .transpose()?;
+    let file_stem = path
         .file_stem()
-        .unwrap()
-        .to_str()
-        .unwrap()
+        .and_then(|stem| stem.to_str())
+        .ok_or_else(|| format!("{} is not a file name.", args.path))?
         .to_string();
+    let output = args.output.unwrap_or(format!(
+        "{0}.{1}",
+        file_stem,
+        args.format.unwrap_or(Format::Ttf).extension()
+    ));
+    // an existing font may have been edited by hand, so it is only replaced when asked to
+    if !args.analyze && Path::new(&output).exists() && !args.force {
+        return Err(format!(
+            "{} already exists. Use --force to overwrite it.",
+            output
+        ));
+    }
+
+    // reproducible builds pin the timestamps through SOURCE_DATE_EPOCH
+    let created_timestamp = match args.created_timestamp {
+        Some(timestamp) => Some(timestamp),
+        None => env::var("SOURCE_DATE_EPOCH")
+            .ok()
+            .map(|epoch| {
+                epoch.trim().parse::<i64>().map_err(|_| {
+                    format!("SOURCE_DATE_EPOCH '{}' is not a number of seconds.", epoch)
+                })
+            })
+            .transpose()?,
+    };
+
+    let params = Params {
+        file_path: None,
+        copyright: args.copyright,
+        family: args.family,
+        subfamily: args.subfamily,
+        font_version: args.font_version,
+        font_weight: args.font_weight,
+        glyph_width: Some(args.glyph_width),
+        glyph_height: Some(args.glyph_height),
+        trim: Some(args.trim),
+        trim_pad: Some(args.trim_pad),
+        line_gap: Some(args.line_gap),
+        baseline: args.baseline,
+        underline_position: Some(args.underline_position),
+        underline_thickness: Some(args.underline_thickness),
+        spacing: args.spacing,
+        use_tags: Some(args.use_tags),
+        config,
+        web_optimized: Some(args.web_optimized),
+        variable: Some(args.variable),
+        pixel_size: args.pixel_size,
+        margin_top: Some(args.margin_top),
+        margin_bottom: Some(args.margin_bottom),
+        mirror: Some(args.mirror),
+        mirror_pua: Some(args.mirror_pua),
+        family_class: args.family_class,
+        cap_height_reference: args.cap_height_reference,
+        downscale: args.downscale,
+        name_ids: args.name_ids,
+        device_metrics: args.device_metrics,
+        italic_angle: args.italic_angle,
+        max_points: args.max_points,
+        bleed: args.bleed,
+        clamp_trim_scale: Some(args.clamp_trim_scale),
+        max_weight: args.max_weight,
+        keep_trailing_space: Some(args.keep_trailing_space),
+        emit_blank_glyphs: Some(args.emit_blank_glyphs),
+        units_per_em: args.units_per_em,
+        corner_radius: args.corner_radius,
+        created_timestamp,
+        ascent: args.ascent,
+        descent: args.descent,
+        kerning,
+        composite_threshold: Some(args.composite_threshold),
+        color: Some(args.color),
+        embed_bitmaps: Some(args.embed_bitmaps),
+        space_width: args.space_width,
+        advances,
+        charset: args.charset,
+        alpha_threshold: args.alpha_threshold,
+        include_hidden: Some(args.include_hidden),
+        cell_margin: args.cell_margin,
+        vendor_id: args.vendor_id,
+        embedding: args.embedding.map(EmbeddingLevel::from),
+        postscript_name: args.postscript_name,
+        share_glyphs: Some(args.share_glyphs),
+        synthetic_bold: args.synthetic_bold,
+        localized_names: None,
+    };
+    // the path of the sheet becomes its file_path, from which the family is named
+    let (ase_bytes, params) = read_ase_file(path, params).map_err(|e| e.to_string())?;
+
+    // the report takes the place of the font, so nothing is written
+    if args.analyze {
+        let report = analyze(&ase_bytes, &params).map_err(|e| e.to_string())?;
+        print!("{}", report);
+        return Ok(());
+    }
+
+    let metrics = if args.print_metrics {
+        Some(font_metrics(&ase_bytes, &params).map_err(|e| e.to_string())?)
+    } else {
+        None
+    };
+
+    // the outlines are written next to the font, named after their codepoints
+    if let Some(dir) = &args.debug_svg {
+        let svgs = export_glyphs_svg(&ase_bytes, &params).map_err(|e| e.to_string())?;
+        let write_error =
+            |path: &Path, e: std::io::Error| format!("Failed to write {}: {}", path.display(), e);
+        let svgs: Vec<_> = svgs
+            .into_iter()
+            .map(|(codepoint, svg)| (Path::new(dir).join(format!("U+{:04X}.svg", codepoint)), svg))
+            .collect();
+        // like the font, outlines from an earlier run are only replaced when asked to
+        if let Some((path, _)) = svgs.iter().find(|(path, _)| path.exists() && !args.force) {
+            return Err(format!(
+                "{} already exists. Use --force to overwrite it.",
+                path.display()
+            ));
+        }
+        fs::create_dir_all(dir).map_err(|e| write_error(Path::new(dir), e))?;
+        for (path, svg) in svgs {
+            fs::write(&path, svg).map_err(|e| write_error(&path, e))?;
+        }
+    }
+
+    // the output format is chosen by --format, or else by the file extension
+    let format = args.format.unwrap_or_else(|| {
+        Format::from_extension(
+            Path::new(&output)
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .unwrap_or(""),
+        )
+    });
+    let (bytes, warnings) = match format {
+        Format::Ttf => generate_ttf_with_warnings(&ase_bytes, params),
+        Format::Woff => with_warnings(&ase_bytes, params, generate_woff),
+        Format::Woff2 => with_warnings(&ase_bytes, params, generate_woff2),
+        Format::Sfd => with_warnings(&ase_bytes, params, |bytes, params| {
+            generate_sfd(bytes, params).map(String::into_bytes)
+        }),
+        Format::Svg => with_warnings(&ase_bytes, params, |bytes, params| {
+            generate_svg(bytes, params).map(String::into_bytes)
+        }),
+        Format::Fnt => with_warnings(&ase_bytes, params, |bytes, params| {
+            generate_fnt(bytes, params).map(String::into_bytes)
+        }),
+    }
+    .map_err(|e| e.to_string())?;
+
+    for warning in warnings {
+        // simplifying is what --max-points asks for, so it is only noted
+        match warning.kind {
+            WarningKind::SimplifiedGlyph => eprintln!("note: {}", warning),
+            _ => eprintln!("warning: {}", warning),
+        }
+    }
+
+    let write_error = |e: std::io::Error| format!("Failed to write {}: {}", output, e);
+    let mut file = File::create(&output).map_err(write_error)?;
+    file.write_all(&bytes).map_err(write_error)?;
+    file.flush().map_err(write_error)?;
 
-    let mut file = File::create(args.output.unwrap_or(format!("{0}.ttf", file_stem))).unwrap();
-    file.write_all(&ttf_bytes).expect("Failed to write file.");
-    file.flush().expect("Failed to write file.");
+    if let Some(metrics) = metrics {
+        eprint!("{}", metrics);
+    }
+    Ok(())
 }
@@ -0,0 +1,96 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+/// A sheet of two 4x4 glyphs, 'A' and 'B'.
+const SHEET: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/sheet.aseprite");
+
+/// Creates an empty directory for a test to write its files in.
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("ase2ttf-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn ase2ttf(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_ase2ttf"))
+        .current_dir(dir)
+        .arg(SHEET)
+        .args(["--glyph-width=4", "--glyph-height=4"])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn debug_svgs_are_only_replaced_with_force() {
+    let dir = temp_dir("debug-svg");
+    let svg = dir.join("svg/U+0041.svg");
+    fs::create_dir_all(svg.parent().unwrap()).unwrap();
+    fs::write(&svg, "edited").unwrap();
+
+    let output = ase2ttf(&dir, &["--debug-svg=svg"]);
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        format!(
+            "error: {} already exists. Use --force to overwrite it.\n",
+            Path::new("svg").join("U+0041.svg").display()
+        )
+    );
+    assert_eq!(fs::read_to_string(&svg).unwrap(), "edited");
+    assert!(!dir.join("sheet.ttf").exists());
+
+    let output = ase2ttf(&dir, &["--debug-svg=svg", "--force"]);
+    assert!(output.status.success());
+    assert!(fs::read_to_string(&svg).unwrap().starts_with("<svg "));
+    assert!(dir.join("sheet.ttf").exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn warnings_are_printed_with_the_font() {
+    let dir = temp_dir("warnings");
+    fs::write(dir.join("kerning.txt"), "A Z -1\n").unwrap();
+
+    for format in ["ttf", "sfd"] {
+        let output = ase2ttf(
+            &dir,
+            &[
+                "--kerning-file=kerning.txt",
+                &format!("--format={}", format),
+            ],
+        );
+        assert!(output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(stderr.matches("warning: ").count(), 1, "{}", stderr);
+        assert!(dir.join(format!("sheet.{}", format)).exists());
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn existing_fonts_are_only_replaced_with_force() {
+    let dir = temp_dir("force");
+    let font = dir.join("sheet.ttf");
+    fs::write(&font, "edited").unwrap();
+
+    let output = ase2ttf(&dir, &[]);
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "error: sheet.ttf already exists. Use --force to overwrite it.\n"
+    );
+    assert_eq!(fs::read_to_string(&font).unwrap(), "edited");
+
+    let output = ase2ttf(&dir, &["--force"]);
+    assert!(output.status.success());
+    assert!(fs::read(&font).unwrap().starts_with(&[0, 1, 0, 0]));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
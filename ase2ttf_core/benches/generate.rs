@@ -0,0 +1,131 @@
+use ase2ttf_core::{Params, generate_ttf};
+use criterion::{Criterion, criterion_group, criterion_main};
+
+const COLS: u16 = 16;
+const ROWS: u16 = 6;
+
+/// Builds a single-layer RGBA sheet of `COLS` x `ROWS` glyphs named `U+0021-`, deciding each
+/// pixel with `ink`. The frame holds one uncompressed cel that covers the whole sprite.
+fn sheet(glyph_size: u16, ink: impl Fn(u16, u16) -> bool) -> Vec<u8> {
+    let (width, height) = (COLS * glyph_size, ROWS * glyph_size);
+
+    let name = "U+0021-";
+    let mut layer = vec![];
+    layer.extend(3u16.to_le_bytes()); // visible and editable
+    layer.extend([0; 10]); // an image layer at the top level with the normal blend mode
+    layer.push(255);
+    layer.extend([0; 3]);
+    layer.extend((name.len() as u16).to_le_bytes());
+    layer.extend(name.as_bytes());
+
+    let mut cel = vec![0; 6]; // the first layer, at the origin
+    cel.push(255);
+    cel.extend([0; 9]); // raw pixels
+    cel.extend(width.to_le_bytes());
+    cel.extend(height.to_le_bytes());
+    for y in 0..height {
+        for x in 0..width {
+            cel.extend(if ink(x, y) { [0, 0, 0, 255] } else { [0; 4] });
+        }
+    }
+
+    let chunks = [chunk(0x2004, layer), chunk(0x2005, cel)].concat();
+    let mut frame = vec![];
+    frame.extend((16 + chunks.len() as u32).to_le_bytes());
+    frame.extend(0xF1FAu16.to_le_bytes());
+    frame.extend(2u16.to_le_bytes());
+    frame.extend(100u16.to_le_bytes());
+    frame.extend([0; 2]);
+    frame.extend(2u32.to_le_bytes());
+    frame.extend(chunks);
+
+    let mut file = vec![];
+    file.extend((128 + frame.len() as u32).to_le_bytes());
+    file.extend(0xA5E0u16.to_le_bytes());
+    file.extend(1u16.to_le_bytes());
+    file.extend(width.to_le_bytes());
+    file.extend(height.to_le_bytes());
+    file.extend(32u16.to_le_bytes());
+    file.extend(1u32.to_le_bytes()); // layer opacity is valid
+    file.extend(100u16.to_le_bytes());
+    file.extend([0; 14]);
+    file.extend([1, 1]); // square pixels
+    file.resize(128, 0);
+    file.extend(frame);
+    file
+}
+
+fn chunk(kind: u16, data: Vec<u8>) -> Vec<u8> {
+    let mut chunk = vec![];
+    chunk.extend((6 + data.len() as u32).to_le_bytes());
+    chunk.extend(kind.to_le_bytes());
+    chunk.extend(data);
+    chunk
+}
+
+/// Deterministic pseudo-random ink, so the sheets have many small contours and holes.
+fn noise(x: u16, y: u16, density: u32) -> bool {
+    let mut h = (x as u32).wrapping_mul(0x9E37_79B9) ^ (y as u32).wrapping_mul(0x85EB_CA6B);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x2C1B_3C6D);
+    h ^= h >> 12;
+    h % 100 < density
+}
+
+fn bench_generate(c: &mut Criterion) {
+    let sheets = [
+        ("solid_8px", 8, sheet(8, |_, _| true)),
+        ("sparse_8px", 8, sheet(8, |x, y| noise(x, y, 35))),
+        // thin horizontal and vertical strokes, similar to CJK glyphs
+        (
+            "strokes_16px",
+            16,
+            sheet(16, |x, y| {
+                x % 16 == 3 || x % 16 == 11 || y % 16 == 2 || y % 16 == 7 || y % 16 == 13
+            }),
+        ),
+        ("dense_16px", 16, sheet(16, |x, y| noise(x, y, 60))),
+        // 20 separate strokes per glyph, alternating horizontal and vertical in a 4 x 5 grid,
+        // so that every glyph nests 20 contours
+        (
+            "twenty_strokes_32px",
+            32,
+            sheet(32, |x, y| {
+                let (x, y) = (x % 32, y % 32);
+                if y >= 30 {
+                    return false;
+                }
+                let (bx, by) = (x % 8, y % 6);
+                if (x / 8 + y / 6) % 2 == 0 {
+                    by == 2 && (1..7).contains(&bx)
+                } else {
+                    bx == 3 && (1..5).contains(&by)
+                }
+            }),
+        ),
+        // a lattice of strokes with many holes in a single outline, whose nesting is compared
+        // pairwise
+        (
+            "lattice_32px",
+            32,
+            sheet(32, |x, y| x % 4 == 0 || y % 4 == 0),
+        ),
+    ];
+
+    let mut group = c.benchmark_group("generate_ttf");
+    for (name, glyph_size, bytes) in &sheets {
+        group.bench_function(*name, |b| {
+            b.iter(|| {
+                let params = Params::builder()
+                    .file_path("bench.aseprite")
+                    .glyph_size(*glyph_size as u32, *glyph_size as u32)
+                    .build();
+                generate_ttf(bytes, params).unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_generate);
+criterion_main!(benches);
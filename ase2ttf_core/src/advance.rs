@@ -0,0 +1,60 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use crate::Error;
+
+/// An advance from the advances file that was left out, because its codepoint has no glyph.
+#[derive(Debug, PartialEq)]
+pub struct SkippedAdvance {
+    pub codepoint: u32,
+}
+
+impl Display for SkippedAdvance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the advance of U+{:04X} was skipped, as it has no glyph",
+            self.codepoint
+        )
+    }
+}
+
+/// Reads an advances file, where each line is a codepoint in hex followed by its advance in
+/// pixels, e.g. `0041 9`. Codepoints can also be written as `U+XXXX`. A codepoint that is listed
+/// twice takes the later advance.
+pub(crate) fn parse_advances(source: &str) -> Result<BTreeMap<u32, u32>, Error> {
+    let mut advances = BTreeMap::new();
+    for (i, line) in source.lines().enumerate() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.is_empty() {
+            continue;
+        }
+        let invalid = |reason: String| {
+            Error::new(format!("Invalid advances file: line {}: {}", i + 1, reason))
+        };
+        let [codepoint, advance] = fields[..] else {
+            return Err(invalid(format!(
+                "expected a codepoint and an advance, but found '{}'.",
+                line.trim()
+            )));
+        };
+        let hex = codepoint
+            .strip_prefix("U+")
+            .or_else(|| codepoint.strip_prefix("u+"))
+            .unwrap_or(codepoint);
+        let codepoint = u32::from_str_radix(hex, 16)
+            .ok()
+            .filter(|_| hex.chars().all(|c| c.is_ascii_hexdigit()))
+            .ok_or_else(|| {
+                invalid(format!(
+                    "'{}' is not a codepoint such as 0041 or U+0041.",
+                    codepoint
+                ))
+            })?;
+        let advance = advance
+            .parse::<u32>()
+            .map_err(|_| invalid(format!("'{}' is not an advance in whole pixels.", advance)))?;
+        advances.insert(codepoint, advance);
+    }
+    Ok(advances)
+}
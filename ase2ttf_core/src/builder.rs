@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+use crate::{EmbeddingLevel, LocalizedName, Params};
+
+/// Builds [`Params`] one setting at a time, leaving the others at their defaults.
+#[derive(Default)]
+pub struct ParamsBuilder {
+    params: Params,
+}
+
+/// Adds a setter for each field of [`Params`], which sets it to the given value.
+macro_rules! setters {
+    ($($name:ident: $ty:ty,)*) => {
+        $(
+            #[doc = concat!("Sets [`Params::", stringify!($name), "`].")]
+            pub fn $name(mut self, $name: $ty) -> ParamsBuilder {
+                self.params.$name = Some($name.into());
+                self
+            }
+        )*
+    };
+}
+
+impl ParamsBuilder {
+    pub fn new() -> ParamsBuilder {
+        ParamsBuilder::default()
+    }
+
+    /// Sets the width and the height of a glyph cell, in pixels.
+    pub fn glyph_size(self, width: u32, height: u32) -> ParamsBuilder {
+        self.glyph_width(width).glyph_height(height)
+    }
+
+    setters! {
+        file_path: impl Into<String>,
+        copyright: impl Into<String>,
+        family: impl Into<String>,
+        subfamily: impl Into<String>,
+        font_version: impl Into<String>,
+        font_weight: u16,
+        glyph_width: u32,
+        glyph_height: u32,
+        trim: bool,
+        trim_pad: u32,
+        line_gap: i16,
+        spacing: u32,
+        baseline: i16,
+        underline_position: i16,
+        underline_thickness: i16,
+        use_tags: bool,
+        config: impl Into<String>,
+        web_optimized: bool,
+        variable: bool,
+        pixel_size: f32,
+        margin_top: u32,
+        margin_bottom: u32,
+        mirror: bool,
+        mirror_pua: bool,
+        family_class: i16,
+        cap_height_reference: u32,
+        downscale: u32,
+        name_ids: Vec<u16>,
+        device_metrics: Vec<u8>,
+        italic_angle: f32,
+        max_points: u16,
+        bleed: u32,
+        clamp_trim_scale: bool,
+        max_weight: u16,
+        keep_trailing_space: bool,
+        emit_blank_glyphs: bool,
+        units_per_em: u16,
+        corner_radius: f64,
+        created_timestamp: i64,
+        ascent: i16,
+        descent: i16,
+        kerning: impl Into<String>,
+        composite_threshold: u16,
+        color: bool,
+        embed_bitmaps: bool,
+        space_width: u32,
+        advances: impl Into<String>,
+        charset: impl Into<String>,
+        alpha_threshold: u8,
+        include_hidden: bool,
+        cell_margin: u32,
+        vendor_id: impl Into<String>,
+        embedding: EmbeddingLevel,
+        postscript_name: impl Into<String>,
+        share_glyphs: bool,
+        synthetic_bold: u32,
+        localized_names: HashMap<u16, LocalizedName>,
+    }
+
+    pub fn build(self) -> Params {
+        self.params
+    }
+}
+
+impl Params {
+    /// Starts building parameters from the defaults.
+    pub fn builder() -> ParamsBuilder {
+        ParamsBuilder::new()
+    }
+}
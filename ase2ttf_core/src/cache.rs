@@ -0,0 +1,197 @@
+use crate::Params;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// 64-bit FNV-1a. Unlike `std::hash`, its output is fixed across Rust versions and platforms,
+/// so keys can be stored between builds.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    /// Writes the length first so that adjacent values can't run into each other.
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.write(&(bytes.len() as u64).to_le_bytes());
+        self.write(bytes);
+    }
+
+    fn write_option<T>(&mut self, value: &Option<T>, write: impl FnOnce(&mut Self, &T)) {
+        match value {
+            Some(value) => {
+                self.write(&[1]);
+                write(self, value);
+            }
+            None => self.write(&[0]),
+        }
+    }
+
+    fn write_string(&mut self, value: &Option<String>) {
+        self.write_option(value, |h, v| h.write_bytes(v.as_bytes()));
+    }
+}
+
+pub(crate) fn cache_key(ase_bytes: &[u8], args: &Params) -> String {
+    // destructured without `..` so that adding a field to Params fails to compile until it is
+    // hashed here too
+    let Params {
+        file_path,
+        copyright,
+        family,
+        subfamily,
+        font_version,
+        font_weight,
+        glyph_width,
+        glyph_height,
+        trim,
+        trim_pad,
+        line_gap,
+        spacing,
+        baseline,
+        underline_position,
+        underline_thickness,
+        use_tags,
+        config,
+        web_optimized,
+        variable,
+        pixel_size,
+        margin_top,
+        margin_bottom,
+        mirror,
+        mirror_pua,
+        family_class,
+        cap_height_reference,
+        downscale,
+        name_ids,
+        device_metrics,
+        italic_angle,
+        max_points,
+        bleed,
+        clamp_trim_scale,
+        max_weight,
+        keep_trailing_space,
+        emit_blank_glyphs,
+        units_per_em,
+        corner_radius,
+        created_timestamp,
+        ascent,
+        descent,
+        kerning,
+        composite_threshold,
+        color,
+        embed_bitmaps,
+        space_width,
+        advances,
+        charset,
+        alpha_threshold,
+        include_hidden,
+        cell_margin,
+        vendor_id,
+        embedding,
+        postscript_name,
+        share_glyphs,
+        synthetic_bold,
+        localized_names,
+    } = args;
+
+    let mut h = Fnv1a(FNV_OFFSET_BASIS);
+    // the output can change between releases even if the inputs don't
+    h.write_bytes(env!("CARGO_PKG_VERSION").as_bytes());
+    h.write_bytes(ase_bytes);
+    h.write_string(file_path);
+    h.write_string(copyright);
+    h.write_string(family);
+    h.write_string(subfamily);
+    h.write_string(font_version);
+    h.write_option(font_weight, |h, v| h.write(&v.to_le_bytes()));
+    h.write_option(glyph_width, |h, v| h.write(&v.to_le_bytes()));
+    h.write_option(glyph_height, |h, v| h.write(&v.to_le_bytes()));
+    h.write_option(trim, |h, v| h.write(&[*v as u8]));
+    h.write_option(trim_pad, |h, v| h.write(&v.to_le_bytes()));
+    h.write_option(line_gap, |h, v| h.write(&v.to_le_bytes()));
+    h.write_option(spacing, |h, v| h.write(&v.to_le_bytes()));
+    h.write_option(baseline, |h, v| h.write(&v.to_le_bytes()));
+    h.write_option(underline_position, |h, v| h.write(&v.to_le_bytes()));
+    h.write_option(underline_thickness, |h, v| h.write(&v.to_le_bytes()));
+    h.write_option(use_tags, |h, v| h.write(&[*v as u8]));
+    h.write_string(config);
+    h.write_option(web_optimized, |h, v| h.write(&[*v as u8]));
+    h.write_option(variable, |h, v| h.write(&[*v as u8]));
+    h.write_option(pixel_size, |h, v| h.write(&v.to_le_bytes()));
+    h.write_option(margin_top, |h, v| h.write(&v.to_le_bytes()));
+    h.write_option(margin_bottom, |h, v| h.write(&v.to_le_bytes()));
+    h.write_option(mirror, |h, v| h.write(&[*v as u8]));
+    h.write_option(mirror_pua, |h, v| h.write(&[*v as u8]));
+    h.write_option(family_class, |h, v| h.write(&v.to_le_bytes()));
+    h.write_option(cap_height_reference, |h, v| h.write(&v.to_le_bytes()));
+    h.write_option(downscale, |h, v| h.write(&v.to_le_bytes()));
+    h.write_option(name_ids, |h, v| {
+        h.write(&(v.len() as u64).to_le_bytes());
+        for id in v {
+            h.write(&id.to_le_bytes());
+        }
+    });
+    h.write_option(device_metrics, |h, v| h.write_bytes(v));
+    h.write_option(italic_angle, |h, v| h.write(&v.to_le_bytes()));
+    h.write_option(max_points, |h, v| h.write(&v.to_le_bytes()));
+    h.write_option(bleed, |h, v| h.write(&v.to_le_bytes()));
+    h.write_option(clamp_trim_scale, |h, v| h.write(&[*v as u8]));
+    h.write_option(max_weight, |h, v| h.write(&v.to_le_bytes()));
+    h.write_option(keep_trailing_space, |h, v| h.write(&[*v as u8]));
+    h.write_option(emit_blank_glyphs, |h, v| h.write(&[*v as u8]));
+    h.write_option(units_per_em, |h, v| h.write(&v.to_le_bytes()));
+    h.write_option(corner_radius, |h, v| h.write(&v.to_le_bytes()));
+    h.write_option(created_timestamp, |h, v| h.write(&v.to_le_bytes()));
+    h.write_option(ascent, |h, v| h.write(&v.to_le_bytes()));
+    h.write_option(descent, |h, v| h.write(&v.to_le_bytes()));
+    h.write_string(kerning);
+    h.write_option(composite_threshold, |h, v| h.write(&v.to_le_bytes()));
+    h.write_option(color, |h, v| h.write(&[*v as u8]));
+    h.write_option(embed_bitmaps, |h, v| h.write(&[*v as u8]));
+    h.write_option(space_width, |h, v| h.write(&v.to_le_bytes()));
+    h.write_string(advances);
+    h.write_string(charset);
+    h.write_option(alpha_threshold, |h, v| h.write(&v.to_le_bytes()));
+    h.write_option(include_hidden, |h, v| h.write(&[*v as u8]));
+    h.write_option(cell_margin, |h, v| h.write(&v.to_le_bytes()));
+    h.write_string(vendor_id);
+    h.write_option(embedding, |h, v| h.write(&[*v as u8]));
+    h.write_string(postscript_name);
+    h.write_option(share_glyphs, |h, v| h.write(&[*v as u8]));
+    h.write_option(synthetic_bold, |h, v| h.write(&v.to_le_bytes()));
+    h.write_option(localized_names, |h, v| {
+        // the map has no order of its own, so the names are hashed by language
+        let mut names: Vec<_> = v.iter().collect();
+        names.sort_by_key(|&(language, _)| *language);
+        h.write(&(names.len() as u64).to_le_bytes());
+        for (language, name) in names {
+            h.write(&language.to_le_bytes());
+            h.write_bytes(name.family.as_bytes());
+            h.write_string(&name.subfamily);
+        }
+    });
+
+    format!("{:016x}", h.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fnv1a_reference_values() {
+        let hash = |bytes: &[u8]| {
+            let mut h = Fnv1a(FNV_OFFSET_BASIS);
+            h.write(bytes);
+            h.0
+        };
+        assert_eq!(hash(b""), 0xcbf29ce484222325);
+        assert_eq!(hash(b"a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(hash(b"foobar"), 0x85944171f73967e8);
+    }
+}
@@ -0,0 +1,108 @@
+use std::ops::RangeInclusive;
+
+/// Letters that only some of the code pages of the OS/2 table have, and the bit of the code page
+/// range they stand for. The first 32 bits are in the first field and the rest in the second.
+const CODE_PAGES: &[(u8, &[RangeInclusive<u32>])] = &[
+    // Latin 1 (1252), which a font of plain ASCII is offered for as well
+    (0, &[0x0021..=0x007E, 0x00C0..=0x00FF]),
+    // Latin 2: Eastern Europe (1250)
+    (
+        1,
+        &[
+            0x0102..=0x0103, // Ă ă
+            0x010C..=0x010D, // Č č
+            0x0141..=0x0142, // Ł ł
+            0x0150..=0x0151, // Ő ő
+            0x0158..=0x0159, // Ř ř
+            0x015A..=0x015B, // Ś ś
+            0x0162..=0x0163, // Ţ ţ
+            0x0170..=0x0171, // Ű ű
+            0x0179..=0x017C, // Ź ź Ż ż
+        ],
+    ),
+    // Cyrillic (1251)
+    (2, &[0x0410..=0x044F]),
+    // Greek (1253)
+    (3, &[0x0391..=0x03C9]),
+    // Turkish (1254)
+    (
+        4,
+        &[
+            0x011E..=0x011F, // Ğ ğ
+            0x0130..=0x0131, // İ ı
+            0x015E..=0x015F, // Ş ş
+        ],
+    ),
+    // Hebrew (1255)
+    (5, &[0x05D0..=0x05EA]),
+    // Arabic (1256)
+    (6, &[0x0621..=0x064A]),
+    // Windows Baltic (1257)
+    (
+        7,
+        &[
+            0x0100..=0x0101, // Ā ā
+            0x0112..=0x0113, // Ē ē
+            0x0116..=0x0117, // Ė ė
+            0x0122..=0x0123, // Ģ ģ
+            0x012A..=0x012B, // Ī ī
+            0x012E..=0x012F, // Į į
+            0x0136..=0x0137, // Ķ ķ
+            0x013B..=0x013C, // Ļ ļ
+            0x0145..=0x0146, // Ņ ņ
+            0x014C..=0x014D, // Ō ō
+            0x016A..=0x016B, // Ū ū
+            0x0172..=0x0173, // Ų ų
+        ],
+    ),
+    // Vietnamese (1258)
+    (
+        8,
+        &[
+            0x01A0..=0x01A1, // Ơ ơ
+            0x01AF..=0x01B0, // Ư ư
+            0x20AB..=0x20AB, // ₫
+        ],
+    ),
+    // Thai (874)
+    (16, &[0x0E01..=0x0E5B]),
+    // JIS/Japan (932)
+    (17, &[0x3041..=0x30FF]),
+    // Chinese: Simplified chars--PRC and Singapore (936)
+    (
+        18,
+        &[
+            0x4E2A..=0x4E2A, // 个
+            0x4EEC..=0x4EEC, // 们
+            0x8FD9..=0x8FD9, // 这
+        ],
+    ),
+    // Korean Wansung (949)
+    (19, &[0xAC00..=0xD7A3]),
+    // Chinese: Traditional chars--Taiwan and Hong Kong (950)
+    (
+        20,
+        &[
+            0x3105..=0x312F, // Bopomofo
+            0x500B..=0x500B, // 個
+            0x5011..=0x5011, // 們
+            0x9019..=0x9019, // 這
+        ],
+    ),
+    // IBM PC US (437), by the double lines of its box drawing
+    (63, &[0x2550..=0x256C]),
+];
+
+/// The two code page range fields of the OS/2 table, with a bit set for each code page that the
+/// codepoints are written in.
+pub(crate) fn code_page_ranges(codepoints: impl IntoIterator<Item = u32>) -> [u32; 2] {
+    let mut ranges = [0; 2];
+    for codepoint in codepoints {
+        for &(bit, letters) in CODE_PAGES {
+            if letters.iter().any(|letters| letters.contains(&codepoint)) {
+                ranges[bit as usize / 32] |= 1 << (bit % 32);
+            }
+        }
+    }
+    ranges
+}
@@ -0,0 +1,285 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Deserialize;
+
+use crate::{Error, LocalizedName};
+
+/// Settings loaded from a TOML configuration file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Config {
+    /// Per-glyph overrides keyed by codepoint, e.g. `[glyphs."U+0041"]`.
+    #[serde(default)]
+    glyphs: HashMap<String, GlyphOverride>,
+    /// Settings for the glyphs that are always added, e.g. `[reserved.notdef]`.
+    #[serde(default)]
+    pub reserved: Reserved,
+    /// Parts of the sheet with their own cell size, e.g. `[[regions]]`.
+    #[serde(default)]
+    regions: Vec<RegionEntry>,
+    /// Codepoints that share the glyph of another codepoint, e.g. `"U+002D" = ["U+2212"]`.
+    #[serde(default)]
+    aliases: HashMap<String, Vec<String>>,
+    /// The names of the font in other languages, e.g. `[[names]]` with `language = 0x0411`.
+    #[serde(default)]
+    names: Vec<NameEntry>,
+    /// Palettes of a color font after the one drawn in the colors of the art, e.g. `[[palettes]]`.
+    #[serde(default)]
+    palettes: Vec<PaletteEntry>,
+}
+
+/// The family and subfamily names of the font in one language, e.g. `language = 0x0411`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NameEntry {
+    /// The Windows language ID, e.g. `0x0411` for Japanese.
+    language: u16,
+    family: String,
+    subfamily: Option<String>,
+}
+
+/// The red, green and blue of a color.
+pub(crate) type Rgb = [u8; 3];
+
+/// A palette that draws some colors of the art in other colors, e.g.
+/// `colors = { "#FF0000" = "#00FFFF" }`. The colors it leaves out are drawn as they are.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PaletteEntry {
+    colors: HashMap<String, String>,
+}
+
+/// A rectangle of the sheet split into cells of its own size, numbered from `codepoint`.
+/// Lengths are in pixels.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RegionEntry {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    glyph_width: u32,
+    glyph_height: u32,
+    codepoint: String,
+}
+
+/// A region with its codepoint parsed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub glyph_width: u32,
+    pub glyph_height: u32,
+    pub codepoint: u32,
+}
+
+/// The `.notdef`, null and space glyphs, which aren't drawn in the Aseprite file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Reserved {
+    #[serde(default)]
+    pub notdef: ReservedGlyph,
+    #[serde(default)]
+    pub null: ReservedGlyph,
+    #[serde(default)]
+    pub space: ReservedGlyph,
+}
+
+/// Lengths are in pixels.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ReservedGlyph {
+    pub advance: Option<u32>,
+    /// Draws a box the width of the advance, shown in place of missing characters. Only allowed
+    /// for `.notdef`.
+    pub visible: Option<bool>,
+}
+
+/// Overrides for the automatically computed values of a single glyph.
+/// Lengths are in pixels.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct GlyphOverride {
+    pub advance: Option<u32>,
+    pub left_bearing: Option<i32>,
+    /// Moves the glyph up (positive) or down (negative) relative to the baseline.
+    pub baseline_offset: Option<i32>,
+    pub name: Option<String>,
+    /// Gives the glyph no advance so it stacks on the glyph before it. The Combining Diacritical
+    /// Marks block is combining by default.
+    pub combining: Option<bool>,
+}
+
+impl Config {
+    pub fn parse(source: &str) -> Result<Config, Error> {
+        toml::from_str(source).map_err(|e| Error::new(format!("Invalid config: {}", e)))
+    }
+
+    pub fn reserved_glyphs(&self) -> Result<[(&'static str, &ReservedGlyph); 3], Error> {
+        let reserved = &self.reserved;
+        for (name, glyph) in [("null", &reserved.null), ("space", &reserved.space)] {
+            if glyph.visible.is_some() {
+                return Err(Error::new(format!(
+                    "Invalid config: only the notdef glyph can be made visible, but reserved.{} sets it.",
+                    name
+                )));
+            }
+        }
+        Ok([
+            (".notdef", &reserved.notdef),
+            ("null", &reserved.null),
+            ("space", &reserved.space),
+        ])
+    }
+
+    /// Returns the regions after checking that they are well formed and don't overlap.
+    pub fn regions(&self) -> Result<Vec<Region>, Error> {
+        let mut regions: Vec<Region> = Vec::new();
+        for (i, entry) in self.regions.iter().enumerate() {
+            let codepoint = parse_codepoint_key(&entry.codepoint).ok_or_else(|| {
+                Error::new(format!(
+                    "Invalid config: '{}' is not a codepoint. Region codepoints must be written as \"U+XXXX\".",
+                    entry.codepoint
+                ))
+            })?;
+            if entry.glyph_width == 0
+                || entry.glyph_height == 0
+                || entry.width == 0
+                || entry.height == 0
+                || !entry.width.is_multiple_of(entry.glyph_width)
+                || !entry.height.is_multiple_of(entry.glyph_height)
+            {
+                return Err(Error::new(format!(
+                    "Invalid config: the width and height of region {} must be non-zero multiples of its glyph_width and glyph_height.",
+                    i
+                )));
+            }
+            let region = Region {
+                x: entry.x,
+                y: entry.y,
+                width: entry.width,
+                height: entry.height,
+                glyph_width: entry.glyph_width,
+                glyph_height: entry.glyph_height,
+                codepoint,
+            };
+            if let Some(j) = regions.iter().position(|other| other.overlaps(&region)) {
+                return Err(Error::new(format!(
+                    "Invalid config: regions {} and {} overlap.",
+                    j, i
+                )));
+            }
+            regions.push(region);
+        }
+        Ok(regions)
+    }
+
+    pub fn glyph_overrides(&self) -> Result<HashMap<u32, &GlyphOverride>, Error> {
+        let mut overrides = HashMap::new();
+        for (key, glyph) in &self.glyphs {
+            let codepoint = parse_codepoint_key(key).ok_or_else(|| {
+                Error::new(format!(
+                    "Invalid config: '{}' is not a codepoint. Glyph keys must be written as \"U+XXXX\".",
+                    key
+                ))
+            })?;
+            overrides.insert(codepoint, glyph);
+        }
+        Ok(overrides)
+    }
+
+    /// Returns the aliases of each codepoint, ordered by the codepoint whose glyph they share.
+    pub fn aliases(&self) -> Result<Vec<(u32, Vec<u32>)>, Error> {
+        let parse = |key: &String| {
+            parse_codepoint_key(key).ok_or_else(|| {
+                Error::new(format!(
+                    "Invalid config: '{}' is not a codepoint. Aliases must be written as \"U+XXXX\".",
+                    key
+                ))
+            })
+        };
+        let mut aliases = Vec::new();
+        for (key, codepoints) in &self.aliases {
+            let codepoints = codepoints
+                .iter()
+                .map(parse)
+                .collect::<Result<Vec<_>, _>>()?;
+            aliases.push((parse(key)?, codepoints));
+        }
+        aliases.sort();
+        Ok(aliases)
+    }
+
+    /// Returns the localized names keyed by language, after checking that each language is named
+    /// once and isn't English, which takes its names from the parameters.
+    pub fn localized_names(&self) -> Result<BTreeMap<u16, LocalizedName>, Error> {
+        let mut names = BTreeMap::new();
+        for entry in &self.names {
+            if entry.language == 0x0409 {
+                return Err(Error::new(
+                    "Invalid config: the names of language 0x0409 are the English ones, which are set by the family and subfamily.".to_string(),
+                ));
+            }
+            let name = LocalizedName {
+                family: entry.family.clone(),
+                subfamily: entry.subfamily.clone(),
+            };
+            if names.insert(entry.language, name).is_some() {
+                return Err(Error::new(format!(
+                    "Invalid config: language 0x{:04X} is named more than once.",
+                    entry.language
+                )));
+            }
+        }
+        Ok(names)
+    }
+
+    /// Returns the colors that each extra palette replaces, with the color it draws them in.
+    pub fn palettes(&self) -> Result<Vec<HashMap<Rgb, Rgb>>, Error> {
+        let parse = |color: &String| {
+            parse_color(color).ok_or_else(|| {
+                Error::new(format!(
+                    "Invalid config: '{}' is not a color. Palette colors must be written as \"#RRGGBB\".",
+                    color
+                ))
+            })
+        };
+        let mut palettes = Vec::new();
+        for entry in &self.palettes {
+            let mut colors = HashMap::new();
+            for (from, to) in &entry.colors {
+                colors.insert(parse(from)?, parse(to)?);
+            }
+            palettes.push(colors);
+        }
+        Ok(palettes)
+    }
+}
+
+impl Region {
+    fn overlaps(&self, other: &Region) -> bool {
+        self.x < other.x + other.width
+            && other.x < self.x + self.width
+            && self.y < other.y + other.height
+            && other.y < self.y + self.height
+    }
+}
+
+fn parse_codepoint_key(key: &str) -> Option<u32> {
+    let hex = key.strip_prefix("U+").or_else(|| key.strip_prefix("u+"))?;
+    if hex.is_empty() || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    u32::from_str_radix(hex, 16).ok()
+}
+
+fn parse_color(color: &str) -> Option<Rgb> {
+    let hex = color.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
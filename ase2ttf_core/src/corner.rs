@@ -0,0 +1,42 @@
+use kurbo::{BezPath, Point};
+
+use crate::edge::drop_collinear;
+use crate::simplify::contours;
+
+/// Replaces each corner of a traced outline with a quadratic curve that starts and ends `radius`
+/// font units away from the corner along its edges, keeping the direction of every contour. The
+/// radius is limited to half of each edge, so that the curves of neighboring corners meet at most.
+/// Returns the rounded outline with its point count.
+pub(crate) fn round_corners(path: &BezPath, radius: f64) -> (BezPath, u16) {
+    let mut rounded = BezPath::new();
+    let mut point_count: usize = 0;
+    for contour in contours(path) {
+        let corners: Vec<Point> = drop_collinear(contour)
+            .into_iter()
+            .map(Point::from)
+            .collect();
+        let n = corners.len();
+        if n < 3 {
+            continue;
+        }
+        // every corner becomes the two ends of a curve and its control point, so that the
+        // contours of two masters still match after rounding
+        for i in 0..n {
+            let (prev, corner, next) = (corners[(i + n - 1) % n], corners[i], corners[(i + 1) % n]);
+            let radius = radius
+                .min((prev - corner).hypot() / 2.0)
+                .min((next - corner).hypot() / 2.0);
+            let start = corner + (prev - corner).normalize() * radius;
+            let end = corner + (next - corner).normalize() * radius;
+            if i == 0 {
+                rounded.move_to(start);
+            } else {
+                rounded.line_to(start);
+            }
+            rounded.quad_to(corner, end);
+        }
+        rounded.close_path();
+        point_count += 3 * n;
+    }
+    (rounded, point_count.min(u16::MAX as usize) as u16)
+}
@@ -0,0 +1,81 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+
+use asefile::AsepriteFile;
+
+use crate::config::Config;
+use crate::font::drawn_codepoints;
+use crate::{Error, Params};
+
+/// A codepoint that is drawn in some frames of its layer but left blank in others.
+#[derive(Debug, PartialEq)]
+pub struct CoverageMismatch {
+    pub codepoint: u32,
+    pub present_frames: Vec<u32>,
+    pub missing_frames: Vec<u32>,
+}
+
+impl Display for CoverageMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let join = |frames: &[u32]| {
+            let list = frames
+                .iter()
+                .map(|frame| frame.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            if frames.len() == 1 {
+                format!("frame {}", list)
+            } else {
+                format!("frames {}", list)
+            }
+        };
+        write!(
+            f,
+            "U+{:04X} is drawn in {} but missing in {}",
+            self.codepoint,
+            join(&self.present_frames),
+            join(&self.missing_frames)
+        )
+    }
+}
+
+pub(crate) fn check_coverage(
+    ase_bytes: &[u8],
+    args: &Params,
+) -> Result<Vec<CoverageMismatch>, Error> {
+    let ase = AsepriteFile::read(ase_bytes).map_err(Error::AseRead)?;
+
+    // with tags, frames hold different codepoints rather than variants of the same ones
+    if args.use_tags.unwrap_or(false) || ase.num_frames() < 2 {
+        return Ok(vec![]);
+    }
+
+    let config = match &args.config {
+        Some(source) => Config::parse(source)?,
+        None => Config::default(),
+    };
+    let regions = config.regions()?;
+
+    // frames in which each codepoint has ink, over the same cells that glyphs are read from
+    let mut coverage: BTreeMap<u32, BTreeSet<u32>> = BTreeMap::new();
+    for frame in 0..ase.num_frames() {
+        for codepoint in drawn_codepoints(&ase, args, &regions, frame)? {
+            coverage.entry(codepoint).or_default().insert(frame);
+        }
+    }
+
+    let mut mismatches = vec![];
+    for (codepoint, frames) in coverage {
+        if frames.len() == ase.num_frames() as usize {
+            continue;
+        }
+        mismatches.push(CoverageMismatch {
+            codepoint,
+            present_frames: frames.iter().copied().collect(),
+            missing_frames: (0..ase.num_frames())
+                .filter(|frame| !frames.contains(frame))
+                .collect(),
+        });
+    }
+    Ok(mismatches)
+}
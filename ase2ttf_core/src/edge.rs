@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 type Point = (usize, usize);
 type Line = (Point, Point);
@@ -44,9 +44,9 @@ impl UnionFind {
     }
 }
 
-fn group(grid: &[f64], width: usize, height: usize) -> HashMap<usize, Vec<usize>> {
+fn group(grid: &[f64], width: usize, height: usize) -> BTreeMap<usize, Vec<usize>> {
     if width == 0 || height == 0 {
-        return HashMap::new();
+        return BTreeMap::new();
     }
 
     let n_cells = width * height;
@@ -75,7 +75,8 @@ fn group(grid: &[f64], width: usize, height: usize) -> HashMap<usize, Vec<usize>
         }
     }
 
-    let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+    // ordered by root, so that the same grid is always traced the same way
+    let mut groups: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
     for i in 0..n_cells {
         if grid[i] > 0.0 {
             let root = uf.find(i);
@@ -86,9 +87,13 @@ fn group(grid: &[f64], width: usize, height: usize) -> HashMap<usize, Vec<usize>
     groups
 }
 
-pub fn get_edges(grid: &[f64], width: usize, height: usize) -> HashMap<usize, Vec<Line>> {
+/// Returns the boundary segments of each group of ink pixels, keeping their direction. Every
+/// segment runs counter-clockwise around the pixel it belongs to (as seen on the sprite, with y
+/// pointing down), so outer contours run counter-clockwise and holes run clockwise. This gives
+/// contours a consistent winding without comparing them with each other.
+pub fn get_directed_edges(grid: &[f64], width: usize, height: usize) -> BTreeMap<usize, Vec<Line>> {
     let group_map = group(&grid, width, height);
-    let mut group_boundaries: HashMap<usize, Vec<Line>> = HashMap::new();
+    let mut group_boundaries: BTreeMap<usize, Vec<Line>> = BTreeMap::new();
 
     for (root_id, indices) in group_map.iter() {
         let mut lines: Vec<Line> = Vec::new();
@@ -99,7 +104,7 @@ pub fn get_edges(grid: &[f64], width: usize, height: usize) -> HashMap<usize, Ve
 
             // top
             if y == 0 || grid[idx - width] == 0.0 {
-                lines.push(((x, y), (x + 1, y)));
+                lines.push(((x + 1, y), (x, y)));
             }
             // bottom
             if y == height - 1 || grid[idx + width] == 0.0 {
@@ -111,28 +116,17 @@ pub fn get_edges(grid: &[f64], width: usize, height: usize) -> HashMap<usize, Ve
             }
             // right
             if x == width - 1 || grid[idx + 1] == 0.0 {
-                lines.push(((x + 1, y), (x + 1, y + 1)));
+                lines.push(((x + 1, y + 1), (x + 1, y)));
             }
         }
 
-        // remove duplicate boundary segments
-        let mut unique_boundaries = HashSet::new();
-        for line in lines {
-            let normalized_line = if line.0 <= line.1 {
-                line
-            } else {
-                (line.1, line.0)
-            };
-            unique_boundaries.insert(normalized_line);
-        }
-
-        group_boundaries.insert(*root_id, unique_boundaries.into_iter().collect());
+        group_boundaries.insert(*root_id, lines);
     }
 
     let mut root_to_id: HashMap<usize, usize> = HashMap::new();
     let mut next_id = 1;
 
-    let mut result: HashMap<usize, Vec<Line>> = HashMap::new();
+    let mut result: BTreeMap<usize, Vec<Line>> = BTreeMap::new();
 
     for (root, boundaries) in group_boundaries {
         let entry = root_to_id.entry(root).or_insert_with(|| {
@@ -146,6 +140,91 @@ pub fn get_edges(grid: &[f64], width: usize, height: usize) -> HashMap<usize, Ve
     result
 }
 
+/// Returns the boundary segments of each group of ink pixels, with each segment running from
+/// its smaller point to its larger one.
+pub fn get_edges(grid: &[f64], width: usize, height: usize) -> BTreeMap<usize, Vec<Line>> {
+    get_directed_edges(grid, width, height)
+        .into_iter()
+        .map(|(id, lines)| {
+            // remove duplicate boundary segments
+            let mut unique_boundaries = HashSet::new();
+            for line in lines {
+                let normalized_line = if line.0 <= line.1 {
+                    line
+                } else {
+                    (line.1, line.0)
+                };
+                unique_boundaries.insert(normalized_line);
+            }
+            let mut lines: Vec<Line> = unique_boundaries.into_iter().collect();
+            lines.sort();
+            (id, lines)
+        })
+        .collect()
+}
+
+/// Returns the outline of a completely filled grid directly, since solid cells don't need the
+/// general tracer. Returns `None` if any cell is empty.
+pub fn filled_rect_path(grid: &[f64], width: usize, height: usize) -> Option<Vec<Point>> {
+    if width == 0 || height == 0 || grid.iter().any(|&v| v <= 0.0) {
+        return None;
+    }
+    Some(vec![
+        (0, 0),
+        (0, height),
+        (width, height),
+        (width, 0),
+        (0, 0),
+    ])
+}
+
+/// Combines an outline grid with a fill grid. Fill cells are kept only where they are enclosed
+/// by the outline, so the result traces as the outline's outer contour with the fill inside it.
+pub fn fill_interior(outline: &[f64], fill: &[f64], width: usize, height: usize) -> Vec<f64> {
+    // flood the empty cells reachable from the border; everything else is inside the outline
+    let mut outside = vec![false; width * height];
+    let mut stack: Vec<usize> = Vec::new();
+    for x in 0..width {
+        stack.push(x);
+        stack.push(x + (height - 1) * width);
+    }
+    for y in 0..height {
+        stack.push(y * width);
+        stack.push(width - 1 + y * width);
+    }
+    while let Some(idx) = stack.pop() {
+        if outside[idx] || outline[idx] > 0.0 {
+            continue;
+        }
+        outside[idx] = true;
+        let (x, y) = (idx % width, idx / width);
+        if x > 0 {
+            stack.push(idx - 1);
+        }
+        if x + 1 < width {
+            stack.push(idx + 1);
+        }
+        if y > 0 {
+            stack.push(idx - width);
+        }
+        if y + 1 < height {
+            stack.push(idx + width);
+        }
+    }
+
+    (0..width * height)
+        .map(|i| {
+            if outline[i] > 0.0 {
+                outline[i]
+            } else if outside[i] {
+                0.0
+            } else {
+                fill[i]
+            }
+        })
+        .collect()
+}
+
 pub fn edges_to_paths(edges: &Vec<Line>) -> Vec<Vec<Point>> {
     let mut point_to_edges: HashMap<Point, Vec<Point>> = HashMap::new();
     let mut edge_set: HashSet<Line> = HashSet::new();
@@ -169,61 +248,86 @@ pub fn edges_to_paths(edges: &Vec<Line>) -> Vec<Vec<Point>> {
             continue;
         }
         let mut path = Vec::new();
+        let mut prev = start;
         let mut curr = end;
         path.push(start);
         used.insert(key);
         while curr != start {
             path.push(curr);
             let neighbors = &point_to_edges[&curr];
-            let mut found = false;
-            for &next in neighbors {
-                let k = if curr <= next {
-                    (curr, next)
+            // where diagonal pixels pinch the outline, the contour turns the corner instead of
+            // crossing over to the other side, so that contours only touch at the pinch
+            let straight = |next: &Point| {
+                (
+                    next.0 as isize - curr.0 as isize,
+                    next.1 as isize - curr.1 as isize,
+                ) == (
+                    curr.0 as isize - prev.0 as isize,
+                    curr.1 as isize - prev.1 as isize,
+                )
+            };
+            let unused = |next: &&Point| {
+                let k = if curr <= **next {
+                    (curr, **next)
                 } else {
-                    (next, curr)
+                    (**next, curr)
                 };
-                if !used.contains(&k) && edge_set.contains(&k) {
-                    used.insert(k);
-                    curr = next;
-                    found = true;
-                    break;
-                }
-            }
-            if !found {
+                !used.contains(&k) && edge_set.contains(&k)
+            };
+            let next = if neighbors.len() > 2 {
+                neighbors.iter().filter(unused).find(|next| !straight(next))
+            } else {
+                neighbors.iter().find(unused)
+            };
+            let Some(&next) = next else {
                 break; // not closed
-            }
+            };
+            used.insert(if curr <= next {
+                (curr, next)
+            } else {
+                (next, curr)
+            });
+            prev = curr;
+            curr = next;
         }
+        path.push(start);
 
-        // separate inner loop
-        let mut visited_point = HashMap::new();
-        let mut l = path.len();
-        let mut i = 0;
-        while i < l - 1 {
-            let insert_ret = visited_point.insert(path[i], i);
-            if let Some(p) = insert_ret {
-                paths.push(path.drain(p..i).rev().collect());
-                l = path.len();
+        // a contour that passes a pinch twice is split there into simple contours
+        let mut open: Vec<Point> = Vec::new();
+        let mut positions: HashMap<Point, usize> = HashMap::new();
+        for point in path {
+            let Some(&position) = positions.get(&point) else {
+                positions.insert(point, open.len());
+                open.push(point);
+                continue;
+            };
+            let mut contour: Vec<Point> = open.drain(position..).collect();
+            for p in &contour[1..] {
+                positions.remove(p);
+            }
+            open.push(point);
+            // only closed paths
+            if contour.len() > 2 {
+                contour.push(point);
+                paths.push(contour);
             }
-            i += 1;
-        }
-
-        // only closed paths
-        if path.len() > 2 && path[0] == *path.last().unwrap() {
-            paths.push(path);
-        } else if path.len() > 2 {
-            path.push(path[0]);
-            paths.push(path);
         }
     }
 
     let n = paths.len();
+    // most contours are far apart, so a point is first checked against the bounds of a contour
+    // before casting a ray across all of its edges
+    let bounds: Vec<Bounds> = paths.iter().map(|path| bounds(path)).collect();
     for i in 0..n {
+        // contours only touch at pinches, so a point inside of one is inside of every contour
+        // that contains it, and its nesting depth decides the winding
+        let point = interior_point(&paths[i]);
         let mut inside_count = 0;
         for j in 0..n {
-            if i == j || paths[j].is_empty() {
+            if i == j || paths[j].is_empty() || !bounds[j].contains(point) {
                 continue;
             }
-            if point_in_polygon(paths[i][0], &paths[j]) {
+            if point_in_polygon(point, &paths[j]) {
                 inside_count += 1;
             }
         }
@@ -242,22 +346,158 @@ pub fn edges_to_paths(edges: &Vec<Line>) -> Vec<Vec<Point>> {
     paths
 }
 
-fn point_in_polygon(point: Point, polygon: &[Point]) -> bool {
-    let (x, y) = (point.0 as isize, point.1 as isize);
+/// Cells with at least this much coverage are inside of the contours of `trace_coverage`.
+const COVERAGE_THRESHOLD: f64 = 0.5;
+
+/// Traces a grid of fractional coverage. Unlike `get_edges`, the contours don't follow the cell
+/// edges: their points are interpolated between the cell centers, so partly covered cells pull
+/// the contour in or out and steps become diagonals. The contours are wound like the ones of
+/// `edges_to_paths`.
+pub fn trace_coverage(grid: &[f64], width: usize, height: usize) -> Vec<Vec<(f64, f64)>> {
+    // surrounded by a blank border, so that every contour is closed
+    let value = |x: usize, y: usize| {
+        if x == 0 || y == 0 || x > width || y > height {
+            0.0
+        } else {
+            grid[(x - 1) + (y - 1) * width]
+        }
+    };
+    let inside = |(x, y): (usize, usize)| value(x, y) >= COVERAGE_THRESHOLD;
+
+    // a crossing is on the side from the sample at (x, y) to the one to its right, or below it
+    // when vertical
+    type Crossing = (usize, usize, bool);
+    let position = |(x, y, vertical): Crossing| {
+        let (x1, y1) = if vertical { (x, y + 1) } else { (x + 1, y) };
+        let (v0, v1) = (value(x, y), value(x1, y1));
+        let t = (COVERAGE_THRESHOLD - v0) / (v1 - v0);
+        // samples are at the cell centers, and the border is one cell outside of the grid
+        (
+            x as f64 - 0.5 + t * (x1 - x) as f64,
+            y as f64 - 0.5 + t * (y1 - y) as f64,
+        )
+    };
+
+    let mut next: BTreeMap<Crossing, Crossing> = BTreeMap::new();
+    for y in 0..=height {
+        for x in 0..=width {
+            // the square between four samples, clockwise from the top left
+            let corners = [(x, y), (x + 1, y), (x + 1, y + 1), (x, y + 1)];
+            let sides = [
+                (x, y, false),
+                (x + 1, y, true),
+                (x, y + 1, false),
+                (x, y, true),
+            ];
+            let ink = corners.map(inside);
+            // contours enter through the sides going from blank to ink and leave through the
+            // others, which keeps the ink on their left
+            let entries: Vec<usize> = (0..4).filter(|&i| !ink[i] && ink[(i + 1) % 4]).collect();
+            let exits: Vec<usize> = (0..4).filter(|&i| ink[i] && !ink[(i + 1) % 4]).collect();
+            if entries.len() == 2 {
+                // a saddle, joined through the center if the four samples cover it on average
+                let center = corners.iter().map(|&(x, y)| value(x, y)).sum::<f64>() / 4.0;
+                for entry in entries {
+                    let exit = if center >= COVERAGE_THRESHOLD {
+                        (entry + 3) % 4
+                    } else {
+                        (entry + 1) % 4
+                    };
+                    next.insert(sides[entry], sides[exit]);
+                }
+            } else if let (Some(&entry), Some(&exit)) = (entries.first(), exits.first()) {
+                next.insert(sides[entry], sides[exit]);
+            }
+        }
+    }
+
+    let mut contours = Vec::new();
+    while let Some((&start, _)) = next.first_key_value() {
+        let mut contour = Vec::new();
+        let mut crossing = start;
+        while let Some(following) = next.remove(&crossing) {
+            contour.push(position(crossing));
+            crossing = following;
+        }
+        let contour = drop_collinear(contour);
+        if contour.len() > 2 {
+            contours.push(contour);
+        }
+    }
+    contours
+}
+
+/// Removes the points of a closed contour that lie on the line between their neighbors.
+pub(crate) fn drop_collinear(mut points: Vec<(f64, f64)>) -> Vec<(f64, f64)> {
+    let mut i = 0;
+    while points.len() > 2 && i < points.len() {
+        let n = points.len();
+        let (a, b, c) = (points[(i + n - 1) % n], points[i], points[(i + 1) % n]);
+        let cross = (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0);
+        if cross.abs() < 1e-9 {
+            points.remove(i);
+            // the previous point may have become collinear
+            i = i.saturating_sub(1);
+        } else {
+            i += 1;
+        }
+    }
+    points
+}
+
+struct Bounds {
+    min: Point,
+    max: Point,
+}
+
+impl Bounds {
+    fn contains(&self, point: (f64, f64)) -> bool {
+        let (x, y) = point;
+        x > self.min.0 as f64
+            && x < self.max.0 as f64
+            && y > self.min.1 as f64
+            && y < self.max.1 as f64
+    }
+}
+
+fn bounds(path: &[Point]) -> Bounds {
+    let mut bounds = Bounds {
+        min: (usize::MAX, usize::MAX),
+        max: (0, 0),
+    };
+    for &(x, y) in path {
+        bounds.min = (bounds.min.0.min(x), bounds.min.1.min(y));
+        bounds.max = (bounds.max.0.max(x), bounds.max.1.max(y));
+    }
+    bounds
+}
+
+/// Returns a point a quarter of a pixel inside of the first edge of a contour. It lies off the
+/// grid lines, so it is never on the edge of another contour, where inside and outside tie.
+fn interior_point(path: &[Point]) -> (f64, f64) {
+    let (a, b) = (path[0], path[1]);
+    let (x, y) = ((a.0 + b.0) as f64 / 2.0, (a.1 + b.1) as f64 / 2.0);
+    // perpendicular to the edge, on one side or the other
+    let (dx, dy) = (
+        (b.1 as f64 - a.1 as f64) / 4.0,
+        (a.0 as f64 - b.0 as f64) / 4.0,
+    );
+    if point_in_polygon((x + dx, y + dy), path) {
+        (x + dx, y + dy)
+    } else {
+        (x - dx, y - dy)
+    }
+}
+
+fn point_in_polygon(point: (f64, f64), polygon: &[Point]) -> bool {
+    let (x, y) = point;
     let mut inside = false;
     let n = polygon.len();
     for i in 0..n {
-        let (x0, y0) = (polygon[i].0 as isize, polygon[i].1 as isize);
-        let (x1, y1) = (
-            polygon[(i + 1) % n].0 as isize,
-            polygon[(i + 1) % n].1 as isize,
-        );
+        let (x0, y0) = (polygon[i].0 as f64, polygon[i].1 as f64);
+        let (x1, y1) = (polygon[(i + 1) % n].0 as f64, polygon[(i + 1) % n].1 as f64);
         if (y0 > y) != (y1 > y) {
-            let denom = y1 - y0;
-            if denom == 0 {
-                continue;
-            }
-            let intersect_x = (x1 - x0) * (y - y0) / denom + x0;
+            let intersect_x = (x1 - x0) * (y - y0) / (y1 - y0) + x0;
             if x < intersect_x {
                 inside = !inside;
             }
@@ -313,4 +553,234 @@ mod tests {
         assert_eq!(areas.iter().filter(|&a| *a < 0.0).count(), 1);
         assert_eq!(areas.iter().filter(|&a| *a > 0.0).count(), 1);
     }
+
+    #[test]
+    fn filled_rect_matches_general_path() {
+        let (width, height) = (4, 3);
+        let grid = vec![1.0; width * height];
+
+        let rect = filled_rect_path(&grid, width, height).unwrap();
+        let boundaries = get_edges(&grid, width, height);
+        let paths = edges_to_paths(&Vec::from_iter(boundaries.into_values().flatten()));
+        assert_eq!(paths.len(), 1);
+
+        // the same points from the same start once straight runs are dropped, as in a glyph
+        let outline = |path: &[Point]| {
+            drop_collinear(path.iter().map(|&(x, y)| (x as f64, y as f64)).collect())
+        };
+        assert_eq!(outline(&rect), outline(&paths[0]));
+    }
+
+    #[test]
+    fn filled_rect_requires_every_cell() {
+        let mut grid = vec![1.0; 9];
+        grid[4] = 0.0;
+        assert_eq!(filled_rect_path(&grid, 3, 3), None);
+    }
+
+    #[test]
+    fn fill_interior_keeps_enclosed_fill() {
+        let outline = "
+-####-
+-#--#-
+-#--#-
+-####-
+";
+        let fill = "
+######
+######
+--#---
+######
+";
+        let parse = |src: &str| -> Vec<f64> {
+            src.trim()
+                .replace("\n", "")
+                .bytes()
+                .map(|x| if x == b'#' { 1.0 } else { 0.0 })
+                .collect()
+        };
+
+        let grid = fill_interior(&parse(outline), &parse(fill), 6, 4);
+        assert_eq!(grid, parse("-####-\n-####-\n-##-#-\n-####-"));
+
+        // the unfilled cell is left as a hole
+        let boundaries = get_edges(&grid, 6, 4);
+        let paths = edges_to_paths(&Vec::from_iter(boundaries.into_values().flatten()));
+        assert_eq!(paths.len(), 2);
+    }
+
+    fn trace(src: &str, width: usize, height: usize) -> Vec<Vec<Point>> {
+        let grid: Vec<f64> = src
+            .trim()
+            .replace("\n", "")
+            .bytes()
+            .map(|x| if x == b'#' { 1.0 } else { 0.0 })
+            .collect();
+        let boundaries = get_edges(&grid, width, height);
+        edges_to_paths(&Vec::from_iter(boundaries.into_values().flatten()))
+    }
+
+    #[test]
+    fn thin_ring_keeps_outer_contour_and_hole() {
+        let paths = trace("###\n#-#\n###", 3, 3);
+        assert_eq!(paths.len(), 2);
+
+        let mut areas: Vec<_> = paths.iter().map(|p| signed_area(p)).collect();
+        areas.sort_by(f64::total_cmp);
+        // the hole is wound opposite to the outer contour
+        assert_eq!(areas, [-9.0, 1.0]);
+        assert!(
+            paths
+                .iter()
+                .all(|p| p.len() >= 5 && p[0] == *p.last().unwrap())
+        );
+    }
+
+    #[test]
+    fn hole_touching_outline_at_a_corner() {
+        let paths = trace("###\n#-#\n##-", 3, 3);
+        assert_eq!(paths.len(), 2);
+
+        let mut areas: Vec<_> = paths.iter().map(|p| signed_area(p)).collect();
+        areas.sort_by(f64::total_cmp);
+        assert_eq!(areas, [-8.0, 1.0]);
+    }
+
+    #[test]
+    fn pinched_holes_are_split_into_simple_contours() {
+        // the holes touch each other and the outside at corners, where four edges meet
+        let paths = trace("###-\n##-#\n#-##\n###-", 4, 4);
+        assert_eq!(paths.len(), 3);
+        for path in &paths {
+            let points: HashSet<_> = path[..path.len() - 1].iter().collect();
+            assert_eq!(points.len(), path.len() - 1);
+        }
+
+        let mut areas: Vec<_> = paths.iter().map(|p| signed_area(p)).collect();
+        areas.sort_by(f64::total_cmp);
+        assert_eq!(areas, [-14.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn nested_rings_alternate_their_winding() {
+        let src = "
+#########
+#-------#
+#-#####-#
+#-#---#-#
+#-#-#-#-#
+#-#---#-#
+#-#####-#
+#-------#
+#########
+";
+        let paths = trace(src, 9, 9);
+        assert_eq!(paths.len(), 5);
+        let mut areas: Vec<_> = paths.iter().map(|p| signed_area(p)).collect();
+        areas.sort_by(|a, b| b.abs().total_cmp(&a.abs()));
+        assert_eq!(areas, [-81.0, 49.0, -25.0, 9.0, -1.0]);
+    }
+
+    #[test]
+    fn hole_with_a_pinch_at_every_corner() {
+        let paths = trace("#####\n#-#-#\n##-##\n#-#-#\n#####", 5, 5);
+        assert_eq!(paths.len(), 6);
+        let mut areas: Vec<_> = paths.iter().map(|p| signed_area(p)).collect();
+        areas.sort_by(f64::total_cmp);
+        assert_eq!(areas, [-25.0, 1.0, 1.0, 1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn diagonal_pixels_stay_separate() {
+        let paths = trace("-#-\n#-#\n-#-", 3, 3);
+        // four separate pixels touching at their corners, with no enclosed area
+        assert_eq!(paths.len(), 4);
+        assert!(paths.iter().all(|p| signed_area(p).abs() == 1.0));
+    }
+
+    #[test]
+    fn directed_edges_wind_around_the_ink() {
+        let src = "
+            #####
+            #...#
+            #.#.#
+            #...#
+            #####
+        ";
+        let grid: Vec<f64> = src
+            .split_whitespace()
+            .flat_map(|row| row.bytes().map(|b| if b == b'#' { 1.0 } else { 0.0 }))
+            .collect();
+
+        let groups = get_directed_edges(&grid, 5, 5);
+        assert_eq!(groups.len(), 2);
+        for lines in groups.values() {
+            // every point is left as often as it is reached, so the segments form closed loops
+            let mut degree: HashMap<Point, i32> = HashMap::new();
+            for &(a, b) in lines {
+                *degree.entry(a).or_default() += 1;
+                *degree.entry(b).or_default() -= 1;
+            }
+            assert!(degree.values().all(|&d| d == 0));
+        }
+
+        // the outer contour is counter-clockwise and the hole clockwise, so the area of the
+        // segments is the ink of the group
+        let area = |lines: &Vec<Line>| -> f64 {
+            lines
+                .iter()
+                .map(|&((x0, y0), (x1, y1))| (x0 as f64 * y1 as f64 - x1 as f64 * y0 as f64) * 0.5)
+                .sum()
+        };
+        let mut areas: Vec<f64> = groups.values().map(area).collect();
+        areas.sort_by(f64::total_cmp);
+        assert_eq!(areas, vec![-16.0, -1.0]);
+    }
+
+    fn coverage_area(path: &[(f64, f64)]) -> f64 {
+        let n = path.len();
+        (0..n)
+            .map(|i| {
+                let ((x0, y0), (x1, y1)) = (path[i], path[(i + 1) % n]);
+                x0 * y1 - x1 * y0
+            })
+            .sum::<f64>()
+            * 0.5
+    }
+
+    #[test]
+    fn coverage_contour_cuts_the_corners() {
+        let contours = trace_coverage(&[1.0; 4], 2, 2);
+        assert_eq!(contours.len(), 1);
+        // the corners are cut halfway between the cell centers and the blank border
+        assert_eq!(contours[0].len(), 8);
+        assert!(contours[0].contains(&(0.5, 0.0)));
+        assert!(contours[0].contains(&(0.0, 0.5)));
+        // wound like an outer contour of edges_to_paths
+        assert_eq!(coverage_area(&contours[0]), -3.5);
+    }
+
+    #[test]
+    fn partial_coverage_moves_the_contour() {
+        let contours = trace_coverage(&[1.0, 0.75], 2, 1);
+        let right = contours[0].iter().map(|p| p.0).fold(f64::MIN, f64::max);
+        assert!((right - (1.5 + 1.0 / 3.0)).abs() < 1e-9);
+
+        // a cell less than half covered stays outside, but still pulls the contour out
+        let contours = trace_coverage(&[1.0, 0.25], 2, 1);
+        let right = contours[0].iter().map(|p| p.0).fold(f64::MIN, f64::max);
+        assert!((right - (0.5 + 2.0 / 3.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn coverage_ring_has_a_hole() {
+        let mut grid = [1.0; 9];
+        grid[4] = 0.0;
+        let contours = trace_coverage(&grid, 3, 3);
+        assert_eq!(contours.len(), 2);
+
+        let mut areas: Vec<_> = contours.iter().map(|c| coverage_area(c)).collect();
+        areas.sort_by(f64::total_cmp);
+        assert!(areas[0] < 0.0 && areas[1] > 0.0);
+    }
 }
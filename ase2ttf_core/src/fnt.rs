@@ -0,0 +1,103 @@
+use std::path::Path;
+
+use crate::Params;
+use crate::font::FontData;
+
+/// Builds an AngelCode BMFont descriptor (.fnt) in the text format. The glyphs are drawn from
+/// the cells of the sprite, so every sheet (a layer, a tagged frame or the flattened first frame
+/// for regions) is a texture page, named after the Aseprite file.
+pub(crate) fn build_fnt(font: &FontData, args: &Params) -> String {
+    let pixels = |units: f64| (units / font.units_per_pixel as f64).round() as i32;
+    let ascender = pixels(font.ascender as f64);
+    let line_height = ascender - pixels(font.descender as f64) + args.line_gap.unwrap_or(0) as i32;
+    let (width, height) = font.sprite_size;
+
+    let mut lines = vec![
+        format!(
+            "info face=\"{}\" size={} bold=0 italic=0 charset=\"\" unicode=1 stretchH=100 smooth=0 aa=1 padding=0,0,0,0 spacing=0,0",
+            font.family.replace('"', "'"),
+            font.units_per_em / font.units_per_pixel
+        ),
+        format!(
+            "common lineHeight={} base={} scaleW={} scaleH={} pages={} packed=0",
+            line_height, ascender, width, height, font.sheet_count
+        ),
+    ];
+
+    let stem = args
+        .file_path
+        .as_ref()
+        .and_then(|path| Path::new(path).file_stem())
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(&font.family);
+    for page in 0..font.sheet_count {
+        let file = if font.sheet_count == 1 {
+            format!("{}.png", stem)
+        } else {
+            format!("{}_{}.png", stem, page)
+        };
+        lines.push(format!("page id={} file=\"{}\"", page, file));
+    }
+
+    // the null glyph is never drawn, and mirrored glyphs have no cell that looks like them
+    let mut entries: Vec<(u32, u16)> = font
+        .cmap_entries
+        .iter()
+        .copied()
+        .filter(|&(codepoint, glyph_id)| {
+            let glyph = &font.glyphs[glyph_id as usize];
+            codepoint != 0 && (glyph.cell.is_some() || glyph.path.elements().is_empty())
+        })
+        .collect();
+    entries.sort();
+    lines.push(format!("chars count={}", entries.len()));
+    for &(codepoint, glyph_id) in &entries {
+        let glyph = &font.glyphs[glyph_id as usize];
+        let advance = pixels(glyph.advance as f64);
+        // glyphs that aren't drawn from a cell, such as the space, only move the cursor
+        let line = match &glyph.cell {
+            Some(cell) => format!(
+                "char id={} x={} y={} width={} height={} xoffset={} yoffset={} xadvance={} page={} chnl=15",
+                codepoint,
+                cell.x,
+                cell.y,
+                cell.width,
+                cell.height,
+                pixels(cell.origin.x),
+                ascender - pixels(cell.origin.y),
+                advance,
+                cell.sheet
+            ),
+            None => format!(
+                "char id={} x=0 y=0 width=0 height=0 xoffset=0 yoffset=0 xadvance={} page=0 chnl=15",
+                codepoint, advance
+            ),
+        };
+        lines.push(line);
+    }
+
+    // kerning pairs are between glyphs, so a glyph drawn for several characters kerns each of them
+    let mut kernings = vec![];
+    for &(left, right, amount) in &font.kerning {
+        let amount = pixels(amount as f64);
+        if amount == 0 {
+            continue;
+        }
+        for &(first, _) in entries.iter().filter(|&&(_, glyph_id)| glyph_id == left) {
+            for &(second, _) in entries.iter().filter(|&&(_, glyph_id)| glyph_id == right) {
+                kernings.push((first, second, amount));
+            }
+        }
+    }
+    kernings.sort();
+    if !kernings.is_empty() {
+        lines.push(format!("kernings count={}", kernings.len()));
+        for (first, second, amount) in kernings {
+            lines.push(format!(
+                "kerning first={} second={} amount={}",
+                first, second, amount
+            ));
+        }
+    }
+    lines.join("\n") + "\n"
+}
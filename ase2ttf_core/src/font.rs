@@ -0,0 +1,1948 @@
+use std::cell::RefCell;
+use std::cmp;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::Display;
+use std::iter;
+use std::ops::{DerefMut, RangeInclusive};
+use std::path::Path;
+
+use asefile::{AsepriteFile, Layer};
+use kurbo::{Affine, BezPath, Point, Shape};
+
+use crate::advance::{SkippedAdvance, parse_advances};
+use crate::config::{Config, Region};
+use crate::corner::round_corners;
+use crate::edge::{
+    drop_collinear, edges_to_paths, fill_interior, filled_rect_path, get_edges, trace_coverage,
+};
+use crate::kerning::{SkippedKerningPair, apply_kerning};
+use crate::report::LayerReport;
+use crate::simplify::{SimplifiedGlyph, simplify};
+use crate::variable::match_masters;
+use crate::{
+    Error, LocalizedName, MAX_UNITS_PER_EM, Params, SCALE, composite_layers, descent, glyph_layers,
+    parse_codepoint, parse_codepoint_range, parse_ligature, to_font_units,
+};
+
+/// A traced glyph with its horizontal metrics in font units.
+pub(crate) struct GlyphData {
+    pub name: String,
+    pub path: BezPath,
+    pub advance: u32,
+    pub lsb: i32,
+    /// The cell the glyph was cut from, for glyphs drawn as they appear in the sprite.
+    pub cell: Option<GlyphCell>,
+}
+
+/// A cell of the sprite, for formats that draw glyphs from the sprite as a texture.
+pub(crate) struct GlyphCell {
+    /// The index of the sheet the cell is in.
+    pub sheet: usize,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// The top-left corner of the cell relative to the glyph origin, in font units with y up.
+    pub origin: Point,
+    /// The RGBA pixels of the cell, row by row from the top.
+    pub pixels: Vec<[u8; 4]>,
+}
+
+/// The glyphs and metrics traced from an Aseprite file, shared by every output format.
+pub(crate) struct FontData {
+    pub family: String,
+    /// The name of the font in PostScript, which is unique to it and free of special characters.
+    pub postscript_name: String,
+    /// The vendor tag of the OS/2 table, padded with spaces.
+    pub vendor_id: [u8; 4],
+    /// The family and subfamily names in other languages, keyed by Windows language ID.
+    pub localized_names: BTreeMap<u16, LocalizedName>,
+    /// The width and height of the sprite, in pixels.
+    pub sprite_size: (u32, u32),
+    pub sheet_count: usize,
+    pub glyph_width: u32,
+    pub glyph_height: u32,
+    pub units_per_em: u32,
+    /// The number of font units that one pixel of the sprite spans.
+    pub units_per_pixel: u32,
+    pub ascender: i16,
+    pub descender: i16,
+    pub glyphs: Vec<GlyphData>,
+    pub cmap_entries: Vec<(u32, u16)>,
+    /// Proportional and tabular glyph ids of figures drawn in both styles.
+    pub figure_pairs: Vec<(u16, u16)>,
+    /// The glyph ids of the components of each ligature, and the glyph that replaces them.
+    pub ligatures: Vec<(Vec<u16>, u16)>,
+    /// The palettes of a color font. The first one holds the colors of the art in the order they
+    /// first appear, and the others draw them in the colors of the config.
+    pub palettes: Vec<Vec<[u8; 3]>>,
+    /// The layer glyphs of each color glyph, with the palette index they are drawn in.
+    pub color_layers: Vec<(u16, Vec<(u16, u16)>)>,
+    /// The cap height measured from the reference glyph, in font units.
+    pub cap_height: Option<i16>,
+    pub max_point: u16,
+    pub max_contour_count: u16,
+    /// Glyphs that were simplified to fit within `max_points`.
+    pub simplified: Vec<SimplifiedGlyph>,
+    pub uneven_trim_scale: Option<UnevenTrimScale>,
+    /// Layers that start like a codepoint but were left out, because their name doesn't parse.
+    pub ignored_layers: Vec<IgnoredLayer>,
+    /// The glyphs of the second master of a variable font, in the same order as `glyphs`.
+    pub heavy_master: Option<Vec<GlyphData>>,
+    /// Adjustments of the advance between two glyph ids, in font units.
+    pub kerning: Vec<(u16, u16, i16)>,
+    /// Kerning pairs that were left out, because they name a codepoint without a glyph.
+    pub skipped_kerning: Vec<SkippedKerningPair>,
+    /// Advances from the advances file that were left out, because their codepoint has no glyph.
+    pub skipped_advances: Vec<SkippedAdvance>,
+}
+
+impl FontData {
+    /// The cap height written to the font, which is taken from the reference glyph, or else from
+    /// the top of `H`. Without either, it is 70% of the ascender.
+    pub fn measured_cap_height(&self) -> i16 {
+        self.cap_height
+            .or_else(|| self.ink_top(0x0048))
+            .unwrap_or_else(|| self.part_of_ascender(0.7))
+    }
+
+    /// The x-height written to the font, which is the top of `x`, or else half of the ascender.
+    pub fn measured_x_height(&self) -> i16 {
+        self.ink_top(0x0078)
+            .unwrap_or_else(|| self.part_of_ascender(0.5))
+    }
+
+    /// The top of the ink of the glyph mapped to `codepoint`, in font units.
+    fn ink_top(&self, codepoint: u32) -> Option<i16> {
+        let &(_, glyph_id) = self.cmap_entries.iter().find(|(c, _)| *c == codepoint)?;
+        let path = &self.glyphs[glyph_id as usize].path;
+        (!path.elements().is_empty()).then(|| path.bounding_box().y1.round() as i16)
+    }
+
+    /// A part of the ascender, rounded to whole pixels.
+    fn part_of_ascender(&self, part: f64) -> i16 {
+        let pixels = (self.ascender as f64 * part / self.units_per_pixel as f64).round();
+        (pixels * self.units_per_pixel as f64) as i16
+    }
+}
+
+/// Trimmed advances that were rounded to whole pixels, because the ratio between the glyph size
+/// and the glyph width isn't a whole number. Text set in such a font can be spaced slightly
+/// unevenly.
+#[derive(Debug, PartialEq)]
+pub struct UnevenTrimScale {
+    /// The ratio that trimmed widths are scaled by.
+    pub ratio: f64,
+    /// The number of glyphs whose advance was rounded.
+    pub glyphs: usize,
+    /// The codepoints of the rounded glyphs.
+    pub codepoints: Vec<u32>,
+}
+
+impl Display for UnevenTrimScale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "trimmed advances are scaled by {:.3}, which isn't a whole number, so {} glyphs were rounded to the nearest pixel",
+            self.ratio, self.glyphs
+        )
+    }
+}
+
+/// A layer named like a codepoint, which was left out because its name doesn't parse, e.g.
+/// `U+XYZ` or `U+`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IgnoredLayer {
+    pub name: String,
+}
+
+impl Display for IgnoredLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the layer {} was skipped, as its name is not a codepoint such as U+0041",
+            self.name
+        )
+    }
+}
+
+/// The block of the Private Use Area that mirrored copies of glyphs are placed in, at
+/// `MIRROR_PUA_START` plus the codepoint of the original glyph.
+const MIRROR_PUA_START: u32 = 0xE000;
+const MIRROR_PUA_END: u32 = 0xF8FF;
+
+/// The Combining Diacritical Marks block, whose glyphs are given no advance by default.
+const COMBINING_MARKS: RangeInclusive<u32> = 0x0300..=0x036F;
+
+/// The part of an image that is split into cells of one size.
+#[derive(Clone, Copy)]
+struct Grid {
+    x: u32,
+    y: u32,
+    cols: u32,
+    rows: u32,
+    cell_width: u32,
+    cell_height: u32,
+    /// The gap between neighboring cells, such as a line drawn to separate them.
+    margin: u32,
+}
+
+impl Grid {
+    /// The top-left pixel of a cell.
+    fn cell_origin(&self, col: u32, row: u32) -> (u32, u32) {
+        (
+            self.x + col * (self.cell_width + self.margin),
+            self.y + row * (self.cell_height + self.margin),
+        )
+    }
+}
+
+/// An image to split into glyphs, with the codepoints assigned to its cells.
+struct Sheet<I> {
+    name: String,
+    grid: Grid,
+    base_code: u32,
+    /// The last codepoint of an explicit range, if the layer declares one.
+    end_code: Option<u32>,
+    image: I,
+    /// The fill drawn inside the outline of this sheet.
+    fill: Option<I>,
+    role: Option<LayerRole>,
+    /// The characters that a ligature layer joins, which is empty for any other layer.
+    components: Vec<u32>,
+    /// Slice pivots in the coordinates of the sprite, each setting the origin of the cell it is in.
+    pivots: Vec<(u32, u32)>,
+    /// The codepoint of each cell of a layer mapped through the charset, `None` for blank cells.
+    charset: Option<Vec<Option<u32>>>,
+}
+
+impl<I> Sheet<I> {
+    /// The codepoint of the cell at `index`, counted row by row from the top-left cell.
+    fn codepoint(&self, index: u32) -> Option<u32> {
+        match &self.charset {
+            Some(codepoints) => codepoints[index as usize],
+            None => Some(self.base_code + index),
+        }
+    }
+}
+
+/// Collects the images to split into glyphs on one frame, with the layers that were left out
+/// because their name doesn't parse. asefile draws each cel at its position on an image the size
+/// of the sprite, so cells are read from the same place however small the cel is. `frame_image`
+/// flattens a frame and `cel_image` draws one layer of a frame.
+fn read_sheets<I: DerefMut<Target = [u8]>>(
+    ase: &AsepriteFile,
+    args: &Params,
+    frame: u32,
+    regions: &[Region],
+    frame_image: impl Fn(u32) -> I,
+    cel_image: impl Fn(&Layer, u32) -> I,
+) -> Result<(Vec<Sheet<I>>, Vec<IgnoredLayer>), Error> {
+    let glyph_width = args.glyph_width.unwrap_or(16);
+    let glyph_height = args.glyph_height.unwrap_or(16);
+    let cell_margin = args.cell_margin.unwrap_or(0);
+    let width = ase.width() as u32;
+    let height = ase.height() as u32;
+    let alpha_threshold = alpha_threshold(args);
+    // the alpha of a pixel of an image the size of the sprite
+    let alpha = |image: &I, x: u32, y: u32| image[((y * width + x) * 4 + 3) as usize];
+
+    let cols = (width + cell_margin) / (glyph_width + cell_margin);
+    let rows = (height + cell_margin) / (glyph_height + cell_margin);
+    let full_grid = Grid {
+        x: 0,
+        y: 0,
+        cols,
+        rows,
+        cell_width: glyph_width,
+        cell_height: glyph_height,
+        margin: cell_margin,
+    };
+
+    let use_tags = args.use_tags.unwrap_or(false);
+    let mut sheets = Vec::new();
+    let mut ignored_layers = vec![];
+    if !regions.is_empty() {
+        if use_tags {
+            return Err(Error::new(
+                "Regions can't be used together with tags.".to_string(),
+            ));
+        }
+        // every region is cut from the visible layers of the frame
+        for (i, region) in regions.iter().enumerate() {
+            if region.x + region.width > width || region.y + region.height > height {
+                return Err(Error::new(format!(
+                    "Region {} extends past the {}x{} sheet.",
+                    i, width, height
+                )));
+            }
+            sheets.push(Sheet {
+                name: format!("region {}", i),
+                grid: Grid {
+                    x: region.x,
+                    y: region.y,
+                    cols: region.width / region.glyph_width,
+                    rows: region.height / region.glyph_height,
+                    cell_width: region.glyph_width,
+                    cell_height: region.glyph_height,
+                    margin: 0,
+                },
+                base_code: region.codepoint,
+                end_code: None,
+                image: frame_image(frame),
+                fill: None,
+                role: None,
+                components: vec![],
+                pivots: slice_pivots(ase, frame),
+                charset: None,
+            });
+        }
+    } else if use_tags {
+        // each frame in a tagged range continues the codepoints of the previous frame
+        for i in 0..ase.num_tags() {
+            let tag = ase.tag(i);
+            let Some(base_code) = parse_codepoint(tag.name()) else {
+                continue;
+            };
+            for tag_frame in tag.from_frame()..=tag.to_frame() {
+                let offset = (tag_frame - tag.from_frame()) * cols * rows;
+                sheets.push(Sheet {
+                    name: tag.name().to_string(),
+                    grid: full_grid,
+                    base_code: base_code + offset,
+                    end_code: None,
+                    image: frame_image(tag_frame),
+                    fill: None,
+                    role: None,
+                    components: vec![],
+                    pivots: slice_pivots(ase, tag_frame),
+                    charset: None,
+                });
+            }
+        }
+    } else {
+        let mut layers = Vec::new();
+        let mut fills = HashMap::new();
+        let mut charset = args.charset.as_ref().map(|charset| charset.chars());
+        let layer_image =
+            |layers: &[Layer]| composite_layers(layers, |layer| cel_image(layer, frame));
+        for (layer, members) in glyph_layers(ase, args) {
+            // a ligature is drawn in the first cell of its layer
+            if let Some(components) = parse_ligature(layer.name()) {
+                layers.push(Sheet {
+                    name: layer.name().to_string(),
+                    grid: Grid {
+                        cols: 1,
+                        rows: 1,
+                        ..full_grid
+                    },
+                    base_code: components[0],
+                    end_code: None,
+                    image: layer_image(&members),
+                    fill: None,
+                    role: None,
+                    components,
+                    pivots: slice_pivots(ase, frame),
+                    charset: None,
+                });
+                continue;
+            }
+            let Some((base_code, end_code)) = parse_codepoint_range(layer.name()) else {
+                // other layers are free to hold sketches or references, but one that starts like
+                // a codepoint was most likely meant to be a glyph
+                let name = layer.name();
+                if name.starts_with("U+") || name.starts_with("u+") {
+                    ignored_layers.push(IgnoredLayer {
+                        name: name.to_string(),
+                    });
+                    continue;
+                }
+                // with a charset, each drawn cell takes its next character
+                let Some(chars) = charset.as_mut() else {
+                    continue;
+                };
+                let image = layer_image(&members);
+                let mut codepoints = vec![];
+                for index in 0..cols * rows {
+                    let (x0, y0) = full_grid.cell_origin(index % cols, index / cols);
+                    let drawn = (0..glyph_height).any(|y| {
+                        (0..glyph_width).any(|x| alpha(&image, x0 + x, y0 + y) >= alpha_threshold)
+                    });
+                    if !drawn {
+                        codepoints.push(None);
+                        continue;
+                    }
+                    let c = chars.next().ok_or_else(|| {
+                        Error::new(format!(
+                            "The charset has {} characters, which aren't enough for the drawn cells of layer {}.",
+                            args.charset.as_ref().map_or(0, |charset| charset.chars().count()),
+                            name
+                        ))
+                    })?;
+                    codepoints.push(Some(c as u32));
+                }
+                layers.push(Sheet {
+                    name: name.to_string(),
+                    grid: full_grid,
+                    base_code: 0,
+                    end_code: None,
+                    image,
+                    fill: None,
+                    role: None,
+                    components: vec![],
+                    pivots: slice_pivots(ase, frame),
+                    charset: Some(codepoints),
+                });
+                continue;
+            };
+            if let Some(end_code) = end_code {
+                let count = end_code as i64 - base_code as i64 + 1;
+                if count < 1 || count > (cols * rows) as i64 {
+                    return Err(Error::new(format!(
+                        "The range of layer {} covers {} codepoints, but the layer has {} cells.",
+                        layer.name(),
+                        count.max(0),
+                        cols * rows
+                    )));
+                }
+            }
+            match layer_role(layer.name()) {
+                Some(LayerRole::Fill) => {
+                    fills.insert(base_code, (layer.name().to_string(), layer_image(&members)));
+                }
+                role => layers.push(Sheet {
+                    name: layer.name().to_string(),
+                    grid: full_grid,
+                    base_code,
+                    end_code,
+                    image: layer_image(&members),
+                    fill: None,
+                    role,
+                    components: vec![],
+                    pivots: slice_pivots(ase, frame),
+                    charset: None,
+                }),
+            }
+        }
+
+        for mut sheet in layers {
+            if sheet.role == Some(LayerRole::Outline) {
+                sheet.fill = fills.remove(&sheet.base_code).map(|(_, fill)| fill);
+            }
+            sheets.push(sheet);
+        }
+        if let Some(name) = fills.into_values().map(|(name, _)| name).min() {
+            return Err(Error::new(format!(
+                "The fill layer {} has no matching outline layer.",
+                name
+            )));
+        }
+    }
+    Ok((sheets, ignored_layers))
+}
+
+/// Reads the sheets of the first frame like `build_font`, and reports which of their cells are
+/// drawn without tracing them, so that a sheet that can't be built yet can still be looked into.
+pub(crate) fn analyze_sheets(
+    ase_bytes: &[u8],
+    args: &Params,
+) -> Result<(Vec<LayerReport>, Vec<IgnoredLayer>, usize), Error> {
+    let ase = AsepriteFile::read(ase_bytes).map_err(Error::AseRead)?;
+    let config = match &args.config {
+        Some(source) => Config::parse(source)?,
+        None => Config::default(),
+    };
+    let regions = config.regions()?;
+    let aliases = config
+        .aliases()?
+        .iter()
+        .map(|(_, aliases)| aliases.len())
+        .sum();
+    let (sheets, ignored_layers) = read_sheets(
+        &ase,
+        args,
+        0,
+        &regions,
+        |frame| ase.frame(frame).image(),
+        |layer, frame| layer.frame(frame).image(),
+    )?;
+    let alpha_threshold = alpha_threshold(args);
+
+    let mut reports = vec![];
+    for sheet in &sheets {
+        let grid = sheet.grid;
+        let mut glyphs = vec![];
+        let mut blank_cells = vec![];
+        let mut ink_bounds: Option<(u32, u32, u32, u32)> = None;
+        for index in 0..grid.cols * grid.rows {
+            // blank cells of a charset layer have no codepoint
+            let Some(codepoint) = sheet.codepoint(index) else {
+                continue;
+            };
+            let (x0, y0) = grid.cell_origin(index % grid.cols, index / grid.cols);
+            let mut drawn = false;
+            for y in 0..grid.cell_height {
+                for x in 0..grid.cell_width {
+                    if sheet.image.get_pixel(x0 + x, y0 + y)[3] < alpha_threshold {
+                        continue;
+                    }
+                    drawn = true;
+                    ink_bounds = Some(match ink_bounds {
+                        Some((left, top, right, bottom)) => {
+                            (left.min(x), top.min(y), right.max(x), bottom.max(y))
+                        }
+                        None => (x, y, x, y),
+                    });
+                }
+            }
+            if drawn {
+                glyphs.push(codepoint);
+            } else {
+                blank_cells.push(codepoint);
+            }
+        }
+        reports.push(LayerReport {
+            name: sheet.name.clone(),
+            cells: grid.cols * grid.rows,
+            glyphs,
+            blank_cells,
+            ink_bounds,
+        });
+    }
+    Ok((reports, ignored_layers, aliases))
+}
+
+/// Returns the codepoints whose cells are drawn on one frame, read from the same sheets as
+/// `build_font`. Ligature and charset layers are left out, as their cells aren't tied to a
+/// codepoint that the other frames would draw in the same place.
+pub(crate) fn drawn_codepoints(
+    ase: &AsepriteFile,
+    args: &Params,
+    regions: &[Region],
+    frame: u32,
+) -> Result<BTreeSet<u32>, Error> {
+    let (sheets, _) = read_sheets(
+        ase,
+        args,
+        frame,
+        regions,
+        |frame| ase.frame(frame).image(),
+        |layer, frame| layer.frame(frame).image(),
+    )?;
+    let alpha_threshold = alpha_threshold(args);
+
+    let mut codepoints = BTreeSet::new();
+    for sheet in sheets {
+        if sheet.charset.is_some() || !sheet.components.is_empty() {
+            continue;
+        }
+        let grid = sheet.grid;
+        for index in 0..grid.cols * grid.rows {
+            let (x0, y0) = grid.cell_origin(index % grid.cols, index / grid.cols);
+            let drawn = (0..grid.cell_height).any(|y| {
+                (0..grid.cell_width)
+                    .any(|x| sheet.image.get_pixel(x0 + x, y0 + y)[3] >= alpha_threshold)
+            });
+            if let Some(codepoint) = sheet.codepoint(index).filter(|_| drawn) {
+                codepoints.insert(codepoint);
+            }
+        }
+    }
+    Ok(codepoints)
+}
+
+/// A cell read from a sheet and traced, before it is placed in the font.
+struct TracedCell {
+    /// The coverage of the cell and its bleed, after filling and mirroring.
+    bitmap: Vec<f64>,
+    path: BezPath,
+    point: u16,
+    contour_count: u16,
+    /// The outline of each color of the cell, in color mode.
+    colors: Vec<([u8; 3], BezPath)>,
+}
+
+pub(crate) fn build_font(ase_bytes: &[u8], args: &Params) -> Result<FontData, Error> {
+    let ase = AsepriteFile::read(ase_bytes).map_err(Error::AseRead)?;
+    let mut font = build_masters(&ase, args)?;
+    if let Some(radius) = args.corner_radius {
+        round_font_corners(&mut font, radius, args.max_points)?;
+    }
+    if let Some(units_per_em) = args.units_per_em {
+        rescale(&mut font, units_per_em)?;
+    }
+    if let Some(kerning) = &args.kerning {
+        apply_kerning(&mut font, kerning)?;
+    }
+    Ok(font)
+}
+
+fn build_masters(ase: &AsepriteFile, args: &Params) -> Result<FontData, Error> {
+    if !args.variable.unwrap_or(false) {
+        return build_master(ase, args, 0);
+    }
+
+    // each frame is a master of a variable font; a single master would produce an fvar table
+    // with nothing to vary
+    if ase.num_frames() < 2 {
+        return Err(Error::new(format!(
+            "A variable font needs at least two masters, one per frame, but the file has {}.",
+            ase.num_frames()
+        )));
+    }
+    if ase.num_frames() > 2 {
+        return Err(Error::new(format!(
+            "A variable font is built from two masters, one per frame, but the file has {}.",
+            ase.num_frames()
+        )));
+    }
+    if args.use_tags.unwrap_or(false) {
+        return Err(Error::new(
+            "Tags can't be used together with a variable font, as each frame is a master."
+                .to_string(),
+        ));
+    }
+    let mut font = build_master(ase, args, 0)?;
+    let heavy = build_master(ase, args, 1)?;
+    match_masters(&mut font, heavy)?;
+    Ok(font)
+}
+
+/// Rounds the corners of every glyph, by `radius` pixels. A radius of 0 keeps the pixel look.
+fn round_font_corners(
+    font: &mut FontData,
+    radius: f64,
+    max_points: Option<u16>,
+) -> Result<(), Error> {
+    if radius.is_nan() || radius < 0.0 {
+        return Err(Error::new(format!(
+            "The corner radius must be 0 or more, but it is {}.",
+            radius
+        )));
+    }
+    if radius == 0.0 {
+        return Ok(());
+    }
+    // rounding adds two points to every corner, which the point budget doesn't account for
+    if max_points.is_some() {
+        return Err(Error::new(
+            "A corner radius can't be used together with a point budget, as rounding adds points to every corner."
+                .to_string(),
+        ));
+    }
+
+    let radius = radius * font.units_per_pixel as f64;
+    let mut max_point = 0;
+    for glyph in font
+        .glyphs
+        .iter_mut()
+        .chain(font.heavy_master.iter_mut().flatten())
+    {
+        if glyph.path.elements().is_empty() {
+            continue;
+        }
+        let (path, point) = round_corners(&glyph.path, radius);
+        glyph.path = path;
+        max_point = max_point.max(point);
+    }
+    font.max_point = max_point;
+    Ok(())
+}
+
+/// Scales the font units of a traced font to another em size. The glyphs are traced at `SCALE`
+/// units per pixel, and the em size must be a whole number of units per pixel, so that the
+/// outlines stay on the pixel grid.
+fn rescale(font: &mut FontData, units_per_em: u16) -> Result<(), Error> {
+    let em = font.units_per_em / font.units_per_pixel;
+    let units_per_em = units_per_em as u32;
+    if !(16..=MAX_UNITS_PER_EM).contains(&units_per_em) {
+        return Err(Error::new(format!(
+            "The units per em must be between 16 and {}, but it is {}.",
+            MAX_UNITS_PER_EM, units_per_em
+        )));
+    }
+    if !units_per_em.is_multiple_of(em) {
+        return Err(Error::new(format!(
+            "The units per em {} must be a multiple of the em size of {} pixels.",
+            units_per_em, em
+        )));
+    }
+
+    let units_per_pixel = units_per_em / em;
+    let ratio = units_per_pixel as f64 / font.units_per_pixel as f64;
+    let scale = |units: f64| (units * ratio).round();
+    let scale_i16 = |units: i16| {
+        i16::try_from(scale(units as f64) as i64).map_err(|_| {
+            Error::new(format!(
+                "{} font units is too large to be scaled to {} units per em.",
+                units, units_per_em
+            ))
+        })
+    };
+    font.ascender = scale_i16(font.ascender)?;
+    font.descender = scale_i16(font.descender)?;
+    font.cap_height = font.cap_height.map(scale_i16).transpose()?;
+    for glyph in font
+        .glyphs
+        .iter_mut()
+        .chain(font.heavy_master.iter_mut().flatten())
+    {
+        glyph.path.apply_affine(Affine::scale(ratio));
+        glyph.advance = scale(glyph.advance as f64) as u32;
+        glyph.lsb = scale(glyph.lsb as f64) as i32;
+        if let Some(cell) = &mut glyph.cell {
+            cell.origin = Affine::scale(ratio) * cell.origin;
+        }
+    }
+    font.units_per_em = units_per_em;
+    font.units_per_pixel = units_per_pixel;
+    Ok(())
+}
+
+/// Builds the glyphs drawn on one frame. Only tags read other frames, to assign codepoints.
+fn build_master(ase: &AsepriteFile, args: &Params, frame: u32) -> Result<FontData, Error> {
+    // params
+    let glyph_width = args.glyph_width.unwrap_or(16);
+    let glyph_height = args.glyph_height.unwrap_or(16);
+    let cell_margin = args.cell_margin.unwrap_or(0);
+    let base_line = descent(args)?;
+    let size = cmp::max(glyph_width, glyph_height);
+    let margin_top = args.margin_top.unwrap_or(0);
+    let margin_bottom = args.margin_bottom.unwrap_or(0);
+    let vendor_id = vendor_id(args)?;
+    // without a family, the name is taken from the file the bytes were read from
+    let family = match &args.family {
+        Some(family) => family.clone(),
+        None => args
+            .file_path
+            .as_ref()
+            .and_then(|path| Path::new(path).file_stem())
+            .and_then(|stem| stem.to_str())
+            .map(|stem| stem.to_string())
+            .ok_or_else(|| {
+                Error::new("A family name is required when no file path is given.".to_string())
+            })?,
+    };
+    let postscript_name = postscript_name(args, &family)?;
+
+    let config = match &args.config {
+        Some(source) => Config::parse(source)?,
+        None => Config::default(),
+    };
+    let regions = config.regions()?;
+    // the names of the params take the place of the ones in the config for the same language
+    let mut localized_names = config.localized_names()?;
+    localized_names.extend(args.localized_names.clone().unwrap_or_default());
+    if localized_names.contains_key(&0x0409) {
+        return Err(Error::new(
+            "The names of language 0x0409 are the English ones, which are set by the family and subfamily.".to_string(),
+        ));
+    }
+
+    // validate size
+    let width = ase.width() as u32;
+    let height = ase.height() as u32;
+    // regions are checked against their own cells instead
+    if regions.is_empty() {
+        // the last column and row have no margin after them
+        let fits = |size: u32, cell: u32| (size + cell_margin).is_multiple_of(cell + cell_margin);
+        if !fits(width, glyph_width) || !fits(height, glyph_height) {
+            return Err(Error::DimensionMismatch {
+                width,
+                height,
+                glyph_width,
+                glyph_height,
+                cell_margin,
+            });
+        }
+    }
+    // each block of downscale x downscale pixels becomes one cell of the traced grid
+    let downscale = args.downscale.unwrap_or(1);
+    // pixels read around each cell, for glyphs that share their edges with their neighbors
+    let bleed = args.bleed.unwrap_or(0);
+    // the pixels that a synthetic bold widens the ink by, to the right
+    let synthetic_bold = args.synthetic_bold.unwrap_or(0);
+    let alpha_threshold = alpha_threshold(args);
+    let cell_sizes = || {
+        iter::once((glyph_width, glyph_height))
+            .chain(regions.iter().map(|r| (r.glyph_width, r.glyph_height)))
+    };
+    if downscale == 0
+        || cell_sizes().any(|(w, h)| !w.is_multiple_of(downscale) || !h.is_multiple_of(downscale))
+    {
+        return Err(Error::new(format!(
+            "The glyph width and height must be multiples of the downscale ratio {}.",
+            downscale
+        )));
+    }
+    if !bleed.is_multiple_of(downscale) {
+        return Err(Error::new(format!(
+            "The bleed must be a multiple of the downscale ratio {}.",
+            downscale
+        )));
+    }
+    // a baseline outside of the cells would invert the ascender or the descender
+    let cell_height = cell_sizes().map(|(_, h)| h).min().unwrap_or(glyph_height);
+    if base_line < 0 || base_line as u32 > cell_height {
+        return Err(Error::new(format!(
+            "The baseline {} must be between 0 and the glyph height {}.",
+            base_line, cell_height
+        )));
+    }
+    // the margins enlarge the em around the glyphs, which stay at the same place on the baseline
+    let em = size as u64 + margin_top as u64 + margin_bottom as u64;
+    if em * SCALE as u64 > MAX_UNITS_PER_EM as u64 {
+        return Err(Error::new(format!(
+            "The glyph size including the margins must be at most {} pixels.",
+            MAX_UNITS_PER_EM / SCALE
+        )));
+    }
+    let units_per_em = em as u32 * SCALE;
+
+    let glyph_overrides = config.glyph_overrides()?;
+    let advances = match &args.advances {
+        Some(source) => parse_advances(source)?,
+        None => BTreeMap::new(),
+    };
+    let mut used_overrides = HashSet::new();
+
+    // vertical metrics in font units
+    let ascender = to_font_units(
+        glyph_height as i64 - base_line as i64 + margin_top as i64,
+        SCALE,
+    )?;
+    let descender = to_font_units(-(base_line as i64) - margin_bottom as i64, SCALE)?;
+
+    // build glyph
+    let mut glyphs = vec![];
+    let mut cmap_entries = vec![];
+    let mut max_point: u16 = 0;
+    let mut max_contour_count: u16 = 0;
+    let mut simplified = vec![];
+
+    // add .notdef / null / space
+    for (name, reserved) in config.reserved_glyphs()? {
+        let advance = match name {
+            "space" => args.space_width.or(reserved.advance),
+            _ => reserved.advance,
+        }
+        .unwrap_or(glyph_width);
+        let mut path = BezPath::new();
+        if reserved.visible.unwrap_or(false) {
+            let (box_path, point, contour_count) = notdef_box(advance, glyph_height, base_line);
+            path = box_path;
+            max_point = max_point.max(point);
+            max_contour_count = max_contour_count.max(contour_count);
+        }
+        glyphs.push(GlyphData {
+            name: name.to_string(),
+            path,
+            advance: advance * SCALE,
+            lsb: 0,
+            cell: None,
+        });
+    }
+    cmap_entries.push((0x0000, 1)); // null
+    cmap_entries.push((0x0020, 2)); // space
+
+    let use_tags = args.use_tags.unwrap_or(false);
+    let (sheets, ignored_layers) = read_sheets(
+        ase,
+        args,
+        frame,
+        &regions,
+        |frame| ase.frame(frame).image(),
+        |layer, frame| layer.frame(frame).image(),
+    )?;
+
+    // alternate figures, reached through GSUB instead of the cmap
+    let mut figure_variants = vec![];
+    // ligatures, which are also reached through GSUB
+    let mut ligature_glyphs = vec![];
+
+    let mirror = args.mirror.unwrap_or(false);
+    // the colors of each color glyph, which become layer glyphs once every cell is traced
+    let color = args.color.unwrap_or(false);
+    let mut color_glyphs = vec![];
+    let mirror_pua = args.mirror_pua.unwrap_or(false);
+    let mut mirrored_codes = vec![];
+
+    // with a cap height reference, each sheet is moved up or down so that its capitals line up
+    // with the top of the reference glyph
+    let mut cap_height = None;
+    let mut sheet_offsets = vec![0; sheets.len()];
+    if let Some(reference) = args.cap_height_reference {
+        // tops are measured in pixels from the bottom of the cell, which is the same distance
+        // below the baseline in every grid
+        let mut reference_top = None;
+        let mut capital_tops = vec![];
+        for sheet in &sheets {
+            let grid = sheet.grid;
+            let mut tops: HashMap<u32, usize> = HashMap::new();
+            for row in 0..grid.rows {
+                for col in 0..grid.cols {
+                    let Some(codepoint) = sheet.codepoint(row * grid.cols + col) else {
+                        continue;
+                    };
+                    let is_capital = char::from_u32(codepoint).is_some_and(char::is_uppercase);
+                    if codepoint != reference && !is_capital {
+                        continue;
+                    }
+                    let (x0, y0) = grid.cell_origin(col, row);
+                    let Some(top_row) = (0..grid.cell_height).find(|&y| {
+                        (0..grid.cell_width)
+                            .any(|x| sheet.image.get_pixel(x0 + x, y0 + y)[3] >= alpha_threshold)
+                    }) else {
+                        continue;
+                    };
+                    let top = grid.cell_height - top_row;
+                    if codepoint == reference && reference_top.is_none() {
+                        reference_top = Some(top);
+                    }
+                    *tops.entry(top).or_default() += 1;
+                }
+            }
+            // the most common top, so that accented capitals don't move the line
+            capital_tops.push(
+                tops.into_iter()
+                    .max_by_key(|&(top, count)| (count, top))
+                    .map(|(top, _)| top),
+            );
+        }
+
+        let reference_top = reference_top.ok_or_else(|| {
+            Error::new(format!(
+                "The cap height reference U+{:04X} has no glyph.",
+                reference
+            ))
+        })?;
+        for (offset, top) in sheet_offsets.iter_mut().zip(capital_tops) {
+            if let Some(top) = top {
+                *offset = reference_top as i32 - top as i32;
+            }
+        }
+        cap_height = Some(to_font_units(
+            reference_top as i64 - base_line as i64,
+            SCALE,
+        )?);
+    }
+
+    // trimmed widths are scaled by the glyph size, not by the cells of a region. A ratio that
+    // isn't a whole number rounds each advance on its own, unless it is clamped to one
+    let trim_ratio = size as f64 / glyph_width as f64;
+    let trim_scale = if args.clamp_trim_scale.unwrap_or(false) {
+        trim_ratio.floor()
+    } else {
+        trim_ratio
+    };
+    let rounded_advances = RefCell::new(vec![]);
+    // blank cells, which are only given glyphs when asked for
+    let emit_blank_glyphs = args.emit_blank_glyphs.unwrap_or(false);
+    let mut blank_cells = vec![];
+    // the sheet that drew each codepoint, to report ranges that overlap
+    let mut drawn_by: HashMap<u32, String> = HashMap::new();
+    // the first glyph traced from each bitmap, so that a cell drawn exactly like it can share it.
+    // The masters of a variable font keep a glyph for every codepoint, as their outlines may
+    // only match in one of them
+    let share_glyphs = args.share_glyphs.unwrap_or(false) && !args.variable.unwrap_or(false);
+    let mut glyphs_by_bitmap: HashMap<Vec<u64>, u16> = HashMap::new();
+    let sheet_count = sheets.len();
+    for (sheet_index, (sheet, sheet_offset)) in sheets.into_iter().zip(sheet_offsets).enumerate() {
+        let cell_codepoints: Vec<Option<u32>> = (0..sheet.grid.cols * sheet.grid.rows)
+            .map(|index| sheet.codepoint(index))
+            .collect();
+        let Sheet {
+            name: sheet_name,
+            grid,
+            end_code,
+            image,
+            fill,
+            role,
+            components,
+            pivots,
+            ..
+        } = sheet;
+        let mut sheet_blank_cells = vec![];
+        let mut last_drawn = None;
+        // the cells of this sheet, which differ from the glyph size in regions
+        let Grid {
+            cols,
+            rows,
+            cell_width: glyph_width,
+            cell_height: glyph_height,
+            ..
+        } = grid;
+        // the cell and its bleed, which lies on the baseline of the cell, and the columns that a
+        // synthetic bold widens it by
+        let (read_width, read_height) = (
+            glyph_width + 2 * bleed + synthetic_bold,
+            glyph_height + 2 * bleed,
+        );
+        let read_base_line = base_line + bleed as i16;
+        // the cells are traced on their own, which is where most of the time goes, and are
+        // then added in the order of the sheet so that glyph ids don't depend on the threads
+        let fill = fill.as_ref();
+        let trace_cell = |row: u32, col: u32| {
+            let (x0, y0) = grid.cell_origin(col, row);
+
+            let mut bitmap = vec![0.0f64; (read_width * read_height) as usize];
+            let mut fill_bitmap = vec![0.0f64; bitmap.len()];
+            let mut color_bitmaps: BTreeMap<[u8; 3], Vec<f64>> = BTreeMap::new();
+            // cells with ink only in their bleed belong to their neighbors
+            let mut has_ink = false;
+            for y in 0..read_height {
+                for x in 0..read_width {
+                    let (Some(px), Some(py)) =
+                        ((x0 + x).checked_sub(bleed), (y0 + y).checked_sub(bleed))
+                    else {
+                        continue;
+                    };
+                    if px >= width || py >= height || x >= read_width - synthetic_bold {
+                        continue;
+                    }
+                    let pixel = image.get_pixel(px, py);
+                    // fainter pixels are left empty, so that they don't thicken the outline
+                    let ink = pixel[3] >= alpha_threshold;
+                    if ink {
+                        bitmap[(y * read_width + x) as usize] = pixel[3] as f64 / 255.0;
+                    }
+                    if color && ink {
+                        color_bitmaps
+                            .entry([pixel[0], pixel[1], pixel[2]])
+                            .or_insert_with(|| vec![0.0; (read_width * read_height) as usize])
+                            [(y * read_width + x) as usize] = pixel[3] as f64 / 255.0;
+                    }
+                    if let Some(fill) = fill {
+                        let pixel = fill.get_pixel(px, py);
+                        if pixel[3] >= alpha_threshold {
+                            fill_bitmap[(y * read_width + x) as usize] = pixel[3] as f64 / 255.0;
+                        }
+                    }
+                    has_ink |= ink
+                        && (x0..x0 + glyph_width).contains(&px)
+                        && (y0..y0 + glyph_height).contains(&py);
+                }
+            }
+            if !has_ink {
+                return None;
+            }
+            if fill.is_some() {
+                bitmap = fill_interior(
+                    &bitmap,
+                    &fill_bitmap,
+                    read_width as usize,
+                    read_height as usize,
+                );
+            }
+
+            if synthetic_bold > 0 {
+                bitmap = dilate_bitmap(&bitmap, read_width, synthetic_bold);
+                for color_bitmap in color_bitmaps.values_mut() {
+                    *color_bitmap = dilate_bitmap(color_bitmap, read_width, synthetic_bold);
+                }
+            }
+            if mirror {
+                bitmap = mirror_bitmap(&bitmap, read_width, read_height);
+            }
+            let trace_bitmap = |bitmap: &[f64]| {
+                if downscale > 1 {
+                    trace_downscaled(bitmap, read_width, read_height, read_base_line, downscale)
+                } else {
+                    trace(bitmap, read_width, read_height, read_base_line)
+                }
+            };
+            let (path, point, contour_count) = trace_bitmap(&bitmap);
+            let colors = color_bitmaps
+                .into_iter()
+                .map(|(rgb, color_bitmap)| {
+                    let color_bitmap = if mirror {
+                        mirror_bitmap(&color_bitmap, read_width, read_height)
+                    } else {
+                        color_bitmap
+                    };
+                    (rgb, trace_bitmap(&color_bitmap).0)
+                })
+                .collect();
+            Some(TracedCell {
+                bitmap,
+                path,
+                point,
+                contour_count,
+                colors,
+            })
+        };
+        let traced_cells = map_cells(rows * cols, |index| trace_cell(index / cols, index % cols));
+        for (index, traced) in (0..).zip(traced_cells) {
+            let (row, col) = (index / cols, index % cols);
+            let (x0, y0) = grid.cell_origin(col, row);
+            let Some(TracedCell {
+                bitmap,
+                mut path,
+                mut point,
+                contour_count,
+                colors,
+            }) = traced
+            else {
+                // blank cells of a charset layer have no codepoint
+                if let Some(codepoint) = cell_codepoints[index as usize] {
+                    sheet_blank_cells.push((codepoint, glyph_width));
+                }
+                continue;
+            };
+            // a slice pivot is the origin of the glyph and takes the place of the baseline
+            let pivot = pivots
+                .iter()
+                .find(|&&(x, y)| {
+                    (x0..x0 + glyph_width).contains(&x) && (y0..y0 + glyph_height).contains(&y)
+                })
+                .map(|&(x, y)| {
+                    let x = x - x0;
+                    (if mirror { glyph_width - x } else { x }, y - y0)
+                });
+            // moves the traced outline into place, and is applied once the glyph is complete
+            let bleed_offset = Affine::translate((-((bleed * SCALE) as f64), 0.0));
+            let mut placement = bleed_offset;
+            if let Some((pivot_x, pivot_y)) = pivot {
+                let pivot_height = glyph_height as i32 - pivot_y as i32 - base_line as i32;
+                placement = Affine::translate((
+                    -((pivot_x * SCALE) as f64),
+                    -((pivot_height * SCALE as i32) as f64),
+                )) * placement;
+            } else if sheet_offset != 0 {
+                placement =
+                    Affine::translate((0.0, (sheet_offset * SCALE as i32) as f64)) * placement;
+            }
+
+            let Some(codepoint) = cell_codepoints[index as usize] else {
+                continue;
+            };
+            if point == 0 {
+                sheet_blank_cells.push((codepoint, glyph_width));
+                continue;
+            }
+            if end_code.is_some_and(|end_code| codepoint > end_code) {
+                return Err(Error::new(format!(
+                    "Layer {} has a glyph at U+{:04X}, which is outside of its range.",
+                    sheet_name, codepoint
+                )));
+            }
+            last_drawn = Some(codepoint);
+            // alternate figures share the codepoint of the default glyph
+            let previous = if role.and_then(LayerRole::feature).is_none() && components.is_empty() {
+                drawn_by.insert(codepoint, sheet_name.clone())
+            } else {
+                None
+            };
+            if let Some(previous) = previous {
+                return Err(Error::new(format!(
+                    "U+{:04X} is drawn by both {} and {}. Each codepoint can only have one glyph.",
+                    codepoint, previous, sheet_name
+                )));
+            }
+
+            if let Some(max_points) = args.max_points.filter(|&max_points| point > max_points) {
+                let name = format!("U+{:04X}", codepoint);
+                let (simplified_path, simplified_point) =
+                    simplify(&path, max_points).ok_or_else(|| {
+                        Error::new(format!(
+                            "{} has {} contours, which can't fit within {} points.",
+                            name, contour_count, max_points
+                        ))
+                    })?;
+                simplified.push(SimplifiedGlyph {
+                    name,
+                    original_points: point,
+                    points: simplified_point,
+                });
+                path = simplified_path;
+                point = simplified_point;
+            }
+
+            max_point = if point > max_point { point } else { max_point };
+            max_contour_count = if contour_count > max_contour_count {
+                contour_count
+            } else {
+                max_contour_count
+            };
+
+            // tabular figures keep the full cell width even when trimming
+            let trim = match role {
+                Some(LayerRole::Tabular) => false,
+                Some(LayerRole::Proportional) => true,
+                _ => args.trim.unwrap_or(true),
+            };
+            // with trailing space kept, the glyph starts at its ink, and the transparent
+            // columns after the ink are part of the advance instead of the padding
+            let keep_trailing_space = trim && args.keep_trailing_space.unwrap_or(false);
+            let metrics = |bitmap: &[f64]| {
+                let (min_x, max_x) = ink_extent(bitmap, read_width, read_height);
+                if trim {
+                    let trailing_space =
+                        (bleed + glyph_width + synthetic_bold).saturating_sub(max_x + 1);
+                    let trimmed_width = if min_x > max_x {
+                        0
+                    } else if keep_trailing_space && trailing_space > 0 {
+                        max_x - min_x + 1 + trailing_space
+                    } else {
+                        max_x - min_x + 1 + args.trim_pad.unwrap_or(1)
+                    };
+                    let scaled_width = trimmed_width as f64 * trim_scale;
+                    if scaled_width.fract() != 0.0 {
+                        rounded_advances.borrow_mut().push(codepoint);
+                    }
+                    let scaled_width = scaled_width.round() as u32;
+                    (scaled_width * SCALE, 0)
+                } else {
+                    // a synthetic bold widens the cell along with its ink
+                    (
+                        (glyph_width + synthetic_bold) * SCALE,
+                        (min_x as i32 - bleed as i32) * SCALE as i32,
+                    )
+                }
+            };
+            // the leftmost ink relative to the cell, which is negative in the bleed
+            let leading_space = |bitmap: &[f64]| {
+                ink_extent(bitmap, read_width, read_height).0 as i32 - bleed as i32
+            };
+            let mut min_x = leading_space(&bitmap);
+            let mut metric = metrics(&bitmap);
+            if let Some((pivot_x, _)) = pivot {
+                if !trim {
+                    metric.0 = (glyph_width + synthetic_bold).saturating_sub(pivot_x) * SCALE;
+                }
+                metric.1 = (min_x - pivot_x as i32) * SCALE as i32;
+            } else if keep_trailing_space && min_x != 0 {
+                placement = Affine::translate((-((min_x * SCALE as i32) as f64), 0.0)) * placement;
+                min_x = 0;
+            }
+            let mut glyph_name = format!("U+{:04X}", codepoint);
+            // the texture of the cell is drawn as it is, which doesn't work for mirrored glyphs
+            let cell = |placement: Affine| {
+                (!mirror).then(|| GlyphCell {
+                    sheet: sheet_index,
+                    x: x0,
+                    y: y0,
+                    width: glyph_width,
+                    height: glyph_height,
+                    // the cell starts after the bleed in the traced outline
+                    origin: placement
+                        * Point::new(
+                            (bleed * SCALE) as f64,
+                            ((glyph_height as i32 - base_line as i32) * SCALE as i32) as f64,
+                        ),
+                    pixels: (0..glyph_height)
+                        .flat_map(|y| (0..glyph_width).map(move |x| (x, y)))
+                        .map(|(x, y)| image.get_pixel(x0 + x, y0 + y).0)
+                        .collect(),
+                })
+            };
+
+            if !components.is_empty() {
+                ligature_glyphs.push((sheet_name.clone(), components.clone(), glyphs.len() as u16));
+                color_glyphs.push((glyphs.len() as u16, colors, placement));
+                path.apply_affine(placement);
+                glyphs.push(GlyphData {
+                    name: sheet_name.clone(),
+                    path,
+                    advance: metric.0,
+                    lsb: metric.1,
+                    cell: cell(placement),
+                });
+                continue;
+            }
+            if let Some(suffix) = role.and_then(LayerRole::feature) {
+                figure_variants.push((codepoint, glyphs.len() as u16, role));
+                color_glyphs.push((glyphs.len() as u16, colors, placement));
+                path.apply_affine(placement);
+                glyphs.push(GlyphData {
+                    name: format!("{}.{}", glyph_name, suffix),
+                    path,
+                    advance: metric.0,
+                    lsb: metric.1,
+                    cell: cell(placement),
+                });
+                continue;
+            }
+
+            // per-glyph overrides from the config
+            if let Some(glyph_override) = glyph_overrides.get(&codepoint) {
+                used_overrides.insert(codepoint);
+                let mut dx = 0;
+                if let Some(left_bearing) = glyph_override.left_bearing {
+                    dx = left_bearing - min_x;
+                    metric.1 = left_bearing * SCALE as i32;
+                }
+                let dy = glyph_override.baseline_offset.unwrap_or(0);
+                if dx != 0 || dy != 0 {
+                    placement =
+                        Affine::translate(((dx * SCALE as i32) as f64, (dy * SCALE as i32) as f64))
+                            * placement;
+                }
+                if let Some(advance) = glyph_override.advance {
+                    metric.0 = advance * SCALE;
+                }
+                if let Some(name) = &glyph_override.name {
+                    glyph_name = name.clone();
+                }
+            }
+            // the advances file takes the place of the advance from the config
+            let advance = advances.get(&codepoint);
+            if let Some(advance) = advance {
+                metric.0 = advance * SCALE;
+            }
+
+            // combining marks take no advance and are moved back by the advance they would have
+            // as a letter, so that a mark drawn in place over a letter of its cell stacks on the
+            // glyph before it. That is one cell, or the trimmed advance when trimming
+            let glyph_override = glyph_overrides.get(&codepoint);
+            let combining = glyph_override
+                .and_then(|glyph_override| glyph_override.combining)
+                .unwrap_or(
+                    role == Some(LayerRole::Combining) || COMBINING_MARKS.contains(&codepoint),
+                );
+            if combining {
+                let letter_advance = if trim {
+                    metrics(&bitmap).0
+                } else {
+                    glyph_width * SCALE
+                } as i32;
+                placement = Affine::translate((-letter_advance as f64, 0.0)) * placement;
+                metric.1 -= letter_advance;
+                // an advance from the config or the advances file still applies
+                if glyph_override
+                    .and_then(|glyph_override| glyph_override.advance)
+                    .or(advance.copied())
+                    .is_none()
+                {
+                    metric.0 = 0;
+                }
+            }
+
+            path.apply_affine(placement);
+            let cell = cell(placement);
+            // a lookalike such as Cyrillic А after Latin A is mapped to the glyph that is already
+            // drawn, unless the config names it or it ends up placed or colored differently
+            let renamed =
+                glyph_override.is_some_and(|glyph_override| glyph_override.name.is_some());
+            let bitmap_key = (share_glyphs && !renamed).then(|| {
+                bitmap
+                    .iter()
+                    .map(|coverage| coverage.to_bits())
+                    .collect::<Vec<u64>>()
+            });
+            let shared = bitmap_key
+                .as_ref()
+                .and_then(|bitmap_key| glyphs_by_bitmap.get(bitmap_key))
+                .copied()
+                .filter(|&glyph_id| {
+                    let glyph = &glyphs[glyph_id as usize];
+                    glyph.path == path
+                        && glyph.advance == metric.0
+                        && glyph.lsb == metric.1
+                        && glyph.cell.as_ref().map(|cell| &cell.pixels)
+                            == cell.as_ref().map(|cell| &cell.pixels)
+                        && color_glyphs
+                            .iter()
+                            .find(|(id, _, _)| *id == glyph_id)
+                            .is_some_and(|(_, shared_colors, shared_placement)| {
+                                *shared_colors == colors && *shared_placement == placement
+                            })
+                });
+            if let Some(glyph_id) = shared {
+                cmap_entries.push((codepoint, glyph_id));
+            } else {
+                if let Some(bitmap_key) = bitmap_key {
+                    glyphs_by_bitmap
+                        .entry(bitmap_key)
+                        .or_insert(glyphs.len() as u16);
+                }
+                cmap_entries.push((codepoint, glyphs.len() as u16));
+                color_glyphs.push((glyphs.len() as u16, colors, placement));
+                glyphs.push(GlyphData {
+                    name: glyph_name,
+                    path,
+                    advance: metric.0,
+                    lsb: metric.1,
+                    cell,
+                });
+            }
+
+            // a mirrored copy in the Private Use Area, flipped relative to the glyph above
+            if mirror_pua {
+                let mirrored_code = MIRROR_PUA_START + codepoint;
+                if mirrored_code > MIRROR_PUA_END {
+                    return Err(Error::new(format!(
+                        "U+{:04X} can't be mirrored into the Private Use Area. Only codepoints up to U+{:04X} can be mirrored.",
+                        codepoint,
+                        MIRROR_PUA_END - MIRROR_PUA_START
+                    )));
+                }
+                let bitmap = mirror_bitmap(&bitmap, read_width, read_height);
+                let (mut path, point, _) = trace(&bitmap, read_width, read_height, read_base_line);
+                // the same outline as the glyph above, so it fits wherever that one did
+                if let Some((simplified_path, _)) = args
+                    .max_points
+                    .filter(|&max_points| point > max_points)
+                    .and_then(|max_points| simplify(&path, max_points))
+                {
+                    path = simplified_path;
+                }
+                let dx = if keep_trailing_space {
+                    -leading_space(&bitmap)
+                } else {
+                    0
+                };
+                path.apply_affine(
+                    Affine::translate((
+                        (dx * SCALE as i32) as f64,
+                        (sheet_offset * SCALE as i32) as f64,
+                    )) * bleed_offset,
+                );
+                let metric = metrics(&bitmap);
+                mirrored_codes.push(mirrored_code);
+                cmap_entries.push((mirrored_code, glyphs.len() as u16));
+                glyphs.push(GlyphData {
+                    name: format!("U+{:04X}", mirrored_code),
+                    path,
+                    advance: metric.0,
+                    lsb: metric.1,
+                    cell: None,
+                });
+            }
+        }
+
+        // the cells after the last glyph are only the rest of the sheet, unless the layer
+        // declares where its range ends
+        if let Some(last_code) = end_code.or(last_drawn).filter(|_| {
+            emit_blank_glyphs
+                && role.and_then(LayerRole::feature).is_none()
+                && components.is_empty()
+        }) {
+            blank_cells.extend(
+                sheet_blank_cells
+                    .into_iter()
+                    .filter(|&(codepoint, _)| codepoint <= last_code),
+            );
+        }
+    }
+
+    // blank cells get an empty glyph, unless their codepoint is drawn elsewhere
+    for (codepoint, cell_width) in blank_cells {
+        if cmap_entries.iter().any(|(c, _)| *c == codepoint) {
+            continue;
+        }
+        cmap_entries.push((codepoint, glyphs.len() as u16));
+        glyphs.push(GlyphData {
+            name: format!("U+{:04X}", codepoint),
+            path: BezPath::new(),
+            advance: cell_width * SCALE,
+            lsb: 0,
+            cell: None,
+        });
+    }
+
+    for mirrored_code in mirrored_codes {
+        if cmap_entries
+            .iter()
+            .filter(|(c, _)| *c == mirrored_code)
+            .count()
+            > 1
+        {
+            return Err(Error::new(format!(
+                "U+{:04X} is drawn in the file, so it can't be used for a mirrored glyph.",
+                mirrored_code
+            )));
+        }
+    }
+
+    // aliases map more codepoints to a glyph that is drawn once
+    for (codepoint, aliases) in config.aliases()? {
+        let Some(&(_, glyph_id)) = cmap_entries.iter().find(|(c, _)| *c == codepoint) else {
+            return Err(Error::new(format!(
+                "Invalid config: U+{:04X} has aliases but no glyph.",
+                codepoint
+            )));
+        };
+        for alias in aliases {
+            if cmap_entries.iter().any(|(c, _)| *c == alias) {
+                return Err(Error::new(format!(
+                    "U+{:04X} already has a glyph, so it can't be an alias of U+{:04X}.",
+                    alias, codepoint
+                )));
+            }
+            cmap_entries.push((alias, glyph_id));
+        }
+    }
+
+    // match each alternate with the default glyph at the same position
+    let mut figure_pairs = vec![];
+    for (codepoint, glyph_id, role) in figure_variants {
+        let Some(&(_, default_id)) = cmap_entries.iter().find(|(c, _)| *c == codepoint) else {
+            continue;
+        };
+        figure_pairs.push(if role == Some(LayerRole::Tabular) {
+            (default_id, glyph_id)
+        } else {
+            (glyph_id, default_id)
+        });
+    }
+
+    // each color of a color glyph is drawn by a glyph of its own. They come after every other
+    // glyph, so that the glyph ids don't depend on the color mode
+    let mut palette: Vec<[u8; 3]> = vec![];
+    let mut color_layers = vec![];
+    for (glyph_id, colors, placement) in color_glyphs {
+        if colors.is_empty() {
+            continue;
+        }
+        let base = &glyphs[glyph_id as usize];
+        let (name, advance, lsb) = (base.name.clone(), base.advance, base.lsb);
+        let x_min = base.path.bounding_box().x0;
+        let mut layers = vec![];
+        for (i, (rgb, mut path)) in colors.into_iter().enumerate() {
+            let palette_index = palette
+                .iter()
+                .position(|&entry| entry == rgb)
+                .unwrap_or_else(|| {
+                    palette.push(rgb);
+                    palette.len() - 1
+                });
+            path.apply_affine(placement);
+            layers.push((glyphs.len() as u16, palette_index as u16));
+            glyphs.push(GlyphData {
+                name: format!("{}.color{}", name, i),
+                // the side bearing keeps its distance to the outline, as in the base glyph
+                lsb: lsb + (path.bounding_box().x0 - x_min).round() as i32,
+                path,
+                advance,
+                cell: None,
+            });
+        }
+        color_layers.push((glyph_id, layers));
+    }
+    let mut palettes = vec![];
+    if !palette.is_empty() {
+        for (i, colors) in config.palettes()?.into_iter().enumerate() {
+            if let Some(color) = colors.keys().find(|color| !palette.contains(color)) {
+                return Err(Error::new(format!(
+                    "Invalid config: palette {} replaces #{:02X}{:02X}{:02X}, which no glyph is drawn in.",
+                    i + 1,
+                    color[0],
+                    color[1],
+                    color[2]
+                )));
+            }
+            palettes.push(
+                palette
+                    .iter()
+                    .map(|color| colors.get(color).copied().unwrap_or(*color))
+                    .collect(),
+            );
+        }
+        palettes.insert(0, palette);
+    }
+
+    // a ligature replaces the glyphs of its components, so each of them has to be drawn
+    let mut ligatures = vec![];
+    for (name, components, glyph_id) in ligature_glyphs {
+        let component_ids = components
+            .iter()
+            .map(|&codepoint| {
+                cmap_entries
+                    .iter()
+                    .find(|(c, _)| *c == codepoint)
+                    .map(|&(_, glyph_id)| glyph_id)
+                    .ok_or_else(|| {
+                        Error::new(format!(
+                            "The ligature {} joins U+{:04X}, which has no glyph.",
+                            name, codepoint
+                        ))
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        ligatures.push((component_ids, glyph_id));
+    }
+
+    let mut missing_overrides: Vec<_> = glyph_overrides
+        .keys()
+        .filter(|codepoint| !used_overrides.contains(codepoint))
+        .collect();
+    if !missing_overrides.is_empty() {
+        missing_overrides.sort();
+        return Err(Error::new(format!(
+            "The config has glyph entries for codepoints that are not in the file: {}",
+            missing_overrides
+                .iter()
+                .map(|codepoint| format!("U+{:04X}", codepoint))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )));
+    }
+
+    let skipped_advances = advances
+        .keys()
+        .filter(|&&codepoint| !cmap_entries.iter().any(|&(c, _)| c == codepoint))
+        .map(|&codepoint| SkippedAdvance { codepoint })
+        .collect();
+
+    if glyphs.len() <= 3 {
+        return Err(if use_tags {
+            Error::NoValidTags
+        } else {
+            Error::NoValidLayers
+        });
+    }
+
+    // spacing tracks the whole font, except for the marks that are drawn over another glyph
+    if let Some(spacing) = args.spacing {
+        for glyph in glyphs.iter_mut().filter(|glyph| glyph.advance != 0) {
+            glyph.advance += spacing * SCALE;
+        }
+    }
+
+    // a mirrored copy is rounded along with its glyph
+    let mut rounded_codepoints = rounded_advances.into_inner();
+    let rounded_glyphs = rounded_codepoints.len();
+    rounded_codepoints.sort();
+    rounded_codepoints.dedup();
+    let uneven_trim_scale = (rounded_glyphs > 0).then_some(UnevenTrimScale {
+        ratio: trim_ratio,
+        glyphs: rounded_glyphs,
+        codepoints: rounded_codepoints,
+    });
+
+    Ok(FontData {
+        family,
+        postscript_name,
+        vendor_id,
+        localized_names,
+        sprite_size: (width, height),
+        sheet_count,
+        glyph_width,
+        glyph_height,
+        units_per_em,
+        units_per_pixel: SCALE,
+        ascender,
+        descender,
+        glyphs,
+        cmap_entries,
+        figure_pairs,
+        ligatures,
+        palettes,
+        color_layers,
+        cap_height,
+        max_point,
+        max_contour_count,
+        simplified,
+        uneven_trim_scale,
+        ignored_layers,
+        heavy_master: None,
+        kerning: vec![],
+        skipped_kerning: vec![],
+        skipped_advances,
+    })
+}
+
+/// Characters that PostScript names can't contain, next to spaces and anything that isn't
+/// printable ASCII.
+const POSTSCRIPT_DELIMITERS: &str = "[](){}<>/%";
+
+/// The longest PostScript name that every PostScript interpreter accepts.
+const MAX_POSTSCRIPT_NAME_LEN: usize = 63;
+
+/// The PostScript name that was given, or else the family with its spaces turned into hyphens and
+/// other characters that aren't allowed left out.
+fn postscript_name(args: &Params, family: &str) -> Result<String, Error> {
+    let allowed = |c: char| c.is_ascii_graphic() && !POSTSCRIPT_DELIMITERS.contains(c);
+    if let Some(name) = &args.postscript_name {
+        if name.is_empty() || name.len() > MAX_POSTSCRIPT_NAME_LEN || !name.chars().all(allowed) {
+            return Err(Error::new(format!(
+                "The PostScript name '{}' must be 1 to {} printable ASCII characters without spaces or any of {}.",
+                name, MAX_POSTSCRIPT_NAME_LEN, POSTSCRIPT_DELIMITERS
+            )));
+        }
+        return Ok(name.clone());
+    }
+    let name: String = family
+        .replace(' ', "-")
+        .chars()
+        .filter(|&c| allowed(c))
+        .take(MAX_POSTSCRIPT_NAME_LEN)
+        .collect();
+    // a family made only of special characters has nothing left
+    Ok(if name.is_empty() {
+        "Untitled".to_string()
+    } else {
+        name
+    })
+}
+
+/// The vendor tag, padded with spaces to four characters.
+fn vendor_id(args: &Params) -> Result<[u8; 4], Error> {
+    let Some(vendor_id) = &args.vendor_id else {
+        return Ok(*b"    ");
+    };
+    if vendor_id.is_empty()
+        || vendor_id.len() > 4
+        || !vendor_id.bytes().all(|b| (0x20..=0x7E).contains(&b))
+    {
+        return Err(Error::new(format!(
+            "The vendor ID '{}' must be 1 to 4 printable ASCII characters.",
+            vendor_id
+        )));
+    }
+    let mut tag = *b"    ";
+    tag[..vendor_id.len()].copy_from_slice(vendor_id.as_bytes());
+    Ok(tag)
+}
+
+/// Calls `f` with the index of every cell of a sheet, on all cores with the `parallel` feature,
+/// and returns the results in the order of the cells.
+fn map_cells<T: Send>(count: u32, f: impl Fn(u32) -> T + Sync + Send) -> Vec<T> {
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        (0..count).into_par_iter().map(f).collect()
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        (0..count).map(f).collect()
+    }
+}
+
+/// Traces the outlines of a cell, returning the path with its point and contour counts.
+fn trace(
+    bitmap: &[f64],
+    glyph_width: u32,
+    glyph_height: u32,
+    base_line: i16,
+) -> (BezPath, u16, u16) {
+    let mut point: u16 = 0;
+    let mut contour_count: u16 = 0;
+    let mut path = BezPath::new();
+
+    let (w, h) = (glyph_width as usize, glyph_height as usize);
+    let contours = match filled_rect_path(bitmap, w, h) {
+        Some(rect) => vec![rect],
+        None => get_edges(bitmap, w, h)
+            .values()
+            .flat_map(edges_to_paths)
+            .collect(),
+    };
+    for path_points in contours {
+        // a straight run of pixel edges only needs the points at its ends
+        let path_points = drop_collinear(
+            path_points
+                .iter()
+                .map(|&(x, y)| (x as f64, y as f64))
+                .collect(),
+        );
+        if path_points.is_empty() {
+            continue;
+        }
+        let y_offset = base_line as f64 * SCALE as f64;
+        let to_font_point = |&(x, y): &(f64, f64)| {
+            (
+                x * SCALE as f64,
+                (glyph_height as f64 - y) * SCALE as f64 - y_offset,
+            )
+        };
+        let mut iter = path_points.iter();
+        if let Some(start) = iter.next() {
+            path.move_to(to_font_point(start));
+            for p in iter {
+                path.line_to(to_font_point(p));
+                point += 1;
+            }
+            // the contour returns to its start before closing, which is counted instead of the
+            // start itself
+            path.line_to(to_font_point(start));
+            point += 1;
+            path.close_path();
+            contour_count += 1;
+        }
+    }
+    (path, point, contour_count)
+}
+
+/// Traces a cell drawn at `ratio` times the size of its grid. Each block of pixels is averaged into
+/// the coverage of one grid cell, so the outlines get the smooth edges of the detailed drawing.
+fn trace_downscaled(
+    bitmap: &[f64],
+    glyph_width: u32,
+    glyph_height: u32,
+    base_line: i16,
+    ratio: u32,
+) -> (BezPath, u16, u16) {
+    let (w, h) = (
+        (glyph_width / ratio) as usize,
+        (glyph_height / ratio) as usize,
+    );
+    let mut coverage = vec![0.0f64; w * h];
+    for y in 0..glyph_height {
+        for x in 0..glyph_width {
+            if bitmap[(y * glyph_width + x) as usize] > 0.0 {
+                coverage[(y / ratio) as usize * w + (x / ratio) as usize] += 1.0;
+            }
+        }
+    }
+    let area = (ratio * ratio) as f64;
+    for value in coverage.iter_mut() {
+        *value /= area;
+    }
+
+    let mut point: u16 = 0;
+    let mut contour_count: u16 = 0;
+    let mut path = BezPath::new();
+
+    // back at the size of the drawing, so the metrics don't depend on the ratio
+    let scale = (ratio * SCALE) as f64;
+    let y_offset = base_line as f64 * SCALE as f64;
+    let to_font = |(x, y): (f64, f64)| {
+        (
+            (x * scale).round(),
+            ((h as f64 - y) * scale - y_offset).round(),
+        )
+    };
+    for contour in trace_coverage(&coverage, w, h) {
+        let mut iter = contour.into_iter();
+        if let Some(first) = iter.next() {
+            path.move_to(to_font(first));
+            point += 1;
+            for p in iter {
+                path.line_to(to_font(p));
+                point += 1;
+            }
+            path.close_path();
+            contour_count += 1;
+        }
+    }
+    (path, point, contour_count)
+}
+
+/// Traces a hollow box for `.notdef`, one pixel narrower than its advance and standing on the
+/// baseline. A box too small to have a hole is filled.
+fn notdef_box(advance: u32, glyph_height: u32, base_line: i16) -> (BezPath, u16, u16) {
+    let width = advance.saturating_sub(1);
+    let height = (glyph_height as i64 - base_line as i64).clamp(0, glyph_height as i64) as u32;
+    if width == 0 || height == 0 {
+        return (BezPath::new(), 0, 0);
+    }
+    let mut bitmap = vec![0.0f64; (width * glyph_height) as usize];
+    for y in 0..height {
+        for x in 0..width {
+            let edge = x == 0 || y == 0 || x == width - 1 || y == height - 1;
+            if edge || width < 3 || height < 3 {
+                bitmap[(y * width + x) as usize] = 1.0;
+            }
+        }
+    }
+    trace(&bitmap, width, glyph_height, base_line)
+}
+
+/// The lowest alpha of a pixel that counts as ink. A threshold of 0 would fill the whole cell, so
+/// it counts as 1.
+pub(crate) fn alpha_threshold(args: &Params) -> u8 {
+    args.alpha_threshold.unwrap_or(128).max(1)
+}
+
+/// Returns the leftmost and rightmost columns with ink, or `(glyph_width, 0)` for an empty cell.
+fn ink_extent(bitmap: &[f64], glyph_width: u32, glyph_height: u32) -> (u32, u32) {
+    let mut min_x = glyph_width;
+    let mut max_x = 0;
+    for y in 0..glyph_height {
+        for x in 0..glyph_width {
+            if bitmap[(y * glyph_width + x) as usize] > 0.0 {
+                min_x = min_x.min(x);
+                max_x = max_x.max(x);
+            }
+        }
+    }
+    (min_x, max_x)
+}
+
+/// Widens the ink of a cell by `radius` pixels to the right, which makes a bolder weight that keeps
+/// the baseline and the height of the glyph. Each pixel takes the strongest coverage of itself and
+/// the pixels to its left.
+fn dilate_bitmap(bitmap: &[f64], glyph_width: u32, radius: u32) -> Vec<f64> {
+    let glyph_width = glyph_width as usize;
+    let mut dilated = bitmap.to_vec();
+    for (row, dilated_row) in bitmap
+        .chunks(glyph_width)
+        .zip(dilated.chunks_mut(glyph_width))
+    {
+        for (x, coverage) in dilated_row.iter_mut().enumerate() {
+            let from = x.saturating_sub(radius as usize);
+            *coverage = row[from..=x].iter().copied().fold(0.0, f64::max);
+        }
+    }
+    dilated
+}
+
+/// Flips a cell horizontally. Tracing the flipped cell keeps the contours correctly wound.
+fn mirror_bitmap(bitmap: &[f64], glyph_width: u32, glyph_height: u32) -> Vec<f64> {
+    let mut mirrored = Vec::with_capacity(bitmap.len());
+    for y in 0..glyph_height {
+        for x in (0..glyph_width).rev() {
+            mirrored.push(bitmap[(y * glyph_width + x) as usize]);
+        }
+    }
+    mirrored
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum LayerRole {
+    /// The outer contour of a glyph.
+    Outline,
+    /// Ink inside the outline of the layer with the same codepoint.
+    Fill,
+    /// Tabular alternates of the glyphs with the same codepoints.
+    Tabular,
+    /// Proportional alternates of the glyphs with the same codepoints.
+    Proportional,
+    /// Combining marks, which are given no advance.
+    Combining,
+}
+
+impl LayerRole {
+    /// The OpenType feature that selects glyphs of this role, for alternate glyphs.
+    fn feature(self) -> Option<&'static str> {
+        match self {
+            LayerRole::Tabular => Some("tnum"),
+            LayerRole::Proportional => Some("pnum"),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the pivots of the slices on a frame, as the top-left corners of the pivot pixels.
+fn slice_pivots(ase: &AsepriteFile, frame: u32) -> Vec<(u32, u32)> {
+    ase.slices()
+        .iter()
+        .filter_map(|slice| {
+            // a key applies from its frame until the next key
+            let key = slice
+                .keys
+                .iter()
+                .rev()
+                .find(|key| key.from_frame <= frame)?;
+            let (x, y) = key.pivot?;
+            let x = u32::try_from(key.origin.0 + x).ok()?;
+            let y = u32::try_from(key.origin.1 + y).ok()?;
+            Some((x, y))
+        })
+        .collect()
+}
+
+/// Reads the role suffix of a layer name such as `U+0041:outline`, `U+0030:tnum` or
+/// `U+0300:combining`.
+fn layer_role(name: &str) -> Option<LayerRole> {
+    match name.rsplit_once(':')?.1 {
+        "outline" => Some(LayerRole::Outline),
+        "fill" => Some(LayerRole::Fill),
+        "tnum" => Some(LayerRole::Tabular),
+        "pnum" => Some(LayerRole::Proportional),
+        "combining" => Some(LayerRole::Combining),
+        _ => None,
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use crate::font::FontData;
+use crate::{Error, parse_codepoint, to_font_units};
+
+/// A kerning pair that was left out of the font, because one of its codepoints has no glyph.
+#[derive(Debug, PartialEq)]
+pub struct SkippedKerningPair {
+    pub left: u32,
+    pub right: u32,
+    /// The codepoints of the pair that have no glyph.
+    pub missing: Vec<u32>,
+}
+
+impl Display for SkippedKerningPair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let missing = self
+            .missing
+            .iter()
+            .map(|codepoint| format!("U+{:04X}", codepoint))
+            .collect::<Vec<_>>()
+            .join(" and ");
+        write!(
+            f,
+            "the kerning pair U+{:04X} U+{:04X} was skipped, as {} has no glyph",
+            self.left, self.right, missing
+        )
+    }
+}
+
+/// Reads a kerning file, where each line is a left and a right character followed by the
+/// adjustment in pixels, e.g. `A V -1`. Characters can also be written as `U+XXXX`.
+fn parse_kerning(source: &str) -> Result<Vec<(u32, u32, i16)>, Error> {
+    let mut pairs = vec![];
+    for (i, line) in source.lines().enumerate() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.is_empty() {
+            continue;
+        }
+        let invalid = |reason: String| {
+            Error::new(format!("Invalid kerning file: line {}: {}", i + 1, reason))
+        };
+        let [left, right, adjustment] = fields[..] else {
+            return Err(invalid(format!(
+                "expected a left and a right character and an adjustment, but found '{}'.",
+                line.trim()
+            )));
+        };
+        let codepoint = |field: &str| {
+            let mut chars = field.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Ok(c as u32),
+                _ => parse_codepoint(field).ok_or_else(|| {
+                    invalid(format!(
+                        "'{}' is not a character or a codepoint such as U+0041.",
+                        field
+                    ))
+                }),
+            }
+        };
+        let adjustment = adjustment.parse::<i16>().map_err(|_| {
+            invalid(format!(
+                "'{}' is not an adjustment in whole pixels.",
+                adjustment
+            ))
+        })?;
+        pairs.push((codepoint(left)?, codepoint(right)?, adjustment));
+    }
+    Ok(pairs)
+}
+
+/// Maps the pairs of a kerning file to the glyphs of the font, in font units. A pair that is
+/// listed twice takes the later adjustment.
+pub(crate) fn apply_kerning(font: &mut FontData, source: &str) -> Result<(), Error> {
+    let glyph_id = |codepoint: u32| {
+        font.cmap_entries
+            .iter()
+            .find(|(c, _)| *c == codepoint)
+            .map(|&(_, glyph_id)| glyph_id)
+    };
+    let mut kerning = BTreeMap::new();
+    let mut skipped = vec![];
+    for (left, right, adjustment) in parse_kerning(source)? {
+        match (glyph_id(left), glyph_id(right)) {
+            (Some(left), Some(right)) => {
+                let adjustment = to_font_units(adjustment as i64, font.units_per_pixel)?;
+                kerning.insert((left, right), adjustment);
+            }
+            (left_id, right_id) => {
+                let mut missing: Vec<u32> = [(left, left_id), (right, right_id)]
+                    .into_iter()
+                    .filter(|(_, glyph_id)| glyph_id.is_none())
+                    .map(|(codepoint, _)| codepoint)
+                    .collect();
+                missing.dedup();
+                skipped.push(SkippedKerningPair {
+                    left,
+                    right,
+                    missing,
+                });
+            }
+        }
+    }
+    font.kerning = kerning
+        .into_iter()
+        .map(|((left, right), adjustment)| (left, right, adjustment))
+        .collect();
+    font.skipped_kerning = skipped;
+    Ok(())
+}
@@ -1,38 +1,55 @@
-use asefile::AsepriteFile;
-use chrono::Utc;
-use kurbo::BezPath;
-use std::cmp;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Display};
+use std::fs;
+use std::iter;
+use std::ops::DerefMut;
 use std::path::Path;
-use write_fonts::tables::cmap::{Cmap, CmapSubtable, EncodingRecord};
-use write_fonts::tables::glyf::{GlyfLocaBuilder, Glyph};
-use write_fonts::tables::hhea::Hhea;
-use write_fonts::tables::hmtx::Hmtx;
-use write_fonts::tables::maxp::Maxp;
-use write_fonts::tables::os2::{Os2, SelectionFlags};
-use write_fonts::tables::post::Post;
-use write_fonts::tables::vmtx::LongMetric;
-use write_fonts::types::{FWord, Tag, UfWord};
-use write_fonts::{
-    OffsetMarker,
-    tables::{
-        cmap::PlatformId,
-        glyf::SimpleGlyph,
-        head::{Head, MacStyle},
-        name::{Name, NameRecord},
-    },
-    types::{Fixed, LongDateTime, NameId},
-};
+
+use asefile::{AsepriteFile, AsepriteParseError, Layer, LayerType};
 
 #[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
-use crate::edge::get_edges;
-
+pub use crate::advance::SkippedAdvance;
+pub use crate::builder::ParamsBuilder;
+pub use crate::coverage::CoverageMismatch;
+use crate::font::{FontData, build_font};
+pub use crate::font::{IgnoredLayer, UnevenTrimScale};
+pub use crate::kerning::SkippedKerningPair;
+pub use crate::metrics::{FontMetrics, GlyphMetrics};
+pub use crate::report::{LayerReport, SheetReport};
+pub use crate::simplify::SimplifiedGlyph;
+use crate::ttf::build_ttf;
+pub use crate::ttf::{EmbeddingLevel, LocalizedName};
+pub use crate::warning::{Warning, WarningKind};
+
+mod advance;
+mod builder;
+mod cache;
+mod code_page;
+mod config;
+mod corner;
+mod coverage;
 mod edge;
+mod fnt;
+mod font;
+mod kerning;
+mod merge;
+mod metrics;
+mod report;
+mod sbix;
+mod sfd;
+mod simplify;
+mod svg;
+mod ttf;
+mod unicode_range;
+mod variable;
+mod warning;
+mod woff;
 #[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
+#[derive(Default)]
 pub struct Params {
-    pub file_path: String,
+    pub file_path: Option<String>,
     pub copyright: Option<String>,
     pub family: Option<String>,
     pub subfamily: Option<String>,
@@ -42,18 +59,101 @@ pub struct Params {
     pub glyph_height: Option<u32>,
     pub trim: Option<bool>,
     pub trim_pad: Option<u32>,
-    pub line_gap: Option<u8>,
+    pub line_gap: Option<i16>,
     pub spacing: Option<u32>,
+    /// Deprecated: the same as `descent`, which it is kept for.
     pub baseline: Option<i16>,
     pub underline_position: Option<i16>,
     pub underline_thickness: Option<i16>,
+    pub use_tags: Option<bool>,
+    pub config: Option<String>,
+    pub web_optimized: Option<bool>,
+    pub variable: Option<bool>,
+    /// The size in pixels that the font is designed for, recorded in the `size` feature. The
+    /// feature holds points, which the pixels are converted to at 96 dpi.
+    pub pixel_size: Option<f32>,
+    pub margin_top: Option<u32>,
+    pub margin_bottom: Option<u32>,
+    pub mirror: Option<bool>,
+    pub mirror_pua: Option<bool>,
+    pub family_class: Option<i16>,
+    pub cap_height_reference: Option<u32>,
+    pub downscale: Option<u32>,
+    pub name_ids: Option<Vec<u16>>,
+    pub device_metrics: Option<Vec<u8>>,
+    pub italic_angle: Option<f32>,
+    pub max_points: Option<u16>,
+    pub bleed: Option<u32>,
+    pub clamp_trim_scale: Option<bool>,
+    pub max_weight: Option<u16>,
+    pub keep_trailing_space: Option<bool>,
+    pub emit_blank_glyphs: Option<bool>,
+    pub units_per_em: Option<u16>,
+    pub corner_radius: Option<f64>,
+    pub created_timestamp: Option<i64>,
+    /// The pixels of each cell above the baseline. The descent is the rest of the cell.
+    pub ascent: Option<i16>,
+    /// The pixels of each cell below the baseline, 2 by default.
+    pub descent: Option<i16>,
+    pub kerning: Option<String>,
+    /// The fewest points that an outline needs to be shared by glyphs that repeat it, as
+    /// composite glyphs. 0 writes every outline in full, which is the default.
+    pub composite_threshold: Option<u16>,
+    /// Draws each color of a glyph as a layer of a COLR color glyph, with the colors in CPAL.
+    pub color: Option<bool>,
+    /// Embeds the pixels of each glyph as a bitmap at the native size, next to the outlines.
+    pub embed_bitmaps: Option<bool>,
+    /// The advance of the space in pixels, instead of the width of a glyph or
+    /// `[reserved.space]` in the config.
+    pub space_width: Option<u32>,
+    /// The contents of an advances file, with a codepoint and its advance in pixels on each line,
+    /// e.g. `0041 9`. The advances take the place of the trimmed or fixed ones.
+    pub advances: Option<String>,
+    /// The characters of the layers that aren't named after a codepoint. Each drawn cell takes the
+    /// next character, continuing from one layer to the next.
+    pub charset: Option<String>,
+    /// The lowest alpha that counts as ink, from 1 to 255. Fainter pixels, such as the edges of
+    /// anti-aliased art, are left empty. Defaults to 128.
+    pub alpha_threshold: Option<u8>,
+    /// Reads the layers that are hidden in Aseprite, or are in a hidden group, which are left out
+    /// by default.
+    pub include_hidden: Option<bool>,
+    /// The pixels between neighboring cells of the sheet, for sheets with a line drawn between
+    /// the cells. There is no margin around the edges of the sheet. Regions keep their cells
+    /// packed.
+    pub cell_margin: Option<u32>,
+    /// The vendor tag of the OS/2 table, of 1 to 4 printable ASCII characters. It is padded with
+    /// spaces, and is all spaces by default.
+    pub vendor_id: Option<String>,
+    /// How applications may embed the font in documents. Installable by default.
+    pub embedding: Option<EmbeddingLevel>,
+    /// The PostScript name of the font, of up to 63 printable ASCII characters other than
+    /// `[](){}<>/%` and spaces. By default it is the family with spaces replaced by hyphens and
+    /// other characters left out.
+    pub postscript_name: Option<String>,
+    /// Maps codepoints whose cells are drawn exactly alike, such as Latin `A` and Cyrillic `А`,
+    /// to the glyph of the first of them instead of giving each its own. Ignored for variable
+    /// fonts.
+    pub share_glyphs: Option<bool>,
+    /// Widens the ink of every glyph by this many pixels to the right, for a bold weight that
+    /// doesn't have to be drawn. Only the width grows, not the height. The advances grow to fit,
+    /// the weight class is at least 700 and the subfamily is Bold unless another one is given.
+    /// The pixels of the cells aren't widened, so it can't be used with embedded bitmaps or
+    /// BMFont output.
+    pub synthetic_bold: Option<u32>,
+    /// The family and subfamily names in other languages, keyed by Windows language ID such as
+    /// `0x0411` for Japanese. They take the place of the `[[names]]` of the config in the same
+    /// language, and English (`0x0409`) can't be given, as its names are the family and subfamily.
+    /// JavaScript can only give them in the config.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub localized_names: Option<HashMap<u16, LocalizedName>>,
 }
 
 #[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl Params {
     #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
     pub fn new(
-        file_path: String,
+        file_path: Option<String>,
         copyright: Option<String>,
         family: Option<String>,
         subfamily: Option<String>,
@@ -63,13 +163,65 @@ impl Params {
         glyph_height: Option<u32>,
         trim: Option<bool>,
         trim_pad: Option<u32>,
-        line_gap: Option<u8>,
+        line_gap: Option<i16>,
         spacing: Option<u32>,
         baseline: Option<i16>,
         underline_position: Option<i16>,
         underline_thickness: Option<i16>,
+        use_tags: Option<bool>,
+        config: Option<String>,
+        web_optimized: Option<bool>,
+        variable: Option<bool>,
+        pixel_size: Option<f32>,
+        margin_top: Option<u32>,
+        margin_bottom: Option<u32>,
+        mirror: Option<bool>,
+        mirror_pua: Option<bool>,
+        family_class: Option<i16>,
+        cap_height_reference: Option<u32>,
+        downscale: Option<u32>,
+        name_ids: Option<Vec<u16>>,
+        device_metrics: Option<Vec<u8>>,
+        italic_angle: Option<f32>,
+        max_points: Option<u16>,
+        bleed: Option<u32>,
+        clamp_trim_scale: Option<bool>,
+        max_weight: Option<u16>,
+        keep_trailing_space: Option<bool>,
+        emit_blank_glyphs: Option<bool>,
+        units_per_em: Option<u16>,
+        corner_radius: Option<f64>,
+        created_timestamp: Option<i64>,
+        ascent: Option<i16>,
+        descent: Option<i16>,
+        kerning: Option<String>,
+        composite_threshold: Option<u16>,
+        color: Option<bool>,
+        embed_bitmaps: Option<bool>,
+        space_width: Option<u32>,
+        advances: Option<String>,
+        charset: Option<String>,
+        alpha_threshold: Option<u8>,
+        include_hidden: Option<bool>,
+        cell_margin: Option<u32>,
+        vendor_id: Option<String>,
+        embedding: Option<EmbeddingLevel>,
+        postscript_name: Option<String>,
+        share_glyphs: Option<bool>,
+        synthetic_bold: Option<u32>,
     ) -> Params {
-        Params {
+        // the positional arguments are for JavaScript, which has no builder
+        let mut builder = Params::builder();
+        macro_rules! set {
+            ($($name:ident,)*) => {
+                $(
+                    if let Some(value) = $name {
+                        builder = builder.$name(value);
+                    }
+                )*
+            };
+        }
+        set! {
             file_path,
             copyright,
             family,
@@ -85,557 +237,564 @@ impl Params {
             baseline,
             underline_position,
             underline_thickness,
+            use_tags,
+            config,
+            web_optimized,
+            variable,
+            pixel_size,
+            margin_top,
+            margin_bottom,
+            mirror,
+            mirror_pua,
+            family_class,
+            cap_height_reference,
+            downscale,
+            name_ids,
+            device_metrics,
+            italic_angle,
+            max_points,
+            bleed,
+            clamp_trim_scale,
+            max_weight,
+            keep_trailing_space,
+            emit_blank_glyphs,
+            units_per_em,
+            corner_radius,
+            created_timestamp,
+            ascent,
+            descent,
+            kerning,
+            composite_threshold,
+            color,
+            embed_bitmaps,
+            space_width,
+            advances,
+            charset,
+            alpha_threshold,
+            include_hidden,
+            cell_margin,
+            vendor_id,
+            embedding,
+            postscript_name,
+            share_glyphs,
+            synthetic_bold,
         }
+        builder.build()
     }
 }
 
-#[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
-pub struct Error {
-    pub message: String,
+/// The reason a font couldn't be generated.
+#[non_exhaustive]
+pub enum Error {
+    /// The Aseprite file couldn't be read.
+    AseRead(AsepriteParseError),
+    /// The file at `path` couldn't be opened.
+    FileRead {
+        path: String,
+        source: std::io::Error,
+    },
+    /// The sprite can't be split into whole glyph cells, with `cell_margin` pixels between them.
+    DimensionMismatch {
+        width: u32,
+        height: u32,
+        glyph_width: u32,
+        glyph_height: u32,
+        cell_margin: u32,
+    },
+    /// No layer is named after a codepoint.
+    NoValidLayers,
+    /// No tag is named after a codepoint, when codepoints are assigned through tags.
+    NoValidTags,
+    /// A font table couldn't be written.
+    TableBuild(String),
+    /// Any other problem with the drawing, the parameters or the config.
+    Other(String),
 }
 
 impl Error {
+    /// Creates an error that is only described by its message.
     pub fn new(message: String) -> Error {
-        Error { message: message }
+        Error::Other(message)
     }
 }
 
 impl Debug for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(&self.message)
+        Display::fmt(self, f)
     }
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(&self.message)
+        match self {
+            Error::AseRead(e) => Display::fmt(e, f),
+            Error::FileRead { path, source } => write!(f, "Failed to read {}: {}", path, source),
+            Error::DimensionMismatch {
+                width,
+                height,
+                glyph_width,
+                glyph_height,
+                cell_margin,
+            } => {
+                if *cell_margin == 0 {
+                    f.write_str(
+                        "The height and width of the aseprite file must be multiples of glyph-width and glyph-height respectively.",
+                    )
+                } else {
+                    write!(
+                        f,
+                        "The {}x{} sheet doesn't fit a whole number of {}x{} cells with a margin of {} between them.",
+                        width, height, glyph_width, glyph_height, cell_margin
+                    )
+                }
+            }
+            Error::NoValidLayers => f.write_str(
+                "No valid layer found. Parsable layer names must start with U+ and be valid Unicode.",
+            ),
+            Error::NoValidTags => f.write_str(
+                "No valid tag found. Parsable tag names must start with U+ and be valid Unicode.",
+            ),
+            Error::TableBuild(message) | Error::Other(message) => f.write_str(message),
+        }
     }
 }
 
 impl std::error::Error for Error {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        None
-    }
-
-    fn description(&self) -> &str {
-        "description() is deprecated; use Display"
+        match self {
+            Error::AseRead(e) => Some(e),
+            Error::FileRead { source, .. } => Some(source),
+            _ => None,
+        }
     }
+}
 
-    fn cause(&self) -> Option<&dyn std::error::Error> {
-        self.source()
+#[cfg(feature = "wasm")]
+impl From<Error> for JsValue {
+    fn from(error: Error) -> JsValue {
+        JsError::new(&error.to_string()).into()
     }
 }
 
-#[cfg(feature = "wasm")]
-#[wasm_bindgen]
-pub fn generate_ttf_js(ase_bytes: &[u8], args: Params) -> Result<Vec<u8>, JsValue> {
-    generate_ttf(ase_bytes, args).map_err(|x| x.into())
+/// Number of font units per source pixel, unless `units_per_em` sets another em size.
+pub(crate) const SCALE: u32 = 64;
+
+/// The largest unitsPerEm allowed by the head table.
+pub(crate) const MAX_UNITS_PER_EM: u32 = 16384;
+
+/// Converts a length in pixels to font units, failing instead of wrapping when the result
+/// doesn't fit in the 16-bit fields used by the font tables.
+pub(crate) fn to_font_units(pixels: i64, units_per_pixel: u32) -> Result<i16, Error> {
+    i16::try_from(pixels * units_per_pixel as i64).map_err(|_| {
+        Error::new(format!(
+            "{} pixels is too large to be represented in font units.",
+            pixels
+        ))
+    })
 }
 
-pub fn generate_ttf(ase_bytes: &[u8], args: Params) -> Result<Vec<u8>, Error> {
-    let ase = AsepriteFile::read(ase_bytes).map_err(|e| Error::new(e.to_string()))?;
-
-    // params
-    let glyph_width = args.glyph_width.unwrap_or(16);
-    let glyph_height = args.glyph_height.unwrap_or(16);
-    let base_line = args.baseline.unwrap_or(2);
-    let line_gap = args.line_gap.unwrap_or(0);
-    let size = cmp::max(glyph_width, glyph_height);
-    let file_stem = Path::new(&args.file_path)
-        .file_stem()
-        .unwrap()
-        .to_str()
-        .unwrap()
-        .to_string();
-    const SCALE: u32 = 64;
-
-    // validate size
-    let width = ase.width() as u32;
-    let height = ase.height() as u32;
-    if width % glyph_width != 0 || height % glyph_height != 0 {
-        return Err(Error::new(
-            "The height and width of the aseprite file must be multiples of glyph-width and glyph-height respectively.".to_string())
-        );
+/// The pixels of each cell below the baseline, from the descent, the ascent or the deprecated
+/// baseline.
+pub(crate) fn descent(args: &Params) -> Result<i16, Error> {
+    let glyph_height = args.glyph_height.unwrap_or(16) as i32;
+    let descent = match (args.descent, args.baseline) {
+        (Some(descent), Some(baseline)) if descent != baseline => {
+            return Err(Error::new(format!(
+                "The baseline {} is the deprecated name of the descent, so it can't differ from the descent {}.",
+                baseline, descent
+            )));
+        }
+        (descent, baseline) => descent.or(baseline),
+    };
+    let Some(ascent) = args.ascent else {
+        return Ok(descent.unwrap_or(2));
+    };
+    if !(0..=glyph_height).contains(&(ascent as i32)) {
+        return Err(Error::new(format!(
+            "The ascent {} must be between 0 and the glyph height {}.",
+            ascent, glyph_height
+        )));
+    }
+    let from_ascent = glyph_height - ascent as i32;
+    match descent {
+        Some(descent) if descent as i32 != from_ascent => Err(Error::new(format!(
+            "The ascent {} and the descent {} must add up to the glyph height {}.",
+            ascent, descent, glyph_height
+        ))),
+        _ => i16::try_from(from_ascent).map_err(|_| {
+            Error::new(format!(
+                "The descent {} below the ascent {} is too large.",
+                from_ascent, ascent
+            ))
+        }),
     }
+}
 
-    let mut builder = write_fonts::FontBuilder::new();
-
-    // build glyph
-    let mut glyf_builder = GlyfLocaBuilder::new();
-    let mut cmap_entries = vec![];
-    let mut glyph_metrics = vec![];
-    let mut glyph_names = vec![];
-    let mut glyph_count = 0;
-    let mut max_point: u16 = 0;
-    let mut max_contour_count: u16 = 0;
-
-    // add .notdef / null / space
-    for _ in 0..3 {
-        glyf_builder.add_glyph(&SimpleGlyph::default()).unwrap();
-        glyph_metrics.push((args.spacing.unwrap_or(glyph_width) * SCALE, 0));
+pub(crate) fn parse_codepoint(name: &str) -> Option<u32> {
+    if !(name.starts_with("U+") || name.starts_with("u+")) {
+        return None;
     }
-    glyph_names.push(".notdef".to_string());
-    glyph_names.push("null".to_string());
-    glyph_names.push("space".to_string());
-    cmap_entries.push((0x0000, 1)); // null
-    cmap_entries.push((0x0020, 2)); // space
-    glyph_count += 3;
-
-    for layer in ase.layers() {
-        let image = layer.frame(0).image();
-        let name = layer.name();
-        let base_code = if name.starts_with("U+") || name.starts_with("u+") {
-            let hex_part: String = name[2..]
-                .chars()
-                .take_while(|c| c.is_ascii_hexdigit())
-                .collect();
-            if let Ok(s) = u32::from_str_radix(&hex_part, 16) {
-                s
-            } else {
-                continue;
+    let hex_part: String = name[2..]
+        .chars()
+        .take_while(|c| c.is_ascii_hexdigit())
+        .collect();
+    u32::from_str_radix(&hex_part, 16).ok()
+}
+
+/// Parses a layer name that starts with a codepoint and may declare the last codepoint of its
+/// range, e.g. `U+0041-U+005A` or `U+0041-005A`. A name like `U+0020-` has no explicit end.
+pub(crate) fn parse_codepoint_range(name: &str) -> Option<(u32, Option<u32>)> {
+    let start = parse_codepoint(name)?;
+    let rest = name[2..].trim_start_matches(|c: char| c.is_ascii_hexdigit());
+    let end = rest.strip_prefix('-').and_then(|end| {
+        parse_codepoint(end).or_else(|| {
+            let hex_part: String = end.chars().take_while(|c| c.is_ascii_hexdigit()).collect();
+            u32::from_str_radix(&hex_part, 16).ok()
+        })
+    });
+    Some((start, end))
+}
+
+/// Parses the name of a ligature layer, which joins its characters with underscores, e.g. `f_i`
+/// or `f_f_l`. A character can also be written as `U+XXXX`.
+pub(crate) fn parse_ligature(name: &str) -> Option<Vec<u32>> {
+    let components = name
+        .split('_')
+        .map(|part| {
+            let mut chars = part.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(c as u32),
+                _ => parse_codepoint(part)
+                    .filter(|_| part[2..].chars().all(|c| c.is_ascii_hexdigit())),
             }
-        } else {
-            continue;
-        };
-
-        let cols = width / glyph_width;
-        let rows = height / glyph_height;
-        for row in 0..rows {
-            for col in 0..cols {
-                let x0 = col * glyph_width;
-                let y0 = row * glyph_height;
-
-                let mut bitmap = vec![0.0f64; (glyph_width * glyph_height) as usize];
-                for y in 0..glyph_height {
-                    for x in 0..glyph_width {
-                        let px = x0 + x;
-                        let py = y0 + y;
-                        if px >= width || py >= height {
-                            continue;
-                        }
-                        let pixel = image.get_pixel(px, py);
-                        bitmap[(y * glyph_width + x) as usize] = pixel[3] as f64 / 255.0;
-                    }
-                }
+        })
+        .collect::<Option<Vec<u32>>>()?;
+    (components.len() > 1).then_some(components)
+}
 
-                let mut point: u16 = 0;
-                let mut contour_count: u16 = 0;
-                let mut path = BezPath::new();
-
-                let boundaries = get_edges(&bitmap, glyph_width as usize, glyph_height as usize);
-                for edges in boundaries.values() {
-                    let paths = crate::edge::edges_to_paths(edges);
-                    for path_points in paths {
-                        if path_points.is_empty() {
-                            continue;
-                        }
-                        let mut iter = path_points.iter();
-                        if let Some(&(x0, y0)) = iter.next() {
-                            let scale_usize = SCALE as usize;
-                            let y_offset = (base_line * SCALE as i16) as f64;
-                            path.move_to((
-                                (x0 * scale_usize) as f64,
-                                ((glyph_height as usize - y0) * scale_usize) as f64 - y_offset,
-                            ));
-                            for &(x, y) in iter {
-                                path.line_to((
-                                    (x * scale_usize) as f64,
-                                    ((glyph_height as usize - y) * scale_usize) as f64 - y_offset,
-                                ));
-                                point += 1;
-                            }
-                            path.close_path();
-                            contour_count += 1;
-                        }
-                    }
-                }
+/// Returns the layers that hold pixels. The children of a group are listed right after it, so
+/// grouped layers are read like any other.
+fn image_layers<'a>(ase: &'a AsepriteFile, args: &Params) -> impl Iterator<Item = Layer<'a>> {
+    let include_hidden = args.include_hidden.unwrap_or(false);
+    ase.layers()
+        .filter(|layer| !matches!(layer.layer_type(), LayerType::Group))
+        .filter(move |layer| include_hidden || is_shown(layer))
+}
 
-                if point == 0 {
-                    continue;
-                }
+/// Returns the layers to read glyphs from, each with the layers whose pixels it is made of. A
+/// group named after a codepoint, none of whose layers are, is read as a single layer, so that a
+/// glyph can be built from a shadow and a fill. Every other layer is made of itself.
+pub(crate) fn glyph_layers<'a>(
+    ase: &'a AsepriteFile,
+    args: &Params,
+) -> Vec<(Layer<'a>, Vec<Layer<'a>>)> {
+    let is_glyph =
+        |name: &str| parse_codepoint_range(name).is_some() || parse_ligature(name).is_some();
+    // the nearest group named after a codepoint that each layer is in
+    let group_of = |layer: &Layer<'a>| {
+        iter::successors(layer.parent().map(|group| ase.layer(group.id())), |group| {
+            group.parent().map(|group| ase.layer(group.id()))
+        })
+        .find(|group| parse_codepoint_range(group.name()).is_some())
+    };
+    let layers: Vec<Layer<'a>> = image_layers(ase, args).collect();
+    let groups: Vec<Option<u32>> = layers
+        .iter()
+        .map(|layer| group_of(layer).map(|group| group.id()))
+        .collect();
+    let members = |group: u32| {
+        layers
+            .iter()
+            .zip(&groups)
+            .filter(move |(_, other)| **other == Some(group))
+            .map(|(layer, _)| layer)
+    };
 
-                glyf_builder
-                    .add_glyph(&Glyph::Simple(SimpleGlyph::from_bezpath(&path).unwrap()))
-                    .unwrap();
-                let codepoint = base_code + (row * cols + col) as u32;
-                cmap_entries.push((codepoint, glyph_count));
-                glyph_count += 1;
-                glyph_names.push(format!("U+{:04X}", codepoint));
-
-                max_point = if point > max_point { point } else { max_point };
-                max_contour_count = if contour_count > max_contour_count {
-                    contour_count
-                } else {
-                    max_contour_count
-                };
-
-                let mut min_x = glyph_width;
-                let mut max_x = 0;
-                for y in 0..glyph_height {
-                    for x in 0..glyph_width {
-                        let px = x0 + x;
-                        let py = y0 + y;
-                        if px >= width || py >= height {
-                            continue;
-                        }
-                        let pixel = image.get_pixel(px, py);
-                        if pixel[3] != 0 {
-                            if x < min_x {
-                                min_x = x;
-                            }
-                            if x > max_x {
-                                max_x = x;
-                            }
-                        }
-                    }
+    let mut glyph_layers = vec![];
+    let mut read_groups = HashSet::new();
+    for (layer, group) in layers.iter().zip(&groups) {
+        let group = group.filter(|&group| !members(group).any(|layer| is_glyph(layer.name())));
+        match group {
+            // a group takes the place of its first layer
+            Some(group) => {
+                if read_groups.insert(group) {
+                    let members = members(group).map(|layer| ase.layer(layer.id())).collect();
+                    glyph_layers.push((ase.layer(group), members));
                 }
+            }
+            None => glyph_layers.push((ase.layer(layer.id()), vec![ase.layer(layer.id())])),
+        }
+    }
+    glyph_layers
+}
 
-                if args.trim.unwrap_or(true) {
-                    let trimmed_width = if min_x > max_x {
-                        0
-                    } else {
-                        max_x - min_x + 1 + args.trim_pad.unwrap_or(1)
-                    };
-                    let scaled_width =
-                        ((trimmed_width as f64) * (size / glyph_width) as f64).round() as u32;
-                    glyph_metrics.push((scaled_width * SCALE, 0));
-                } else {
-                    glyph_metrics.push((glyph_width * SCALE, min_x * SCALE));
-                }
+/// Whether a layer and every group it is in are visible.
+fn is_shown(layer: &Layer) -> bool {
+    layer.is_visible() && layer.parent().is_none_or(|parent| is_shown(&parent))
+}
+
+/// The opacity of a layer, faded by the opacity of the groups it is in.
+fn layer_opacity(layer: &Layer) -> u8 {
+    let parent = layer.parent().map_or(255, |parent| layer_opacity(&parent));
+    (layer.opacity() as u32 * parent as u32 / 255) as u8
+}
+
+/// Fades the alpha of RGBA pixels by the opacity of their layer, so that a faint layer falls below
+/// the alpha threshold like a faint pixel does.
+fn apply_layer_opacity(pixels: &mut [u8], layer: &Layer) {
+    let opacity = layer_opacity(layer) as u32;
+    if opacity == 255 {
+        return;
+    }
+    for pixel in pixels.chunks_exact_mut(4) {
+        pixel[3] = (pixel[3] as u32 * opacity / 255) as u8;
+    }
+}
+
+/// Stacks the images of layers from the bottom up, each faded by its opacity. Every layer is
+/// blended as if its blend mode were normal, which is all that matters for the alpha that is
+/// traced.
+pub(crate) fn composite_layers<I: DerefMut<Target = [u8]>>(
+    layers: &[Layer],
+    image: impl Fn(&Layer) -> I,
+) -> I {
+    let mut below = image(&layers[0]);
+    apply_layer_opacity(&mut below, &layers[0]);
+    for layer in &layers[1..] {
+        let mut above = image(layer);
+        apply_layer_opacity(&mut above, layer);
+        for (dst, src) in below.chunks_exact_mut(4).zip(above.chunks_exact(4)) {
+            let (src_alpha, dst_alpha) = (src[3] as u32, dst[3] as u32);
+            if src_alpha == 0 {
+                continue;
+            }
+            let dst_weight = dst_alpha * (255 - src_alpha) / 255;
+            let alpha = src_alpha + dst_weight;
+            for i in 0..3 {
+                dst[i] = ((src[i] as u32 * src_alpha + dst[i] as u32 * dst_weight) / alpha) as u8;
             }
+            dst[3] = alpha as u8;
         }
     }
+    below
+}
 
-    if glyph_count <= 3 {
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn generate_ttf_js(ase_bytes: &[u8], args: Params) -> Result<Vec<u8>, JsValue> {
+    generate_ttf(ase_bytes, args).map_err(|x| x.into())
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn generate_woff_js(ase_bytes: &[u8], args: Params) -> Result<Vec<u8>, JsValue> {
+    generate_woff(ase_bytes, args).map_err(|x| x.into())
+}
+
+#[cfg(all(feature = "wasm", feature = "woff2"))]
+#[wasm_bindgen]
+pub fn generate_woff2_js(ase_bytes: &[u8], args: Params) -> Result<Vec<u8>, JsValue> {
+    generate_woff2(ase_bytes, args).map_err(|x| x.into())
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn generate_sfd_js(ase_bytes: &[u8], args: Params) -> Result<String, JsValue> {
+    generate_sfd(ase_bytes, args).map_err(|x| x.into())
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn generate_svg_js(ase_bytes: &[u8], args: Params) -> Result<String, JsValue> {
+    generate_svg(ase_bytes, args).map_err(|x| x.into())
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn generate_fnt_js(ase_bytes: &[u8], args: Params) -> Result<String, JsValue> {
+    generate_fnt(ase_bytes, args).map_err(|x| x.into())
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn merge_ttf_js(existing: &[u8], ase_bytes: &[u8], args: Params) -> Result<Vec<u8>, JsValue> {
+    merge_ttf(existing, ase_bytes, args).map_err(|x| x.into())
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn generation_warnings_js(ase_bytes: &[u8], args: &Params) -> Result<Vec<Warning>, JsValue> {
+    generation_warnings(ase_bytes, args).map_err(|x| x.into())
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn font_cache_key_js(ase_bytes: &[u8], args: &Params) -> String {
+    font_cache_key(ase_bytes, args)
+}
+
+pub fn generate_ttf(ase_bytes: &[u8], args: Params) -> Result<Vec<u8>, Error> {
+    let font = build_font(ase_bytes, &args)?;
+    build_ttf(&font, &args)
+}
+
+/// Reads the Aseprite file at `path` and generates its font, with `file_path` set to the path.
+pub fn generate_ttf_from_path(path: impl AsRef<Path>, args: Params) -> Result<Vec<u8>, Error> {
+    let (ase_bytes, args) = read_ase_file(path, args)?;
+    generate_ttf(&ase_bytes, args)
+}
+
+/// Reads the Aseprite file at `path` for the `generate_*` functions, returning its bytes together
+/// with the params, whose `file_path` is set to the path.
+pub fn read_ase_file(path: impl AsRef<Path>, mut args: Params) -> Result<(Vec<u8>, Params), Error> {
+    let path = path.as_ref();
+    let ase_bytes = fs::read(path).map_err(|source| Error::FileRead {
+        path: path.display().to_string(),
+        source,
+    })?;
+    args.file_path = Some(path.to_string_lossy().into_owned());
+    Ok((ase_bytes, args))
+}
+
+/// Generates the font of `generate_ttf`, together with the warnings of `generation_warnings`
+/// about it, without building the font twice.
+pub fn generate_ttf_with_warnings(
+    ase_bytes: &[u8],
+    args: Params,
+) -> Result<(Vec<u8>, Vec<Warning>), Error> {
+    let font = build_font(ase_bytes, &args)?;
+    let bytes = build_ttf(&font, &args)?;
+    Ok((bytes, collect_warnings(font, ase_bytes, &args)?))
+}
+
+/// Generates the font of `generate_ttf` in a WOFF container, which browsers load directly.
+pub fn generate_woff(ase_bytes: &[u8], args: Params) -> Result<Vec<u8>, Error> {
+    let ttf = generate_ttf(ase_bytes, args)?;
+    woff::build_woff(&ttf)
+}
+
+/// Generates the font of `generate_ttf` in a WOFF2 container, which is smaller than WOFF.
+#[cfg(feature = "woff2")]
+pub fn generate_woff2(ase_bytes: &[u8], args: Params) -> Result<Vec<u8>, Error> {
+    let ttf = generate_ttf(ase_bytes, args)?;
+    woff::build_woff2(&ttf)
+}
+
+/// Generates a FontForge SFD file with the traced outlines, for further editing in FontForge.
+pub fn generate_sfd(ase_bytes: &[u8], args: Params) -> Result<String, Error> {
+    let font = build_font(ase_bytes, &args)?;
+    Ok(sfd::build_sfd(&font, &args))
+}
+
+/// Generates an SVG font document with the traced outlines, for SVG-based rendering pipelines.
+pub fn generate_svg(ase_bytes: &[u8], args: Params) -> Result<String, Error> {
+    let font = build_font(ase_bytes, &args)?;
+    Ok(svg::build_svg(&font, &args))
+}
+
+/// Returns an SVG document with the traced outline of each codepoint, in font units, for checking
+/// the winding of contours and holes without opening the font in an editor.
+pub fn export_glyphs_svg(ase_bytes: &[u8], args: &Params) -> Result<HashMap<u32, String>, Error> {
+    let font = build_font(ase_bytes, args)?;
+    Ok(svg::build_glyph_svgs(&font))
+}
+
+/// Generates an AngelCode BMFont descriptor that draws the glyphs from the sprite, for game
+/// engines that render bitmap fonts.
+pub fn generate_fnt(ase_bytes: &[u8], args: Params) -> Result<String, Error> {
+    // the glyphs are drawn from the cells of the sprite, which a synthetic bold doesn't widen
+    if args.synthetic_bold.unwrap_or(0) > 0 {
         return Err(Error::new(
-            "No valid layer found. Parsable layer names must start with U+ and be valid Unicode."
-                .to_string(),
+            "BMFont output can't be used with a synthetic bold, as its glyphs are drawn from the cells of the sprite as they are.".to_string(),
         ));
     }
+    let font = build_font(ase_bytes, &args)?;
+    Ok(fnt::build_fnt(&font, &args))
+}
 
-    // head table
-    let head = Head::new(
-        Fixed::from(0),
-        0,
-        0b0000000000001011,
-        (size * SCALE) as u16,
-        LongDateTime::new(Utc::now().timestamp()),
-        LongDateTime::new(Utc::now().timestamp()),
-        0,
-        -(base_line * SCALE as i16),
-        (glyph_width * SCALE) as i16,
-        (glyph_height as i16 - base_line) * SCALE as i16,
-        MacStyle::empty(),
-        8,
-        0,
-    );
-    builder
-        .add_table(&head)
-        .map_err(|e| Error::new(e.to_string()))?;
-
-    // name table
-    let family = args.family.unwrap_or(file_stem.clone());
-    let mut name_records = Vec::new();
-    for i in 0..2 {
-        let platform_id = match i {
-            0 => PlatformId::Macintosh,
-            1 => PlatformId::Windows,
-            _ => unreachable!(),
-        };
-
-        let encoding_id = match platform_id {
-            PlatformId::Macintosh => 0,
-            PlatformId::Windows => 1,
-            _ => unreachable!(),
-        };
-
-        // english
-        let language_id = match platform_id {
-            PlatformId::Macintosh => 0,
-            PlatformId::Windows => 0x0409,
-            _ => unreachable!(),
-        };
-
-        // 0: copyright
-        if let Some(copyright) = args.copyright.clone() {
-            name_records.push(NameRecord {
-                platform_id: platform_id as u16,
-                encoding_id: encoding_id,
-                language_id: language_id,
-                name_id: NameId::from(0),
-                string: OffsetMarker::new(copyright),
-            });
-        }
+/// Adds the glyphs of an Aseprite file to an existing TrueType font, for building a font up over
+/// several sheets. Glyphs for codepoints the font already has replace the old ones, and the
+/// other glyphs and tables of the font are kept.
+pub fn merge_ttf(existing: &[u8], ase_bytes: &[u8], args: Params) -> Result<Vec<u8>, Error> {
+    let font = build_font(ase_bytes, &args)?;
+    merge::merge_ttf(existing, &font)
+}
 
-        // 1: font family name
-        name_records.push(NameRecord {
-            platform_id: platform_id as u16,
-            encoding_id: encoding_id,
-            language_id: language_id,
-            name_id: NameId::from(1),
-            string: OffsetMarker::new(if let Some(subfamily) = args.subfamily.clone() {
-                format!("{} {}", family, subfamily)
-            } else {
-                family.clone()
-            }),
-        });
-
-        // 2: subfamily name
-        name_records.push(NameRecord {
-            platform_id: platform_id as u16,
-            encoding_id: encoding_id,
-            language_id: language_id,
-            name_id: NameId::from(2),
-            string: OffsetMarker::new(args.subfamily.clone().unwrap_or("Regular".to_string())),
-        });
-
-        // 3: identifier
-        name_records.push(NameRecord {
-            platform_id: platform_id as u16,
-            encoding_id: encoding_id,
-            language_id: language_id,
-            name_id: NameId::from(3),
-            string: OffsetMarker::new(format!("ase2ttf: {}", family.clone())),
-        });
-
-        // 4: font name
-        name_records.push(NameRecord {
-            platform_id: platform_id as u16,
-            encoding_id: encoding_id,
-            language_id: language_id,
-            name_id: NameId::from(4),
-            string: OffsetMarker::new(family.clone()),
-        });
-
-        // 5: version
-        name_records.push(NameRecord {
-            platform_id: platform_id as u16,
-            encoding_id: encoding_id,
-            language_id: language_id,
-            name_id: NameId::from(5),
-            string: OffsetMarker::new(
-                args.font_version
-                    .clone()
-                    .unwrap_or("Version 1.0".to_string()),
-            ),
-        });
-
-        // 6: PostScript name
-        name_records.push(NameRecord {
-            platform_id: platform_id as u16,
-            encoding_id: encoding_id,
-            language_id: language_id,
-            name_id: NameId::from(6),
-            string: OffsetMarker::new(family.replace(" ", "-")),
-        });
-
-        // 16: Preferred family
-        name_records.push(NameRecord {
-            platform_id: platform_id as u16,
-            encoding_id: encoding_id,
-            language_id: language_id,
-            name_id: NameId::from(16),
-            string: OffsetMarker::new(family.clone()),
-        });
-
-        // 17: Preferred subfamily
-        name_records.push(NameRecord {
-            platform_id: platform_id as u16,
-            encoding_id: encoding_id,
-            language_id: language_id,
-            name_id: NameId::from(17),
-            string: OffsetMarker::new(args.subfamily.clone().unwrap_or("Regular".to_string())),
-        });
-    }
+/// Returns a hash of the Aseprite file and every parameter, which changes whenever the generated
+/// font could. Build tools can store it next to the output and skip regeneration while it matches.
+pub fn font_cache_key(ase_bytes: &[u8], args: &Params) -> String {
+    cache::cache_key(ase_bytes, args)
+}
 
-    let name = Name::new(name_records);
-    builder
-        .add_table(&name)
-        .map_err(|e| Error::new(e.to_string()))?;
-
-    // OS/2 table
-    let os2 = Os2 {
-        x_avg_char_width: (glyph_width as i16),
-        us_weight_class: if let Some(weight_class) = args.font_weight {
-            weight_class
-        } else {
-            match args
-                .subfamily
-                .as_deref()
-                .unwrap_or("regular")
-                .to_lowercase()
-                .as_str()
-            {
-                "thin" => 100,
-                "extra-light" | "extralight" | "ultra-light" | "ultralight" => 200,
-                "light" => 300,
-                "regular" => 400,
-                "medium" => 500,
-                "semibold" | "semi-bold" | "demi-bold" | "demibold" => 600,
-                "bold" => 700,
-                "extrabold" | "extra-bold" | "ultrabold" | "ultra-bold" => 800,
-                "black" | "heavy" => 900,
-                _ => 400,
-            }
-        },
-        us_width_class: 5,
-        fs_type: 0b0000_0000_0000_0000,
-        y_subscript_x_size: (glyph_width * SCALE / 2) as i16,
-        y_subscript_y_size: (glyph_height * SCALE / 2) as i16,
-        y_subscript_x_offset: 0,
-        y_subscript_y_offset: (glyph_height * SCALE / 2) as i16,
-        y_superscript_x_size: (glyph_width * SCALE / 2) as i16,
-        y_superscript_y_size: (glyph_height * SCALE / 2) as i16,
-        y_superscript_x_offset: 0,
-        y_superscript_y_offset: (glyph_height * SCALE / 2) as i16,
-        y_strikeout_size: 1,
-        y_strikeout_position: (glyph_height * SCALE / 2) as i16,
-        s_family_class: 0,
-        panose_10: [0; 10],
-        ul_unicode_range_1: 0,
-        ul_unicode_range_2: 0,
-        ul_unicode_range_3: 0,
-        ul_unicode_range_4: 0,
-        ach_vend_id: Tag::from_u32(0),
-        fs_selection: SelectionFlags::empty(),
-        us_first_char_index: 0x0020,
-        us_last_char_index: 0xFFFF,
-        s_typo_ascender: (glyph_height as i16 - base_line) * SCALE as i16,
-        s_typo_descender: -base_line * SCALE as i16,
-        s_typo_line_gap: 0,
-        us_win_ascent: ((glyph_height as i16 - base_line) * SCALE as i16) as u16,
-        us_win_descent: (base_line * SCALE as i16) as u16,
-        ul_code_page_range_1: Default::default(),
-        ul_code_page_range_2: Default::default(),
-        sx_height: Default::default(),
-        s_cap_height: Default::default(),
-        us_default_char: Default::default(),
-        us_break_char: Default::default(),
-        us_max_context: Default::default(),
-        us_lower_optical_point_size: Default::default(),
-        us_upper_optical_point_size: Default::default(),
-    };
-    builder
-        .add_table(&os2)
-        .map_err(|e| Error::new(e.to_string()))?;
-
-    // maxp table
-    let maxp = Maxp {
-        num_glyphs: glyph_count,
-        max_points: Some(max_point),
-        max_contours: Some(max_contour_count),
-        max_composite_points: Some(0),
-        max_composite_contours: Some(0),
-        max_zones: Some(2),
-        max_twilight_points: Some(0),
-        max_storage: Some(1),
-        max_function_defs: Some(1),
-        max_instruction_defs: Some(0),
-        max_stack_elements: Some((glyph_width * glyph_height) as u16),
-        max_size_of_instructions: Some(0),
-        max_component_elements: Some(0),
-        max_component_depth: Some(0),
-    };
-    builder
-        .add_table(&maxp)
-        .map_err(|e| Error::new(e.to_string()))?;
-
-    // post table
-    let glyph_name_refs: Vec<&str> = glyph_names.iter().map(|s| s.as_str()).collect();
-    let mut post = Post::new_v2(glyph_name_refs);
-    post.underline_position = FWord::new(args.underline_position.unwrap_or(0) * SCALE as i16);
-    post.underline_thickness = FWord::new(args.underline_thickness.unwrap_or(1) * SCALE as i16);
-    post.is_fixed_pitch = if args.trim.unwrap_or(false) { 0 } else { 1 };
-    builder
-        .add_table(&post)
-        .map_err(|e| Error::new(e.to_string()))?;
-
-    // cmap table
-    let mut start_code = Vec::new();
-    let mut end_code = Vec::new();
-    let mut id_delta = Vec::new();
-    let mut id_range_offsets = Vec::new();
-    let glyph_id_array = Vec::new();
-    for (codepoint, glyph_id) in &cmap_entries {
-        let unicode = *codepoint as u16;
-        start_code.push(unicode);
-        end_code.push(unicode);
-        id_delta.push((*glyph_id as i32 - unicode as i32) as i16);
-        id_range_offsets.push(0);
-    }
-    start_code.push(0xFFFF);
-    end_code.push(0xFFFF);
-    id_delta.push(1);
-    id_range_offsets.push(0);
-
-    let subtable = CmapSubtable::format_4(
-        0,
-        end_code,
-        start_code,
-        id_delta,
-        id_range_offsets,
-        glyph_id_array,
-    );
-
-    let cmap = Cmap::new(vec![
-        EncodingRecord {
-            platform_id: PlatformId::Unicode,
-            encoding_id: 3,
-            subtable: OffsetMarker::new(subtable.clone()),
-        },
-        EncodingRecord {
-            platform_id: PlatformId::Macintosh,
-            encoding_id: 0,
-            subtable: OffsetMarker::new(subtable.clone()),
-        },
-        EncodingRecord {
-            platform_id: PlatformId::Windows,
-            encoding_id: 1,
-            subtable: OffsetMarker::new(subtable),
-        },
-    ]);
-    builder
-        .add_table(&cmap)
-        .map_err(|e| Error::new(e.to_string()))?;
-
-    // hhea table
-    let hhea = Hhea::new(
-        ((size as i16 - base_line) * SCALE as i16).into(),
-        (-base_line * SCALE as i16).into(),
-        FWord::new(line_gap as i16 * SCALE as i16),
-        UfWord::new((glyph_width * SCALE) as u16),
-        0.into(),
-        0.into(),
-        FWord::new((glyph_width * SCALE) as i16),
-        1,
-        0,
-        0,
-        glyph_count,
-    );
-    builder
-        .add_table(&hhea)
-        .map_err(|e| Error::new(e.to_string()))?;
-
-    // hmtx table
-    let hmtx = Hmtx::new(
-        glyph_metrics
-            .iter()
-            .map(|x| LongMetric::new(x.0 as u16, x.1 as i16))
-            .collect(),
-        vec![],
-    );
-    builder
-        .add_table(&hmtx)
-        .map_err(|e| Error::new(e.to_string()))?;
-
-    // glyf / loca table
-    let (glyf, loca, _) = glyf_builder.build();
-    builder
-        .add_table(&glyf)
-        .map_err(|e| Error::new(e.to_string()))?;
-    builder
-        .add_table(&loca)
-        .map_err(|e| Error::new(e.to_string()))?;
-
-    Ok(builder.build())
+/// Compares the frames of each layer and reports codepoints that are drawn in some frames but
+/// blank in others, such as a glyph missing from the Bold frame of a multi-weight sheet.
+pub fn check_frame_coverage(
+    ase_bytes: &[u8],
+    args: &Params,
+) -> Result<Vec<CoverageMismatch>, Error> {
+    coverage::check_coverage(ase_bytes, args)
+}
+
+/// Returns the vertical metrics and advances that the font would be generated with, for
+/// checking why text sits higher or lower than expected.
+pub fn font_metrics(ase_bytes: &[u8], args: &Params) -> Result<FontMetrics, Error> {
+    let font = build_font(ase_bytes, args)?;
+    metrics::measure(&font, args)
+}
+
+/// Reads the layers and cells of the sheet like `generate_ttf`, and reports which of them are
+/// drawn instead of writing the font. The cells aren't traced, so the report is made even for a
+/// sheet that no font can be built from yet, such as one without any valid layer.
+pub fn analyze(ase_bytes: &[u8], args: &Params) -> Result<SheetReport, Error> {
+    let (layers, ignored_layers, aliases) = font::analyze_sheets(ase_bytes, args)?;
+    let mut warnings: Vec<Warning> = coverage::check_coverage(ase_bytes, args)?
+        .into_iter()
+        .map(Warning::from)
+        .collect();
+    warnings.extend(ignored_layers.iter().cloned().map(Warning::from));
+    Ok(SheetReport {
+        layers,
+        ignored_layers: ignored_layers.into_iter().map(|layer| layer.name).collect(),
+        aliases,
+        warnings,
+    })
+}
+
+/// Returns the glyphs whose outlines were simplified to fit within `max_points`, with their point
+/// counts before and after.
+pub fn simplified_glyphs(ase_bytes: &[u8], args: &Params) -> Result<Vec<SimplifiedGlyph>, Error> {
+    Ok(build_font(ase_bytes, args)?.simplified)
+}
+
+/// Returns how many trimmed advances were rounded to whole pixels, if the glyph size isn't a
+/// whole multiple of the glyph width. `clamp_trim_scale` avoids the rounding.
+pub fn uneven_trim_scale(
+    ase_bytes: &[u8],
+    args: &Params,
+) -> Result<Option<UnevenTrimScale>, Error> {
+    Ok(build_font(ase_bytes, args)?.uneven_trim_scale)
+}
+
+/// Returns the warnings about the font that would be generated with these parameters, with the
+/// codepoints they affect, so that callers such as the web UI can list them.
+pub fn generation_warnings(ase_bytes: &[u8], args: &Params) -> Result<Vec<Warning>, Error> {
+    let font = build_font(ase_bytes, args)?;
+    collect_warnings(font, ase_bytes, args)
+}
+
+fn collect_warnings(
+    font: FontData,
+    ase_bytes: &[u8],
+    args: &Params,
+) -> Result<Vec<Warning>, Error> {
+    let mut warnings: Vec<Warning> = coverage::check_coverage(ase_bytes, args)?
+        .into_iter()
+        .map(Warning::from)
+        .collect();
+    warnings.extend(font.simplified.into_iter().map(Warning::from));
+    warnings.extend(font.uneven_trim_scale.map(Warning::from));
+    warnings.extend(font.skipped_kerning.into_iter().map(Warning::from));
+    warnings.extend(font.skipped_advances.into_iter().map(Warning::from));
+    warnings.extend(font.ignored_layers.into_iter().map(Warning::from));
+    Ok(warnings)
 }
@@ -0,0 +1,319 @@
+use std::collections::{BTreeMap, HashSet};
+
+use write_fonts::FontBuilder;
+use write_fonts::from_obj::{FromTableRef, ToOwnedTable};
+use write_fonts::read::tables::cmap::{CmapSubtable, PlatformId};
+use write_fonts::read::{FontRef, TableProvider};
+use write_fonts::tables::glyf::{Bbox, GlyfLocaBuilder, Glyph, SimpleGlyph};
+use write_fonts::tables::head::Head;
+use write_fonts::tables::hhea::Hhea;
+use write_fonts::tables::maxp::Maxp;
+use write_fonts::tables::os2::Os2;
+use write_fonts::tables::post::Post;
+use write_fonts::tables::vmtx::LongMetric;
+use write_fonts::types::{FWord, GlyphId, GlyphId16, Tag, UfWord, Version16Dot16};
+
+use crate::Error;
+use crate::code_page::code_page_ranges;
+use crate::font::FontData;
+use crate::ttf::{build_cmap, build_hmtx, char_index_range};
+use crate::unicode_range::unicode_ranges;
+
+/// The tables that are rebuilt for the merged glyphs.
+const REBUILT_TABLES: [&[u8; 4]; 9] = [
+    b"head", b"hhea", b"hmtx", b"maxp", b"post", b"cmap", b"glyf", b"loca", b"OS/2",
+];
+
+/// Tables with a record for every glyph, which would no longer match the glyph count. They are
+/// left out of the merged font.
+const PER_GLYPH_TABLES: [&[u8; 4]; 9] = [
+    b"hdmx", b"LTSH", b"vhea", b"vmtx", b"gvar", b"HVAR", b"VVAR", b"sbix", b"DSIG",
+];
+
+/// The first glyph drawn in the Aseprite file, after .notdef, null and space.
+const FIRST_DRAWN_GLYPH: usize = 3;
+
+fn read_error(e: impl std::fmt::Display) -> Error {
+    Error::new(format!("Failed to read the existing font: {}", e))
+}
+
+/// Adds the glyphs of `font` to an existing TrueType font. Glyphs drawn for codepoints that the
+/// font already has take the place of the old glyphs, and the rest are added after the existing
+/// ones. Existing glyphs keep their ids, so the tables that aren't rebuilt, such as `name` and
+/// the layout tables, are copied as they are. `OS/2` is copied with the ranges of the merged
+/// characters.
+pub(crate) fn merge_ttf(existing: &[u8], font: &FontData) -> Result<Vec<u8>, Error> {
+    let existing = FontRef::new(existing).map_err(read_error)?;
+    let head = existing.head().map_err(read_error)?;
+    if head.units_per_em() as u32 != font.units_per_em {
+        return Err(Error::new(format!(
+            "The existing font has {} units per em, but the new glyphs have {}.",
+            head.units_per_em(),
+            font.units_per_em
+        )));
+    }
+    let (Ok(glyf), Ok(loca)) = (existing.glyf(), existing.loca(None)) else {
+        return Err(Error::new(
+            "The existing font has no TrueType outlines.".to_string(),
+        ));
+    };
+    let hmtx = existing.hmtx().map_err(read_error)?;
+    let post = existing.post().map_err(read_error)?;
+    let existing_count = existing.maxp().map_err(read_error)?.num_glyphs() as usize;
+
+    // the Unicode mappings of the existing font
+    let cmap = existing.cmap().map_err(read_error)?;
+    let mut cmap_entries = BTreeMap::new();
+    for record in cmap.encoding_records() {
+        match record.subtable(cmap.offset_data()).map_err(read_error)? {
+            CmapSubtable::Format4(subtable) => cmap_entries.extend(subtable.iter()),
+            CmapSubtable::Format12(subtable) => cmap_entries.extend(subtable.iter()),
+            _ => {}
+        }
+    }
+    // the last segment of a format 4 subtable maps 0xFFFF to .notdef, which isn't a character
+    let mut cmap_entries: BTreeMap<u32, u16> = cmap_entries
+        .into_iter()
+        .filter(|&(_, glyph_id)| glyph_id.to_u32() != 0)
+        .map(|(codepoint, glyph_id)| (codepoint, glyph_id.to_u32() as u16))
+        .collect();
+
+    // every existing glyph with its name, outline and horizontal metrics
+    let mut names = Vec::with_capacity(existing_count);
+    let mut glyphs = Vec::with_capacity(existing_count);
+    let mut h_metrics = Vec::with_capacity(existing_count);
+    for id in 0..existing_count {
+        let glyph_id = GlyphId::new(id as u32);
+        names.push(
+            post.glyph_name(GlyphId16::new(id as u16))
+                .map(|name| name.to_string())
+                .unwrap_or_else(|| format!("glyph{}", id)),
+        );
+        glyphs.push(match loca.get_glyf(glyph_id, &glyf).map_err(read_error)? {
+            Some(glyph) => Glyph::from_table_ref(&glyph),
+            None => Glyph::Empty,
+        });
+        h_metrics.push(LongMetric::new(
+            hmtx.advance(glyph_id).unwrap_or(0),
+            hmtx.side_bearing(glyph_id).unwrap_or(0),
+        ));
+    }
+
+    // the new glyphs, replacing old glyphs whose codepoints are all drawn again
+    let mut replaced = HashSet::new();
+    for (new_id, glyph) in font.glyphs.iter().enumerate().skip(FIRST_DRAWN_GLYPH) {
+        let codepoints: Vec<u32> = font
+            .cmap_entries
+            .iter()
+            .filter(|&&(_, glyph_id)| glyph_id as usize == new_id)
+            .map(|&(codepoint, _)| codepoint)
+            .collect();
+        // alternates that are only reached through the features of the new font are left out
+        if codepoints.is_empty() {
+            continue;
+        }
+
+        let outline = if glyph.path.elements().is_empty() {
+            Glyph::Empty
+        } else {
+            Glyph::Simple(SimpleGlyph::from_bezpath(&glyph.path).map_err(|e| {
+                Error::new(format!("Failed to build glyph {}: {:?}", glyph.name, e))
+            })?)
+        };
+        let advance = u16::try_from(glyph.advance).map_err(|_| {
+            Error::new(format!(
+                "The advance width of {} font units is too large.",
+                glyph.advance
+            ))
+        })?;
+        let metric = LongMetric::new(advance, glyph.lsb as i16);
+
+        let old_id = cmap_entries.get(&codepoints[0]).copied();
+        let id = match old_id {
+            Some(old_id)
+                if old_id != 0
+                    && !replaced.contains(&old_id)
+                    && cmap_entries
+                        .iter()
+                        .filter(|&(_, &glyph_id)| glyph_id == old_id)
+                        .all(|(codepoint, _)| codepoints.contains(codepoint)) =>
+            {
+                replaced.insert(old_id);
+                let id = old_id as usize;
+                glyphs[id] = outline;
+                h_metrics[id] = metric;
+                names[id] = glyph.name.clone();
+                old_id
+            }
+            _ => {
+                let id = u16::try_from(glyphs.len())
+                    .ok()
+                    .filter(|&id| id < u16::MAX)
+                    .ok_or_else(|| {
+                        Error::new("The merged font has too many glyphs.".to_string())
+                    })?;
+                glyphs.push(outline);
+                h_metrics.push(metric);
+                names.push(glyph.name.clone());
+                id
+            }
+        };
+        for codepoint in codepoints {
+            cmap_entries.insert(codepoint, id);
+        }
+    }
+
+    let mut builder = FontBuilder::new();
+    let glyph_count = glyphs.len() as u16;
+
+    // glyf / loca tables, and the extents of the outlines for head and hhea
+    let mut glyf_builder = GlyfLocaBuilder::new();
+    let mut bbox: Option<Bbox> = None;
+    let mut min_left_side_bearing = i32::MAX;
+    let mut min_right_side_bearing = i32::MAX;
+    let mut x_max_extent = i32::MIN;
+    for (glyph, metric) in glyphs.iter().zip(&h_metrics) {
+        glyf_builder
+            .add_glyph(glyph)
+            .map_err(|e| Error::TableBuild(e.to_string()))?;
+        let glyph_bbox = match glyph {
+            Glyph::Simple(glyph) => glyph.bbox,
+            Glyph::Composite(glyph) => glyph.bbox,
+            Glyph::Empty => continue,
+        };
+        bbox = Some(bbox.map_or(glyph_bbox, |bbox| bbox.union(glyph_bbox)));
+        min_left_side_bearing = min_left_side_bearing.min(glyph_bbox.x_min as i32);
+        min_right_side_bearing =
+            min_right_side_bearing.min(metric.advance as i32 - glyph_bbox.x_max as i32);
+        x_max_extent = x_max_extent.max(glyph_bbox.x_max as i32);
+    }
+    let (glyf, loca, loca_format) = glyf_builder.build();
+    builder
+        .add_table(&glyf)
+        .map_err(|e| Error::TableBuild(e.to_string()))?;
+    builder
+        .add_table(&loca)
+        .map_err(|e| Error::TableBuild(e.to_string()))?;
+
+    // head table
+    let mut head: Head = head.to_owned_table();
+    head.index_to_loc_format = loca_format as i16;
+    if let Some(bbox) = bbox {
+        head.x_min = bbox.x_min;
+        head.y_min = bbox.y_min;
+        head.x_max = bbox.x_max;
+        head.y_max = bbox.y_max;
+    }
+    builder
+        .add_table(&head)
+        .map_err(|e| Error::TableBuild(e.to_string()))?;
+
+    // hhea / hmtx tables
+    let mut hhea: Hhea = existing.hhea().map_err(read_error)?.to_owned_table();
+    hhea.advance_width_max = UfWord::new(
+        h_metrics
+            .iter()
+            .map(|metric| metric.advance)
+            .max()
+            .unwrap_or(0),
+    );
+    // the extents are left as they are if no glyph has an outline
+    if bbox.is_some() {
+        let extent = |value: i32| {
+            i16::try_from(value).map(FWord::new).map_err(|_| {
+                Error::new(format!(
+                    "The horizontal extent of {} font units is too large.",
+                    value
+                ))
+            })
+        };
+        hhea.min_left_side_bearing = extent(min_left_side_bearing)?;
+        hhea.min_right_side_bearing = extent(min_right_side_bearing)?;
+        hhea.x_max_extent = extent(x_max_extent)?;
+    }
+    let (hmtx, number_of_h_metrics) = build_hmtx(h_metrics);
+    hhea.number_of_h_metrics = number_of_h_metrics;
+    builder
+        .add_table(&hhea)
+        .map_err(|e| Error::TableBuild(e.to_string()))?;
+    builder
+        .add_table(&hmtx)
+        .map_err(|e| Error::TableBuild(e.to_string()))?;
+
+    // maxp table
+    let mut maxp: Maxp = existing.maxp().map_err(read_error)?.to_owned_table();
+    maxp.num_glyphs = glyph_count;
+    maxp.max_points = maxp.max_points.map(|points| points.max(font.max_point));
+    maxp.max_contours = maxp
+        .max_contours
+        .map(|contours| contours.max(font.max_contour_count));
+    builder
+        .add_table(&maxp)
+        .map_err(|e| Error::TableBuild(e.to_string()))?;
+
+    // post table, with the names of the new glyphs if the existing font has names
+    let mut merged_post = if post.version() == Version16Dot16::VERSION_3_0 {
+        Post {
+            version: Version16Dot16::VERSION_3_0,
+            ..Default::default()
+        }
+    } else {
+        Post::new_v2(names.iter().map(|name| name.as_str()))
+    };
+    merged_post.italic_angle = post.italic_angle();
+    merged_post.underline_position = post.underline_position();
+    merged_post.underline_thickness = post.underline_thickness();
+    merged_post.is_fixed_pitch = post.is_fixed_pitch();
+    builder
+        .add_table(&merged_post)
+        .map_err(|e| Error::TableBuild(e.to_string()))?;
+
+    // OS/2 table, with the character range and the ranges of the merged cmap
+    let cmap_entries: Vec<(u32, u16)> = cmap_entries.into_iter().collect();
+    if let Ok(os2) = existing.os2() {
+        let codepoints = || cmap_entries.iter().map(|&(codepoint, _)| codepoint);
+        let mut os2: Os2 = os2.to_owned_table();
+        (os2.us_first_char_index, os2.us_last_char_index) = char_index_range(codepoints());
+        [
+            os2.ul_unicode_range_1,
+            os2.ul_unicode_range_2,
+            os2.ul_unicode_range_3,
+            os2.ul_unicode_range_4,
+        ] = unicode_ranges(codepoints());
+        // version 0 tables have no code page ranges
+        if os2.ul_code_page_range_1.is_some() {
+            let [range_1, range_2] = code_page_ranges(codepoints());
+            os2.ul_code_page_range_1 = Some(range_1);
+            os2.ul_code_page_range_2 = Some(range_2);
+        }
+        builder
+            .add_table(&os2)
+            .map_err(|e| Error::TableBuild(e.to_string()))?;
+    }
+
+    // cmap table, keeping the Macintosh encoding only if the existing font has one
+    let web_optimized = !cmap
+        .encoding_records()
+        .iter()
+        .any(|record| record.platform_id() == PlatformId::Macintosh);
+    builder
+        .add_table(&build_cmap(&cmap_entries, web_optimized))
+        .map_err(|e| Error::TableBuild(e.to_string()))?;
+
+    // the other tables don't depend on the new glyphs
+    for record in existing.table_directory.table_records() {
+        let tag = record.tag();
+        if REBUILT_TABLES
+            .iter()
+            .chain(&PER_GLYPH_TABLES)
+            .any(|&rebuilt| tag == Tag::new(rebuilt))
+        {
+            continue;
+        }
+        if let Some(data) = existing.table_data(tag) {
+            builder.add_raw(tag, data.as_bytes().to_vec());
+        }
+    }
+
+    Ok(builder.build())
+}
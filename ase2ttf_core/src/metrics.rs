@@ -0,0 +1,113 @@
+use std::fmt::Display;
+
+use crate::font::FontData;
+use crate::{Error, Params, descent, to_font_units};
+
+/// The vertical metrics and advances of a generated font, in font units unless noted otherwise.
+#[derive(Debug, PartialEq)]
+pub struct FontMetrics {
+    pub units_per_em: u32,
+    /// The number of font units that one pixel of the sprite spans.
+    pub units_per_pixel: u32,
+    pub ascender: i16,
+    pub descender: i16,
+    pub line_gap: i16,
+    /// The distance from the bottom of a glyph cell to the baseline, in pixels.
+    pub baseline: i16,
+    /// The cap height measured from the reference glyph or `H`, or estimated from the ascender.
+    pub cap_height: Option<i16>,
+    /// The x-height measured from `x`, or estimated from the ascender.
+    pub x_height: Option<i16>,
+    pub glyphs: Vec<GlyphMetrics>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct GlyphMetrics {
+    pub name: String,
+    /// `None` for glyphs that aren't mapped to a codepoint, such as `.notdef`.
+    pub codepoint: Option<u32>,
+    pub advance: u32,
+}
+
+impl Display for FontMetrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let pixels = |units: i64| units as f64 / self.units_per_pixel as f64;
+        let optional = |value: Option<i16>| match value {
+            Some(value) => format!("{:>6}  ({} px)", value, pixels(value as i64)),
+            None => format!("{:>6}", "-"),
+        };
+
+        writeln!(f, "units per em  {:>6}", self.units_per_em)?;
+        for (label, value) in [
+            ("ascender", self.ascender),
+            ("descender", self.descender),
+            ("line gap", self.line_gap),
+        ] {
+            writeln!(
+                f,
+                "{:<12}  {:>6}  ({} px)",
+                label,
+                value,
+                pixels(value as i64)
+            )?;
+        }
+        writeln!(f, "baseline      {:>6} px", self.baseline)?;
+        writeln!(f, "cap height    {}", optional(self.cap_height))?;
+        writeln!(f, "x-height      {}", optional(self.x_height))?;
+
+        let width = self
+            .glyphs
+            .iter()
+            .map(|glyph| glyph.name.len())
+            .max()
+            .unwrap_or(0)
+            .max("glyph".len());
+        writeln!(f)?;
+        writeln!(f, "{:<width$}  codepoint  advance", "glyph")?;
+        for glyph in &self.glyphs {
+            let codepoint = match glyph.codepoint {
+                Some(codepoint) => format!("U+{:04X}", codepoint),
+                None => "-".to_string(),
+            };
+            writeln!(
+                f,
+                "{:<width$}  {:<9}  {:>7}  ({} px)",
+                glyph.name,
+                codepoint,
+                glyph.advance,
+                pixels(glyph.advance as i64)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn measure(font: &FontData, args: &Params) -> Result<FontMetrics, Error> {
+    let glyphs = font
+        .glyphs
+        .iter()
+        .enumerate()
+        .map(|(id, glyph)| GlyphMetrics {
+            name: glyph.name.clone(),
+            codepoint: font
+                .cmap_entries
+                .iter()
+                .filter(|&&(_, glyph_id)| glyph_id as usize == id)
+                .map(|&(codepoint, _)| codepoint)
+                .min(),
+            advance: glyph.advance,
+        })
+        .collect();
+
+    Ok(FontMetrics {
+        units_per_em: font.units_per_em,
+        units_per_pixel: font.units_per_pixel,
+        ascender: font.ascender,
+        descender: font.descender,
+        line_gap: to_font_units(args.line_gap.unwrap_or(0) as i64, font.units_per_pixel)?,
+        baseline: descent(args)?,
+        cap_height: Some(font.measured_cap_height()),
+        x_height: Some(font.measured_x_height()),
+        glyphs,
+    })
+}
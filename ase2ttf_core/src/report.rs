@@ -0,0 +1,86 @@
+use std::fmt::Display;
+
+use crate::{Warning, WarningKind};
+
+/// How the layers of an Aseprite file were read into glyphs, for finding out why a character is
+/// missing from the font.
+#[derive(Debug, PartialEq)]
+pub struct SheetReport {
+    /// The layers, tags or regions that glyphs were read from, in the order they were read.
+    pub layers: Vec<LayerReport>,
+    /// Layers that start like a codepoint but were left out, because their name doesn't parse.
+    pub ignored_layers: Vec<String>,
+    /// The number of codepoints that the `[aliases]` of the config map to the glyph of another
+    /// codepoint.
+    pub aliases: usize,
+    /// The warnings that are found without tracing the cells, including the ignored layers.
+    pub warnings: Vec<Warning>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LayerReport {
+    pub name: String,
+    /// The number of cells in the grid of the layer.
+    pub cells: u32,
+    /// The codepoints of the cells that were drawn and became glyphs.
+    pub glyphs: Vec<u32>,
+    /// The codepoints of the cells that were left blank.
+    pub blank_cells: Vec<u32>,
+    /// The left, top, right and bottom edges of the ink over all drawn cells, in pixels from the
+    /// top-left corner of a cell. `None` if no cell was drawn.
+    pub ink_bounds: Option<(u32, u32, u32, u32)>,
+}
+
+impl Display for SheetReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let width = self
+            .layers
+            .iter()
+            .map(|layer| layer.name.len())
+            .max()
+            .unwrap_or(0)
+            .max("layer".len());
+        writeln!(
+            f,
+            "{:<width$}  cells  glyphs  blank  codepoints       ink",
+            "layer"
+        )?;
+        for layer in &self.layers {
+            let codepoints = match (layer.glyphs.iter().min(), layer.glyphs.iter().max()) {
+                (Some(first), Some(last)) => format!("U+{:04X}-U+{:04X}", first, last),
+                _ => "-".to_string(),
+            };
+            let ink = match layer.ink_bounds {
+                Some((left, top, right, bottom)) => {
+                    format!("x {}-{}, y {}-{}", left, right, top, bottom)
+                }
+                None => "-".to_string(),
+            };
+            writeln!(
+                f,
+                "{:<width$}  {:>5}  {:>6}  {:>5}  {:<15}  {}",
+                layer.name,
+                layer.cells,
+                layer.glyphs.len(),
+                layer.blank_cells.len(),
+                codepoints,
+                ink
+            )?;
+        }
+        match self.aliases {
+            0 => {}
+            1 => writeln!(f, "1 alias shares the glyph of another codepoint")?,
+            n => writeln!(f, "{} aliases share the glyph of another codepoint", n)?,
+        }
+        for name in &self.ignored_layers {
+            writeln!(f, "ignored layer {}: its name is not a codepoint", name)?;
+        }
+        // the ignored layers are listed above
+        for warning in &self.warnings {
+            if warning.kind != WarningKind::IgnoredLayer {
+                writeln!(f, "warning: {}", warning)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+use std::io::Write;
+
+use flate2::write::ZlibEncoder;
+use flate2::{Compression, Crc};
+
+use crate::font::{FontData, alpha_threshold};
+use crate::{Error, Params};
+
+/// Builds an `sbix` table with a single strike at the native size of the font, holding the
+/// pixels of each glyph cropped to the pixels that the outlines are traced from. Glyphs without a
+/// cell, such as mirrored glyphs, are only drawn by their outlines.
+pub(crate) fn build_sbix(font: &FontData, args: &Params) -> Result<Vec<u8>, Error> {
+    let ppem = font.units_per_em / font.units_per_pixel;
+    let ppem = u16::try_from(ppem).map_err(|_| {
+        Error::new(format!(
+            "The font is {} pixels per em, which is too large for embedded bitmaps.",
+            ppem
+        ))
+    })?;
+
+    let alpha_threshold = alpha_threshold(args);
+
+    let mut glyph_data = vec![];
+    let mut offsets = vec![];
+    // the glyph data offsets follow the size and resolution of the strike
+    let data_start = 4 + 4 * (font.glyphs.len() + 1);
+    for glyph in &font.glyphs {
+        offsets.push((data_start + glyph_data.len()) as u32);
+        let Some(cell) = &glyph.cell else {
+            continue;
+        };
+        let ink = |x: u32, y: u32| cell.pixels[(y * cell.width + x) as usize][3] >= alpha_threshold;
+        let columns: Vec<u32> = (0..cell.width)
+            .filter(|&x| (0..cell.height).any(|y| ink(x, y)))
+            .collect();
+        let rows: Vec<u32> = (0..cell.height)
+            .filter(|&y| (0..cell.width).any(|x| ink(x, y)))
+            .collect();
+        let (Some(&left), Some(&right), Some(&top), Some(&bottom)) =
+            (columns.first(), columns.last(), rows.first(), rows.last())
+        else {
+            continue;
+        };
+
+        let (width, height) = (right - left + 1, bottom - top + 1);
+        let pixels: Vec<[u8; 4]> = (top..=bottom)
+            .flat_map(|y| (left..=right).map(move |x| (x, y)))
+            .map(|(x, y)| cell.pixels[(y * cell.width + x) as usize])
+            .collect();
+        // the bottom-left corner of the bitmap relative to the glyph origin, in pixels
+        let origin_x = (cell.origin.x / font.units_per_pixel as f64).round() as i32 + left as i32;
+        let origin_y =
+            (cell.origin.y / font.units_per_pixel as f64).round() as i32 - bottom as i32 - 1;
+        glyph_data.extend((origin_x as i16).to_be_bytes());
+        glyph_data.extend((origin_y as i16).to_be_bytes());
+        glyph_data.extend(b"png ");
+        glyph_data.extend(encode_png(width, height, &pixels)?);
+    }
+    offsets.push((data_start + glyph_data.len()) as u32);
+
+    let mut strike = vec![];
+    strike.extend(ppem.to_be_bytes());
+    strike.extend(72u16.to_be_bytes()); // ppi
+    for offset in offsets {
+        strike.extend(offset.to_be_bytes());
+    }
+    strike.extend(glyph_data);
+
+    let mut sbix = vec![];
+    sbix.extend(1u16.to_be_bytes()); // version
+    sbix.extend(1u16.to_be_bytes()); // flags, with only the bit that is always set
+    sbix.extend(1u32.to_be_bytes()); // strike count
+    sbix.extend(12u32.to_be_bytes()); // the offset of the strike
+    sbix.extend(strike);
+    Ok(sbix)
+}
+
+/// Encodes RGBA pixels as a PNG image.
+fn encode_png(width: u32, height: u32, pixels: &[[u8; 4]]) -> Result<Vec<u8>, Error> {
+    let png_error = |e: std::io::Error| Error::new(format!("Failed to encode a bitmap: {}", e));
+
+    // every row starts with the filter type, which is none
+    let mut encoder = ZlibEncoder::new(vec![], Compression::best());
+    for row in pixels.chunks(width as usize) {
+        encoder.write_all(&[0]).map_err(png_error)?;
+        encoder.write_all(row.as_flattened()).map_err(png_error)?;
+    }
+    let image_data = encoder.finish().map_err(png_error)?;
+
+    let mut header = vec![];
+    header.extend(width.to_be_bytes());
+    header.extend(height.to_be_bytes());
+    // 8 bits per channel in RGBA, with the only compression and filter methods and no interlace
+    header.extend([8, 6, 0, 0, 0]);
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    for (kind, data) in [(b"IHDR", header), (b"IDAT", image_data), (b"IEND", vec![])] {
+        png.extend((data.len() as u32).to_be_bytes());
+        let mut crc = Crc::new();
+        crc.update(kind);
+        crc.update(&data);
+        png.extend(kind);
+        png.extend(data);
+        png.extend(crc.sum().to_be_bytes());
+    }
+    Ok(png)
+}
@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+
+use kurbo::{PathEl, Point};
+
+use crate::Params;
+use crate::font::FontData;
+use crate::ttf::{localized_strings, subfamily};
+
+/// Encoding slots after the Unicode range, used for glyphs that have no codepoint.
+const UNENCODED_START: u32 = 0x110000;
+
+/// Builds a FontForge Spline Font Database (.sfd) from the traced glyphs.
+pub(crate) fn build_sfd(font: &FontData, args: &Params) -> String {
+    let subfamily = subfamily(args).unwrap_or("Regular".to_string());
+    let version = args
+        .font_version
+        .clone()
+        .unwrap_or("Version 1.0".to_string());
+    let ascent = font.units_per_em as i32 + font.descender as i32;
+    let descent = -(font.descender as i32);
+    let line_gap = args.line_gap.unwrap_or(0) as i32 * font.units_per_pixel as i32;
+
+    let mut lines = vec![
+        "SplineFontDB: 3.2".to_string(),
+        format!("FontName: {}", font.postscript_name),
+        format!("FullName: {}", font.family),
+        format!("FamilyName: {}", font.family),
+        format!("Weight: {}", subfamily),
+    ];
+    if let Some(copyright) = &args.copyright {
+        lines.push(format!("Copyright: {}", escape(copyright)));
+    }
+    lines.extend([
+        format!("Version: {}", version),
+        format!("ItalicAngle: {}", args.italic_angle.unwrap_or(0.0)),
+        format!(
+            "UnderlinePosition: {}",
+            args.underline_position.unwrap_or(0) as i32 * font.units_per_pixel as i32
+        ),
+        format!(
+            "UnderlineWidth: {}",
+            args.underline_thickness.unwrap_or(1) as i32 * font.units_per_pixel as i32
+        ),
+        format!("Ascent: {}", ascent),
+        format!("Descent: {}", descent),
+        "LayerCount: 2".to_string(),
+        "Layer: 0 1 \"Back\" 1".to_string(),
+        "Layer: 1 1 \"Fore\" 0".to_string(),
+        format!("OS2TypoAscent: {}", font.ascender),
+        "OS2TypoAOffset: 0".to_string(),
+        format!("OS2TypoDescent: {}", font.descender),
+        "OS2TypoDOffset: 0".to_string(),
+        format!("OS2TypoLinegap: {}", line_gap),
+        "OS2_UseTypoMetrics: 1".to_string(),
+        format!("OS2Vendor: '{}'", String::from_utf8_lossy(&font.vendor_id)),
+        format!("FSType: {}", args.embedding.unwrap_or_default().fs_type()),
+        format!("OS2WinAscent: {}", font.ascender.max(0)),
+        "OS2WinAOffset: 0".to_string(),
+        format!("OS2WinDescent: {}", descent.max(0)),
+        "OS2WinDOffset: 0".to_string(),
+        format!("HheadAscent: {}", font.ascender),
+        "HheadAOffset: 0".to_string(),
+        format!("HheadDescent: {}", font.descender),
+        "HheadDOffset: 0".to_string(),
+        format!("LineGap: {}", line_gap),
+    ]);
+    // the names of each language, as the strings of name IDs 0 to 17 with the unset ones empty
+    for (language, name) in &font.localized_names {
+        let mut strings = vec![String::new(); 18];
+        for (name_id, string) in localized_strings(name, args) {
+            strings[name_id as usize] = string;
+        }
+        let strings: Vec<_> = strings.iter().map(|string| utf7(string)).collect();
+        lines.push(format!("LangName: {} {}", language, strings.join(" ")));
+    }
+    lines.extend([
+        "Encoding: UnicodeFull".to_string(),
+        "DisplaySize: -48".to_string(),
+        "AntiAlias: 0".to_string(),
+        "FitToEm: 0".to_string(),
+        format!("BeginChars: {} {}", UNENCODED_START, font.glyphs.len()),
+    ]);
+
+    // aliases and identical cells map several codepoints to a glyph
+    let mut codepoints: HashMap<usize, Vec<u32>> = HashMap::new();
+    for (codepoint, glyph_id) in &font.cmap_entries {
+        codepoints
+            .entry(*glyph_id as usize)
+            .or_default()
+            .push(*codepoint);
+    }
+    for codepoints in codepoints.values_mut() {
+        codepoints.sort();
+    }
+
+    let mut next_unencoded = UNENCODED_START;
+    for (glyph_id, glyph) in font.glyphs.iter().enumerate() {
+        let codepoints = codepoints
+            .get(&glyph_id)
+            .map(|codepoints| codepoints.as_slice())
+            .unwrap_or_default();
+        let (encoding, unicode) = match codepoints.first() {
+            Some(&codepoint) => (codepoint, codepoint as i64),
+            None => {
+                next_unencoded += 1;
+                (next_unencoded - 1, -1)
+            }
+        };
+
+        lines.push(String::new());
+        lines.push(format!("StartChar: {}", glyph.name));
+        lines.push(format!("Encoding: {} {} {}", encoding, unicode, glyph_id));
+        // the other codepoints of the glyph, with no variation selector
+        if codepoints.len() > 1 {
+            let alternates: Vec<_> = codepoints[1..]
+                .iter()
+                .map(|codepoint| format!("{:06x}.ffffffff.0", codepoint))
+                .collect();
+            lines.push(format!("AltUni2: {}", alternates.join(" ")));
+        }
+        lines.push(format!("Width: {}", glyph.advance));
+        lines.push("Flags: W".to_string());
+        lines.push("LayerCount: 2".to_string());
+        lines.push("Fore".to_string());
+        lines.push("SplineSet".to_string());
+        let mut start = Point::ZERO;
+        let mut current = Point::ZERO;
+        for element in glyph.path.elements() {
+            match *element {
+                PathEl::MoveTo(p) => {
+                    lines.push(format!("{} {} m 1", p.x, p.y));
+                    start = p;
+                    current = p;
+                }
+                PathEl::LineTo(p) => {
+                    lines.push(format!(" {} {} l 1", p.x, p.y));
+                    current = p;
+                }
+                // quadratic layers store the single control point twice
+                PathEl::QuadTo(c, p) => {
+                    lines.push(format!(
+                        " {} {} {} {} {} {} c 0",
+                        c.x, c.y, c.x, c.y, p.x, p.y
+                    ));
+                    current = p;
+                }
+                PathEl::CurveTo(c1, c2, p) => {
+                    lines.push(format!(
+                        " {} {} {} {} {} {} c 0",
+                        c1.x, c1.y, c2.x, c2.y, p.x, p.y
+                    ));
+                    current = p;
+                }
+                // contours are closed by returning to their first point
+                PathEl::ClosePath => {
+                    if current != start {
+                        lines.push(format!(" {} {} l 1", start.x, start.y));
+                        current = start;
+                    }
+                }
+            }
+        }
+        lines.push("EndSplineSet".to_string());
+        lines.push("EndChar".to_string());
+    }
+
+    lines.push("EndChars".to_string());
+    lines.push("EndSplineFont".to_string());
+    lines.join("\n") + "\n"
+}
+
+/// Quotes a string in UTF-7, which FontForge uses for the strings of the name table. Characters
+/// outside printable ASCII, and the ones FontForge doesn't write as they are, are encoded as
+/// base64 UTF-16 between `+` and `-`.
+fn utf7(value: &str) -> String {
+    const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let direct = |c: char| matches!(c, ' '..='~') && !matches!(c, '+' | '\\' | '~' | '=' | '"');
+
+    let mut quoted = String::from("\"");
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        if direct(c) {
+            quoted.push(c);
+        } else if c == '+' {
+            quoted.push_str("+-");
+        } else {
+            // the run of encoded characters, as the bytes of their UTF-16 code units
+            let mut bytes = vec![];
+            let mut units = [0; 2];
+            for unit in c.encode_utf16(&mut units) {
+                bytes.extend(unit.to_be_bytes());
+            }
+            while let Some(&c) = chars.peek().filter(|&&c| !direct(c) && c != '+') {
+                for unit in c.encode_utf16(&mut units) {
+                    bytes.extend(unit.to_be_bytes());
+                }
+                chars.next();
+            }
+            quoted.push('+');
+            for chunk in bytes.chunks(3) {
+                let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, &byte)| {
+                    bits | (byte as u32) << (16 - 8 * i)
+                });
+                for i in 0..=chunk.len() {
+                    quoted.push(BASE64[(bits >> (18 - 6 * i) & 0x3F) as usize] as char);
+                }
+            }
+            quoted.push('-');
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\n', "\\n")
+}
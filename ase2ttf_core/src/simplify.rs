@@ -0,0 +1,159 @@
+use std::fmt::Display;
+
+use kurbo::{BezPath, PathEl};
+
+use crate::SCALE;
+use crate::edge::drop_collinear;
+
+/// A glyph whose outline was simplified to fit a point budget.
+#[derive(Debug, PartialEq)]
+pub struct SimplifiedGlyph {
+    pub name: String,
+    pub original_points: u16,
+    pub points: u16,
+}
+
+impl Display for SimplifiedGlyph {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} was simplified from {} to {} points",
+            self.name, self.original_points, self.points
+        )
+    }
+}
+
+/// Reduces the points of a traced outline to at most `max_points`. Collinear points are dropped
+/// first, since that keeps the outline as it is, and Douglas-Peucker with a growing tolerance is
+/// used only when that isn't enough. Returns `None` if the glyph has too many contours to fit,
+/// as every contour keeps at least three points.
+pub(crate) fn simplify(path: &BezPath, max_points: u16) -> Option<(BezPath, u16)> {
+    let contours: Vec<Vec<(f64, f64)>> = contours(path).into_iter().map(drop_collinear).collect();
+    if contours.len() * 3 > max_points as usize {
+        return None;
+    }
+
+    let mut simplified = contours.clone();
+    // starts at a quarter of a pixel and doubles until the glyph fits
+    let mut tolerance = SCALE as f64 / 4.0;
+    while point_count(&simplified) > max_points as usize {
+        simplified = contours
+            .iter()
+            .map(|contour| simplify_contour(contour, tolerance))
+            .collect();
+        tolerance *= 2.0;
+    }
+
+    let mut path = BezPath::new();
+    for contour in &simplified {
+        path.move_to(contour[0]);
+        for &point in &contour[1..] {
+            path.line_to(point);
+        }
+        path.close_path();
+    }
+    Some((path, point_count(&simplified) as u16))
+}
+
+pub(crate) fn contours(path: &BezPath) -> Vec<Vec<(f64, f64)>> {
+    let mut contours = Vec::new();
+    let mut contour = Vec::new();
+    for element in path.elements() {
+        match *element {
+            PathEl::MoveTo(p) => {
+                if !contour.is_empty() {
+                    contours.push(std::mem::take(&mut contour));
+                }
+                contour.push((p.x, p.y));
+            }
+            PathEl::LineTo(p) => contour.push((p.x, p.y)),
+            PathEl::ClosePath => {
+                // the traced paths repeat their start point before closing
+                if contour.len() > 1 && contour.first() == contour.last() {
+                    contour.pop();
+                }
+                contours.push(std::mem::take(&mut contour));
+            }
+            // traced outlines only have straight lines
+            _ => {}
+        }
+    }
+    if !contour.is_empty() {
+        contours.push(contour);
+    }
+    contours
+}
+
+fn point_count(contours: &[Vec<(f64, f64)>]) -> usize {
+    contours.iter().map(|contour| contour.len()).sum()
+}
+
+/// Simplifies a closed contour by splitting it at its first point and the point farthest from
+/// it, keeping at least a triangle.
+fn simplify_contour(contour: &[(f64, f64)], tolerance: f64) -> Vec<(f64, f64)> {
+    if contour.len() <= 3 {
+        return contour.to_vec();
+    }
+    let start = contour[0];
+    let far = (1..contour.len())
+        .max_by(|&a, &b| distance(contour[a], start).total_cmp(&distance(contour[b], start)))
+        .unwrap();
+
+    let mut second_half = contour[far..].to_vec();
+    second_half.push(start);
+    let mut simplified = douglas_peucker(&contour[..=far], tolerance);
+    simplified.pop();
+    simplified.extend(douglas_peucker(&second_half, tolerance));
+    simplified.pop();
+
+    if simplified.len() < 3 {
+        // the point farthest from the line between the two ends of the halves
+        let third = (1..contour.len())
+            .filter(|&i| i != far)
+            .max_by(|&a, &b| {
+                segment_distance(contour[a], start, contour[far]).total_cmp(&segment_distance(
+                    contour[b],
+                    start,
+                    contour[far],
+                ))
+            })
+            .unwrap();
+        let mut indices = [0, far, third];
+        indices.sort();
+        return indices.iter().map(|&i| contour[i]).collect();
+    }
+    simplified
+}
+
+/// Simplifies an open polyline, keeping both ends.
+fn douglas_peucker(points: &[(f64, f64)], tolerance: f64) -> Vec<(f64, f64)> {
+    let (first, last) = (points[0], points[points.len() - 1]);
+    let farthest = (1..points.len() - 1).max_by(|&a, &b| {
+        segment_distance(points[a], first, last)
+            .total_cmp(&segment_distance(points[b], first, last))
+    });
+    match farthest {
+        Some(i) if segment_distance(points[i], first, last) > tolerance => {
+            let mut simplified = douglas_peucker(&points[..=i], tolerance);
+            simplified.pop();
+            simplified.extend(douglas_peucker(&points[i..], tolerance));
+            simplified
+        }
+        _ => vec![first, last],
+    }
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - b.0).hypot(a.1 - b.1)
+}
+
+/// The distance from `p` to the segment from `a` to `b`.
+fn segment_distance(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length = dx * dx + dy * dy;
+    if length == 0.0 {
+        return distance(p, a);
+    }
+    let t = (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / length).clamp(0.0, 1.0);
+    distance(p, (a.0 + t * dx, a.1 + t * dy))
+}
@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+
+use crate::Params;
+use crate::font::FontData;
+
+/// Builds an SVG font document from the traced glyphs. Glyph outlines are in font units with the
+/// y axis pointing up, which is the coordinate system of SVG fonts.
+pub(crate) fn build_svg(font: &FontData, args: &Params) -> String {
+    let family = escape(&font.family);
+
+    let mut lines = vec![
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>".to_string(),
+        "<svg xmlns=\"http://www.w3.org/2000/svg\">".to_string(),
+        "<defs>".to_string(),
+        format!(
+            "<font id=\"{}\" horiz-adv-x=\"{}\">",
+            family.replace(' ', "-"),
+            font.glyph_width * font.units_per_pixel
+        ),
+    ];
+
+    let mut font_face = format!(
+        "<font-face font-family=\"{}\" units-per-em=\"{}\" ascent=\"{}\" descent=\"{}\"",
+        family, font.units_per_em, font.ascender, font.descender
+    );
+    if let Some(weight) = args.font_weight {
+        font_face += &format!(" font-weight=\"{}\"", weight);
+    }
+    if let Some(cap_height) = font.cap_height {
+        font_face += &format!(" cap-height=\"{}\"", cap_height);
+    }
+    if let Some(italic_angle) = args.italic_angle.filter(|&angle| angle != 0.0) {
+        font_face += &format!(" font-style=\"italic\" slope=\"{}\"", italic_angle);
+    }
+    lines.push(font_face + "/>");
+
+    // aliases and identical cells map several codepoints to a glyph
+    let mut codepoints: HashMap<usize, Vec<u32>> = HashMap::new();
+    for (codepoint, glyph_id) in &font.cmap_entries {
+        codepoints
+            .entry(*glyph_id as usize)
+            .or_default()
+            .push(*codepoint);
+    }
+    for codepoints in codepoints.values_mut() {
+        codepoints.sort();
+    }
+
+    for (glyph_id, glyph) in font.glyphs.iter().enumerate() {
+        let mut shape = format!(" horiz-adv-x=\"{}\"", glyph.advance);
+        if !glyph.path.elements().is_empty() {
+            shape += &format!(" d=\"{}\"", glyph.path.to_svg());
+        }
+
+        // .notdef is shown for characters that the font doesn't have
+        if glyph_id == 0 {
+            lines.push(format!("<missing-glyph{}/>", shape));
+            continue;
+        }
+
+        // an SVG glyph has a single character, so a glyph is repeated for each of its codepoints
+        let name = format!(" glyph-name=\"{}\"", escape(&glyph.name));
+        let codepoints = codepoints
+            .get(&glyph_id)
+            .map(|codepoints| codepoints.as_slice())
+            .unwrap_or_default();
+        let mut written = false;
+        for &codepoint in codepoints
+            .iter()
+            .filter(|&&codepoint| is_xml_char(codepoint))
+        {
+            lines.push(format!(
+                "<glyph{} unicode=\"&#x{:X};\"{}/>",
+                name, codepoint, shape
+            ));
+            written = true;
+        }
+        if !written {
+            lines.push(format!("<glyph{}{}/>", name, shape));
+        }
+    }
+
+    lines.extend([
+        "</font>".to_string(),
+        "</defs>".to_string(),
+        "</svg>".to_string(),
+    ]);
+    lines.join("\n") + "\n"
+}
+
+/// Builds a standalone SVG document for each mapped codepoint, holding the outline of its glyph
+/// in font units. The path is flipped to draw upright, and the view spans the advance of the glyph
+/// from the ascender to the descender.
+pub(crate) fn build_glyph_svgs(font: &FontData) -> HashMap<u32, String> {
+    let mut svgs = HashMap::new();
+    for &(codepoint, glyph_id) in &font.cmap_entries {
+        let glyph = &font.glyphs[glyph_id as usize];
+        // combining marks have no advance, so they are shown in a whole cell
+        let width = match glyph.advance {
+            0 => font.glyph_width * font.units_per_pixel,
+            advance => advance,
+        };
+        let svg = [
+            format!(
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 {} {} {}\">",
+                -font.ascender,
+                width,
+                font.ascender as i32 - font.descender as i32
+            ),
+            format!(
+                "<path transform=\"scale(1 -1)\" fill-rule=\"nonzero\" d=\"{}\"/>",
+                glyph.path.to_svg()
+            ),
+            "</svg>".to_string(),
+        ];
+        svgs.insert(codepoint, svg.join("\n") + "\n");
+    }
+    svgs
+}
+
+/// Whether a codepoint can be written in an XML document. Glyphs for other codepoints, such as the
+/// null glyph, are written without a `unicode` attribute.
+fn is_xml_char(codepoint: u32) -> bool {
+    matches!(
+        codepoint,
+        0x9 | 0xA | 0xD | 0x20..=0xD7FF | 0xE000..=0xFFFD | 0x10000..=0x10FFFF
+    )
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,1199 @@
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::Utc;
+use write_fonts::tables::cmap::{Cmap, CmapSubtable, EncodingRecord, SequentialMapGroup};
+use write_fonts::tables::colr::{BaseGlyph, Colr, Layer};
+use write_fonts::tables::cpal::{ColorRecord, Cpal};
+use write_fonts::tables::glyf::{
+    Anchor, Bbox, Component, ComponentFlags, CompositeGlyph, GlyfLocaBuilder, Glyph, Transform,
+};
+use write_fonts::tables::gpos::{
+    Gpos, PairPos, PairSet, PairValueRecord, PositionLookup, ValueRecord,
+};
+use write_fonts::tables::gsub::{
+    Gsub, Ligature, LigatureSet, LigatureSubstFormat1, SingleSubst, SubstitutionLookup,
+};
+use write_fonts::tables::hhea::Hhea;
+use write_fonts::tables::hmtx::Hmtx;
+use write_fonts::tables::layout::{
+    CoverageTable, Feature, FeatureList, FeatureParams, FeatureRecord, LangSys, Lookup, LookupFlag,
+    LookupList, Script, ScriptList, ScriptRecord, SizeParams,
+};
+use write_fonts::tables::maxp::Maxp;
+use write_fonts::tables::os2::{Os2, SelectionFlags};
+use write_fonts::tables::post::Post;
+use write_fonts::tables::vmtx::LongMetric;
+use write_fonts::types::{FWord, GlyphId16, Tag, UfWord, Version16Dot16};
+use write_fonts::{
+    OffsetMarker,
+    tables::{
+        cmap::PlatformId,
+        glyf::SimpleGlyph,
+        head::{Flags, Head, MacStyle},
+        name::{Name, NameRecord},
+    },
+    types::{Fixed, LongDateTime, NameId},
+};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::code_page::code_page_ranges;
+use crate::font::FontData;
+use crate::sbix::build_sbix;
+use crate::unicode_range::unicode_ranges;
+use crate::variable::{WEIGHT_AXIS_NAME_ID, add_variation_tables};
+use crate::{Error, Params, to_font_units};
+
+/// The family and subfamily names of the font in a language other than English, written to the
+/// name table next to the English ones.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalizedName {
+    pub family: String,
+    /// The English subfamily is used when it is left out.
+    pub subfamily: Option<String>,
+}
+
+/// How applications may embed the font in documents, which is written to the fsType of the OS/2
+/// table.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmbeddingLevel {
+    /// The font can be embedded and installed on the system that opens the document.
+    #[default]
+    Installable,
+    /// The font must not be embedded without permission from the owner.
+    Restricted,
+    /// The font can be embedded to view and print the document, but not to edit it.
+    PreviewAndPrint,
+    /// The font can be embedded to view, print and edit the document.
+    Editable,
+}
+
+impl EmbeddingLevel {
+    pub(crate) fn fs_type(self) -> u16 {
+        match self {
+            EmbeddingLevel::Installable => 0x0000,
+            EmbeddingLevel::Restricted => 0x0002,
+            EmbeddingLevel::PreviewAndPrint => 0x0004,
+            EmbeddingLevel::Editable => 0x0008,
+        }
+    }
+}
+
+/// Builds an hmtx table from the metrics of every glyph, and returns it with its number of long
+/// metrics. The glyphs at the end that share the advance of the one before them, such as most of
+/// a monospaced font, only keep their side bearings.
+pub(crate) fn build_hmtx(mut h_metrics: Vec<LongMetric>) -> (Hmtx, u16) {
+    let last_advance = h_metrics.last().map(|metric| metric.advance);
+    let repeated = h_metrics
+        .iter()
+        .rev()
+        .take_while(|metric| Some(metric.advance) == last_advance)
+        .count();
+    // the last long metric holds the advance of the rest
+    let count = (h_metrics.len() + 1 - repeated).min(h_metrics.len());
+    let left_side_bearings = h_metrics
+        .split_off(count)
+        .into_iter()
+        .map(|metric| metric.side_bearing)
+        .collect();
+    (Hmtx::new(h_metrics, left_side_bearings), count as u16)
+}
+
+/// The name IDs written to the name table, and the ones that can't be left out of it.
+const NAME_IDS: [u16; 9] = [0, 1, 2, 3, 4, 5, 6, 16, 17];
+const REQUIRED_NAME_IDS: [u16; 2] = [1, 2];
+
+pub(crate) fn build_ttf(font: &FontData, args: &Params) -> Result<Vec<u8>, Error> {
+    let glyph_width = font.glyph_width;
+    let glyph_height = font.glyph_height;
+    let cell_width = to_font_units(glyph_width as i64, font.units_per_pixel)?;
+    let cell_height = to_font_units(glyph_height as i64, font.units_per_pixel)?;
+    let line_gap = to_font_units(args.line_gap.unwrap_or(0) as i64, font.units_per_pixel)?;
+    let (ascender, descender) = (font.ascender, font.descender);
+    let glyph_count = font.glyphs.len() as u16;
+    // web browsers only read the Unicode and Windows records, so the rest can be dropped
+    let web_optimized = args.web_optimized.unwrap_or(false);
+    // the glyphs are drawn slanted already, so the angle is only recorded, never applied
+    let italic_angle = italic_angle(args.italic_angle.unwrap_or(0.0))?;
+    let italic = italic_angle != 0.0;
+    // the style flags let applications pair the font with the other styles of its family
+    let (bold, italic_style) = subfamily_style(args);
+    let italic_style = italic_style || italic;
+
+    let mut builder = write_fonts::FontBuilder::new();
+
+    // name table
+    if let Some(name_ids) = &args.name_ids {
+        check_name_ids(name_ids)?;
+    }
+    let family = &font.family;
+    let mut name_records = Vec::new();
+    for i in 0..2 {
+        let platform_id = match i {
+            0 => PlatformId::Macintosh,
+            1 => PlatformId::Windows,
+            _ => unreachable!(),
+        };
+        if web_optimized && platform_id == PlatformId::Macintosh {
+            continue;
+        }
+
+        let encoding_id = match platform_id {
+            PlatformId::Macintosh => 0,
+            PlatformId::Windows => 1,
+            _ => unreachable!(),
+        };
+
+        // english
+        let language_id = match platform_id {
+            PlatformId::Macintosh => 0,
+            PlatformId::Windows => 0x0409,
+            _ => unreachable!(),
+        };
+
+        // 0: copyright
+        if let Some(copyright) = args.copyright.clone() {
+            name_records.push(NameRecord {
+                platform_id: platform_id as u16,
+                encoding_id: encoding_id,
+                language_id: language_id,
+                name_id: NameId::from(0),
+                string: OffsetMarker::new(copyright),
+            });
+        }
+
+        // 1: font family name
+        name_records.push(NameRecord {
+            platform_id: platform_id as u16,
+            encoding_id: encoding_id,
+            language_id: language_id,
+            name_id: NameId::from(1),
+            string: OffsetMarker::new(if let Some(subfamily) = subfamily(args) {
+                format!("{} {}", family, subfamily)
+            } else {
+                family.clone()
+            }),
+        });
+
+        // 2: subfamily name
+        name_records.push(NameRecord {
+            platform_id: platform_id as u16,
+            encoding_id: encoding_id,
+            language_id: language_id,
+            name_id: NameId::from(2),
+            string: OffsetMarker::new(subfamily(args).unwrap_or("Regular".to_string())),
+        });
+
+        // 3: identifier
+        name_records.push(NameRecord {
+            platform_id: platform_id as u16,
+            encoding_id: encoding_id,
+            language_id: language_id,
+            name_id: NameId::from(3),
+            string: OffsetMarker::new(format!("ase2ttf: {}", family.clone())),
+        });
+
+        // 4: font name
+        name_records.push(NameRecord {
+            platform_id: platform_id as u16,
+            encoding_id: encoding_id,
+            language_id: language_id,
+            name_id: NameId::from(4),
+            string: OffsetMarker::new(family.clone()),
+        });
+
+        // 5: version
+        name_records.push(NameRecord {
+            platform_id: platform_id as u16,
+            encoding_id: encoding_id,
+            language_id: language_id,
+            name_id: NameId::from(5),
+            string: OffsetMarker::new(
+                args.font_version
+                    .clone()
+                    .unwrap_or("Version 1.0".to_string()),
+            ),
+        });
+
+        // 6: PostScript name
+        name_records.push(NameRecord {
+            platform_id: platform_id as u16,
+            encoding_id: encoding_id,
+            language_id: language_id,
+            name_id: NameId::from(6),
+            string: OffsetMarker::new(font.postscript_name.clone()),
+        });
+
+        // 16: Preferred family
+        name_records.push(NameRecord {
+            platform_id: platform_id as u16,
+            encoding_id: encoding_id,
+            language_id: language_id,
+            name_id: NameId::from(16),
+            string: OffsetMarker::new(family.clone()),
+        });
+
+        // 17: Preferred subfamily
+        name_records.push(NameRecord {
+            platform_id: platform_id as u16,
+            encoding_id: encoding_id,
+            language_id: language_id,
+            name_id: NameId::from(17),
+            string: OffsetMarker::new(subfamily(args).unwrap_or("Regular".to_string())),
+        });
+
+        // 256: the name of the weight axis of a variable font
+        if font.heavy_master.is_some() {
+            name_records.push(NameRecord {
+                platform_id: platform_id as u16,
+                encoding_id,
+                language_id,
+                name_id: NameId::from(WEIGHT_AXIS_NAME_ID),
+                string: OffsetMarker::new("Weight".to_string()),
+            });
+        }
+    }
+
+    // the Windows platform takes any language in UTF-16, which macOS reads too. Macintosh records
+    // would need the legacy encoding of each language, such as Shift JIS for Japanese, while
+    // write-fonts only encodes MacRoman, so there are none
+    for (&language, name) in &font.localized_names {
+        for (name_id, string) in localized_strings(name, args) {
+            name_records.push(NameRecord {
+                platform_id: PlatformId::Windows as u16,
+                encoding_id: 1,
+                language_id: language,
+                name_id: NameId::from(name_id),
+                string: OffsetMarker::new(string),
+            });
+        }
+    }
+    // the records are sorted by platform, encoding, language and name
+    name_records.sort_by_key(|record| {
+        (
+            record.platform_id,
+            record.encoding_id,
+            record.language_id,
+            record.name_id,
+        )
+    });
+
+    if let Some(name_ids) = &args.name_ids {
+        // the axis name is referenced by fvar, so it is always kept
+        name_records.retain(|record| {
+            name_ids.contains(&record.name_id.to_u16())
+                || record.name_id.to_u16() == WEIGHT_AXIS_NAME_ID
+        });
+    }
+    let name = Name::new(name_records);
+    builder
+        .add_table(&name)
+        .map_err(|e| Error::TableBuild(e.to_string()))?;
+
+    // OS/2 table
+    let (first_char_index, last_char_index) =
+        char_index_range(font.cmap_entries.iter().map(|&(codepoint, _)| codepoint));
+    // the mean advance of the glyphs that have one, which leaves out combining marks
+    let advances: Vec<u64> = font
+        .glyphs
+        .iter()
+        .map(|glyph| glyph.advance as u64)
+        .filter(|&advance| advance > 0)
+        .collect();
+    let avg_char_width = match advances.len() as u64 {
+        0 => 0,
+        count => (advances.iter().sum::<u64>() + count / 2) / count,
+    };
+    let avg_char_width = i16::try_from(avg_char_width).map_err(|_| {
+        Error::new(format!(
+            "The average advance width of {} font units is too large.",
+            avg_char_width
+        ))
+    })?;
+    let unicode_ranges = unicode_ranges(font.cmap_entries.iter().map(|&(codepoint, _)| codepoint));
+    let code_page_ranges =
+        code_page_ranges(font.cmap_entries.iter().map(|&(codepoint, _)| codepoint));
+    let os2 = Os2 {
+        x_avg_char_width: avg_char_width,
+        us_weight_class: weight_class(args),
+        us_width_class: 5,
+        fs_type: args.embedding.unwrap_or_default().fs_type(),
+        y_subscript_x_size: cell_width / 2,
+        y_subscript_y_size: cell_height / 2,
+        y_subscript_x_offset: 0,
+        y_subscript_y_offset: cell_height / 2,
+        y_superscript_x_size: cell_width / 2,
+        y_superscript_y_size: cell_height / 2,
+        y_superscript_x_offset: 0,
+        y_superscript_y_offset: cell_height / 2,
+        y_strikeout_size: 1,
+        y_strikeout_position: cell_height / 2,
+        s_family_class: family_class(args.family_class.unwrap_or(0))?,
+        panose_10: [0; 10],
+        ul_unicode_range_1: unicode_ranges[0],
+        ul_unicode_range_2: unicode_ranges[1],
+        ul_unicode_range_3: unicode_ranges[2],
+        ul_unicode_range_4: unicode_ranges[3],
+        ach_vend_id: Tag::new(&font.vendor_id),
+        // the typo metrics carry the line gap, so applications are told to prefer them over the
+        // win metrics, which have none
+        fs_selection: SelectionFlags::USE_TYPO_METRICS
+            | match (bold, italic_style) {
+                (false, false) => SelectionFlags::REGULAR,
+                (true, false) => SelectionFlags::BOLD,
+                (false, true) => SelectionFlags::ITALIC,
+                (true, true) => SelectionFlags::BOLD | SelectionFlags::ITALIC,
+            },
+        us_first_char_index: first_char_index,
+        us_last_char_index: last_char_index,
+        s_typo_ascender: ascender,
+        s_typo_descender: descender,
+        s_typo_line_gap: line_gap,
+        us_win_ascent: ascender.max(0) as u16,
+        us_win_descent: descender.min(0).unsigned_abs(),
+        // the cap height and x-height need a version 2 table, which also has these fields
+        ul_code_page_range_1: Some(code_page_ranges[0]),
+        ul_code_page_range_2: Some(code_page_ranges[1]),
+        sx_height: Some(font.measured_x_height()),
+        s_cap_height: Some(font.measured_cap_height()),
+        us_default_char: Some(0),
+        us_break_char: Some(0x20),
+        // the longest sequence of glyphs that a substitution or a kerning pair looks at
+        us_max_context: Some(
+            font.ligatures
+                .iter()
+                .map(|(components, _)| components.len() as u16)
+                .chain((!font.figure_pairs.is_empty()).then_some(1))
+                .chain((!font.kerning.is_empty()).then_some(2))
+                .max()
+                .unwrap_or(0),
+        ),
+        us_lower_optical_point_size: Default::default(),
+        us_upper_optical_point_size: Default::default(),
+    };
+    builder
+        .add_table(&os2)
+        .map_err(|e| Error::TableBuild(e.to_string()))?;
+
+    // post table
+    let mut post = if web_optimized {
+        // format 3 has no glyph names
+        Post {
+            version: Version16Dot16::VERSION_3_0,
+            ..Default::default()
+        }
+    } else {
+        let glyph_name_refs: Vec<&str> = font.glyphs.iter().map(|g| g.name.as_str()).collect();
+        Post::new_v2(glyph_name_refs)
+    };
+    post.underline_position = FWord::new(to_font_units(
+        args.underline_position.unwrap_or(0) as i64,
+        font.units_per_pixel,
+    )?);
+    post.underline_thickness = FWord::new(to_font_units(
+        args.underline_thickness.unwrap_or(1) as i64,
+        font.units_per_pixel,
+    )?);
+    post.italic_angle = Fixed::from_f64(italic_angle);
+    post.is_fixed_pitch = if args.trim.unwrap_or(false) { 0 } else { 1 };
+    builder
+        .add_table(&post)
+        .map_err(|e| Error::TableBuild(e.to_string()))?;
+
+    // cmap table
+    let cmap = build_cmap(&font.cmap_entries, web_optimized);
+    builder
+        .add_table(&cmap)
+        .map_err(|e| Error::TableBuild(e.to_string()))?;
+
+    // the metrics of the hmtx table, which hhea gives the count of
+    let mut h_metrics = Vec::with_capacity(font.glyphs.len());
+    for glyph in &font.glyphs {
+        let advance = u16::try_from(glyph.advance).map_err(|_| {
+            Error::new(format!(
+                "The advance width of {} font units is too large.",
+                glyph.advance
+            ))
+        })?;
+        h_metrics.push(LongMetric::new(advance, glyph.lsb as i16));
+    }
+    let (hmtx, number_of_h_metrics) = build_hmtx(h_metrics);
+
+    // hhea table
+    // spacing and config advances can be wider than a cell
+    let advance_width_max = font
+        .glyphs
+        .iter()
+        .map(|glyph| glyph.advance)
+        .max()
+        .unwrap_or(0)
+        .max(cell_width as u32);
+    let advance_width_max = u16::try_from(advance_width_max).map_err(|_| {
+        Error::new(format!(
+            "The advance width of {} font units is too large.",
+            advance_width_max
+        ))
+    })?;
+    // the caret leans with the glyphs, as a rise of one em over the matching run
+    let (caret_slope_rise, caret_slope_run) = if italic {
+        let rise = font.units_per_em as f64;
+        (rise, (rise * (-italic_angle).to_radians().tan()).round())
+    } else {
+        (1.0, 0.0)
+    };
+    let hhea = Hhea::new(
+        FWord::new(ascender),
+        FWord::new(descender),
+        FWord::new(line_gap),
+        UfWord::new(advance_width_max),
+        0.into(),
+        0.into(),
+        FWord::new(cell_width),
+        caret_slope_rise as i16,
+        caret_slope_run as i16,
+        0,
+        number_of_h_metrics,
+    );
+    builder
+        .add_table(&hhea)
+        .map_err(|e| Error::TableBuild(e.to_string()))?;
+
+    // hmtx table
+    builder
+        .add_table(&hmtx)
+        .map_err(|e| Error::TableBuild(e.to_string()))?;
+
+    // glyf / loca table. With a composite threshold, a glyph that repeats the outline of an
+    // earlier glyph, in place or moved, refers to that glyph instead of holding the points again.
+    // The masters of a variable font can differ where their default outlines don't, so every
+    // glyph keeps its own points there
+    let composite_threshold = if font.heavy_master.is_some() {
+        0
+    } else {
+        args.composite_threshold.unwrap_or(0)
+    };
+    let mut outlines = HashMap::new();
+    let mut has_composites = false;
+    let mut max_composite = (0, 0);
+    let mut glyf_builder = GlyfLocaBuilder::new();
+    let mut bbox: Option<Bbox> = None;
+    for (glyph_id, glyph) in font.glyphs.iter().enumerate() {
+        if glyph.path.elements().is_empty() {
+            glyf_builder
+                .add_glyph(&Glyph::Simple(SimpleGlyph::default()))
+                .map_err(|e| Error::TableBuild(e.to_string()))?;
+            continue;
+        }
+        let simple_glyph = SimpleGlyph::from_bezpath(&glyph.path)
+            .map_err(|e| Error::new(format!("Failed to build glyph {}: {:?}", glyph.name, e)))?;
+        let glyph_bbox = simple_glyph.bbox;
+        bbox = Some(bbox.map_or(glyph_bbox, |bbox| bbox.union(glyph_bbox)));
+
+        let point_count: usize = simple_glyph.contours.iter().map(|c| c.len()).sum();
+        let mut shared = None;
+        if composite_threshold > 0 && point_count >= composite_threshold as usize {
+            // the outline relative to the corner of its bounding box
+            let outline: Vec<Vec<_>> = simple_glyph
+                .contours
+                .iter()
+                .map(|contour| {
+                    contour
+                        .iter()
+                        .map(|p| (p.x - glyph_bbox.x_min, p.y - glyph_bbox.y_min, p.on_curve))
+                        .collect()
+                })
+                .collect();
+            match outlines.entry(outline) {
+                Entry::Occupied(entry) => shared = Some(*entry.get()),
+                Entry::Vacant(entry) => {
+                    entry.insert((glyph_id as u16, glyph_bbox));
+                }
+            }
+        }
+        let outline = match shared {
+            Some((base_id, base_bbox)) => {
+                has_composites = true;
+                max_composite = (
+                    max_composite.0.max(point_count as u16),
+                    max_composite.1.max(simple_glyph.contours.len() as u16),
+                );
+                let component = Component::new(
+                    GlyphId16::new(base_id),
+                    Anchor::Offset {
+                        x: glyph_bbox.x_min - base_bbox.x_min,
+                        y: glyph_bbox.y_min - base_bbox.y_min,
+                    },
+                    Transform::default(),
+                    ComponentFlags::default(),
+                );
+                Glyph::Composite(CompositeGlyph::new(component, glyph_bbox))
+            }
+            None => Glyph::Simple(simple_glyph),
+        };
+        glyf_builder
+            .add_glyph(&outline)
+            .map_err(|e| Error::TableBuild(e.to_string()))?;
+    }
+    let (glyf, loca, _) = glyf_builder.build();
+    builder
+        .add_table(&glyf)
+        .map_err(|e| Error::TableBuild(e.to_string()))?;
+    builder
+        .add_table(&loca)
+        .map_err(|e| Error::TableBuild(e.to_string()))?;
+
+    // maxp table
+    let maxp = Maxp {
+        num_glyphs: glyph_count,
+        max_points: Some(font.max_point),
+        max_contours: Some(font.max_contour_count),
+        max_composite_points: Some(max_composite.0),
+        max_composite_contours: Some(max_composite.1),
+        max_zones: Some(2),
+        max_twilight_points: Some(0),
+        max_storage: Some(1),
+        max_function_defs: Some(1),
+        max_instruction_defs: Some(0),
+        max_stack_elements: Some(
+            u16::try_from(glyph_width as u64 * glyph_height as u64).map_err(|_| {
+                Error::new(format!(
+                    "The {}x{} glyph cell has too many pixels.",
+                    glyph_width, glyph_height
+                ))
+            })?,
+        ),
+        max_size_of_instructions: Some(0),
+        // a composite glyph has a single component, which is a simple glyph
+        max_component_elements: Some(if has_composites { 1 } else { 0 }),
+        max_component_depth: Some(if has_composites { 1 } else { 0 }),
+    };
+    builder
+        .add_table(&maxp)
+        .map_err(|e| Error::TableBuild(e.to_string()))?;
+
+    // head table, bounding the ink of every glyph. Without any ink, the box is the glyph cell
+    let (x_min, y_min, x_max, y_max) = match bbox {
+        Some(bbox) => (bbox.x_min, bbox.y_min, bbox.x_max, bbox.y_max),
+        None => (0, descender, cell_width, ascender),
+    };
+    // a fixed timestamp makes the same sprite build the same bytes
+    let timestamp = args
+        .created_timestamp
+        .unwrap_or_else(|| Utc::now().timestamp());
+    let head = Head::new(
+        font_revision(args.font_version.as_deref()),
+        0,
+        Flags::from_bits_truncate(0b0000000000001011),
+        font.units_per_em as u16,
+        LongDateTime::new(timestamp),
+        LongDateTime::new(timestamp),
+        x_min,
+        y_min,
+        x_max,
+        y_max,
+        match (bold, italic_style) {
+            (false, false) => MacStyle::empty(),
+            (true, false) => MacStyle::BOLD,
+            (false, true) => MacStyle::ITALIC,
+            (true, true) => MacStyle::BOLD | MacStyle::ITALIC,
+        },
+        8,
+        0,
+    );
+    builder
+        .add_table(&head)
+        .map_err(|e| Error::TableBuild(e.to_string()))?;
+
+    // GSUB table
+    if !font.figure_pairs.is_empty() || !font.ligatures.is_empty() {
+        let gsub = build_gsub(&font.figure_pairs, &font.ligatures);
+        builder
+            .add_table(&gsub)
+            .map_err(|e| Error::TableBuild(e.to_string()))?;
+    }
+
+    // COLR / CPAL tables, drawing each color glyph with its layers over the palettes
+    if !font.color_layers.is_empty() {
+        let (colr, cpal) = build_color_tables(&font.palettes, &font.color_layers);
+        builder
+            .add_table(&colr)
+            .map_err(|e| Error::TableBuild(e.to_string()))?;
+        builder
+            .add_table(&cpal)
+            .map_err(|e| Error::TableBuild(e.to_string()))?;
+    }
+
+    // GPOS table
+    if args.pixel_size.is_some() || !font.kerning.is_empty() {
+        let gpos = build_gpos(args.pixel_size, &font.kerning)?;
+        builder
+            .add_table(&gpos)
+            .map_err(|e| Error::TableBuild(e.to_string()))?;
+    }
+
+    // fvar / avar / gvar tables
+    if let Some(heavy_master) = &font.heavy_master {
+        add_variation_tables(
+            &mut builder,
+            font,
+            heavy_master,
+            weight_class(args),
+            args.max_weight.unwrap_or(900),
+        )?;
+    }
+
+    // hdmx / LTSH tables
+    if let Some(ppems) = &args.device_metrics {
+        let hdmx = build_hdmx(font, ppems)?;
+        builder.add_raw(Tag::new(b"hdmx"), hdmx);
+        builder.add_raw(Tag::new(b"LTSH"), build_ltsh(font.glyphs.len()));
+    }
+
+    // sbix table
+    if args.embed_bitmaps.unwrap_or(false) {
+        // the bitmaps are the cells as they are drawn, which a synthetic bold doesn't widen
+        if synthetic_bold(args) {
+            return Err(Error::new(
+                "Embedded bitmaps can't be used with a synthetic bold, as they hold the pixels of the cells as they are drawn.".to_string(),
+            ));
+        }
+        builder.add_raw(Tag::new(b"sbix"), build_sbix(font, args)?);
+    }
+
+    Ok(builder.build())
+}
+
+/// The weight class of the font, given directly or taken from the subfamily name.
+fn weight_class(args: &Params) -> u16 {
+    if let Some(weight_class) = args.font_weight {
+        return weight_class;
+    }
+    // the weight is named without the slope, as in "Bold Italic"
+    let weight = subfamily_words(args)
+        .into_iter()
+        .filter(|word| !is_slope(word))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let weight_class = match weight.as_str() {
+        "thin" => 100,
+        "extra-light" | "extralight" | "ultra-light" | "ultralight" => 200,
+        "light" => 300,
+        "regular" | "" => 400,
+        "medium" => 500,
+        "semibold" | "semi-bold" | "demi-bold" | "demibold" => 600,
+        "bold" => 700,
+        "extrabold" | "extra-bold" | "ultrabold" | "ultra-bold" => 800,
+        "black" | "heavy" => 900,
+        _ => 400,
+    };
+    // a synthetic bold is at least as heavy as Bold, whatever the subfamily is called
+    if synthetic_bold(args) {
+        weight_class.max(700)
+    } else {
+        weight_class
+    }
+}
+
+/// Whether the glyphs are made bold by widening their pixels.
+fn synthetic_bold(args: &Params) -> bool {
+    args.synthetic_bold.unwrap_or(0) > 0
+}
+
+/// The name IDs and strings of the names of a language, which take the English subfamily if
+/// the language has none.
+pub(crate) fn localized_strings(name: &LocalizedName, args: &Params) -> [(u16, String); 5] {
+    let subfamily = name.subfamily.clone().or_else(|| subfamily(args));
+    [
+        (
+            1,
+            match &subfamily {
+                Some(subfamily) => format!("{} {}", name.family, subfamily),
+                None => name.family.clone(),
+            },
+        ),
+        (2, subfamily.clone().unwrap_or("Regular".to_string())),
+        (4, name.family.clone()),
+        (16, name.family.clone()),
+        (17, subfamily.unwrap_or("Regular".to_string())),
+    ]
+}
+
+/// The subfamily given in the params, which is Bold by default for a synthetic bold.
+pub(crate) fn subfamily(args: &Params) -> Option<String> {
+    args.subfamily
+        .clone()
+        .or_else(|| synthetic_bold(args).then(|| "Bold".to_string()))
+}
+
+/// The lowercase words of the subfamily, which is Regular by default.
+fn subfamily_words(args: &Params) -> Vec<String> {
+    subfamily(args)
+        .as_deref()
+        .unwrap_or("regular")
+        .to_lowercase()
+        .split_whitespace()
+        .map(str::to_string)
+        .collect()
+}
+
+fn is_slope(word: &str) -> bool {
+    word == "italic" || word == "oblique"
+}
+
+/// Whether the subfamily is bold and whether it is italic.
+fn subfamily_style(args: &Params) -> (bool, bool) {
+    let words = subfamily_words(args);
+    (
+        synthetic_bold(args) || words.iter().any(|word| word == "bold"),
+        words.iter().any(|word| is_slope(word)),
+    )
+}
+
+/// The first and the last character of the OS/2 table. The null glyph is left out of the range,
+/// which starts at the space at the latest, and codepoints past the BMP are recorded as 0xFFFF.
+pub(crate) fn char_index_range(codepoints: impl IntoIterator<Item = u32>) -> (u16, u16) {
+    let codepoints: Vec<u16> = codepoints
+        .into_iter()
+        .map(|codepoint| codepoint.min(0xFFFF) as u16)
+        .filter(|&codepoint| codepoint >= 0x0020)
+        .collect();
+    (
+        codepoints.iter().copied().min().unwrap_or(0x0020),
+        codepoints.iter().copied().max().unwrap_or(0x0020),
+    )
+}
+
+/// Builds a format 4 cmap with a segment for each codepoint, in the Unicode, Macintosh (unless
+/// web optimized) and Windows encodings.
+pub(crate) fn build_cmap(cmap_entries: &[(u32, u16)], web_optimized: bool) -> Cmap {
+    let mut start_code = Vec::new();
+    let mut end_code = Vec::new();
+    let mut id_delta = Vec::new();
+    let mut id_range_offsets = Vec::new();
+    let glyph_id_array = Vec::new();
+    // the segments are searched in codepoint order, and since the entries are sorted by glyph ID
+    // after the codepoint, a codepoint drawn twice keeps its lowest glyph ID
+    let mut cmap_entries = cmap_entries.to_vec();
+    cmap_entries.sort();
+    cmap_entries.dedup_by_key(|&mut (codepoint, _)| codepoint);
+    // format 4 only reaches the Basic Multilingual Plane
+    for (codepoint, glyph_id) in cmap_entries.iter().filter(|(c, _)| *c <= 0xFFFF) {
+        let unicode = *codepoint as u16;
+        start_code.push(unicode);
+        end_code.push(unicode);
+        id_delta.push((*glyph_id as i32 - unicode as i32) as i16);
+        id_range_offsets.push(0);
+    }
+    start_code.push(0xFFFF);
+    end_code.push(0xFFFF);
+    id_delta.push(1);
+    id_range_offsets.push(0);
+
+    let subtable = CmapSubtable::format_4(
+        0,
+        end_code,
+        start_code,
+        id_delta,
+        id_range_offsets,
+        glyph_id_array,
+    );
+
+    // codepoints above U+FFFF need a format 12 subtable as well, which maps every codepoint
+    let full_subtable = cmap_entries.iter().any(|(c, _)| *c > 0xFFFF).then(|| {
+        CmapSubtable::format_12(
+            0,
+            cmap_entries
+                .iter()
+                .map(|&(codepoint, glyph_id)| {
+                    SequentialMapGroup::new(codepoint, codepoint, glyph_id as u32)
+                })
+                .collect(),
+        )
+    });
+
+    // the records are sorted by platform and encoding
+    let mut encoding_records = vec![EncodingRecord {
+        platform_id: PlatformId::Unicode,
+        encoding_id: 3,
+        subtable: OffsetMarker::new(subtable.clone()),
+    }];
+    if let Some(full_subtable) = &full_subtable {
+        encoding_records.push(EncodingRecord {
+            platform_id: PlatformId::Unicode,
+            encoding_id: 4,
+            subtable: OffsetMarker::new(full_subtable.clone()),
+        });
+    }
+    if !web_optimized {
+        encoding_records.push(EncodingRecord {
+            platform_id: PlatformId::Macintosh,
+            encoding_id: 0,
+            subtable: OffsetMarker::new(subtable.clone()),
+        });
+    }
+    encoding_records.push(EncodingRecord {
+        platform_id: PlatformId::Windows,
+        encoding_id: 1,
+        subtable: OffsetMarker::new(subtable),
+    });
+    if let Some(full_subtable) = full_subtable {
+        encoding_records.push(EncodingRecord {
+            platform_id: PlatformId::Windows,
+            encoding_id: 10,
+            subtable: OffsetMarker::new(full_subtable),
+        });
+    }
+    Cmap::new(encoding_records)
+}
+
+/// Builds the device metrics for the native size of the font and the given sizes, so that old
+/// rasterizers use the exact advances in whole pixels instead of scaling and rounding them.
+fn build_hdmx(font: &FontData, ppems: &[u8]) -> Result<Vec<u8>, Error> {
+    let native = font.units_per_em / font.units_per_pixel;
+    let mut sizes = vec![u8::try_from(native).map_err(|_| {
+        Error::new(format!(
+            "The font is {} pixels per em, but device metrics only go up to 255.",
+            native
+        ))
+    })?];
+    if ppems.contains(&0) {
+        return Err(Error::new(
+            "Device metrics need sizes of at least 1 pixel per em.".to_string(),
+        ));
+    }
+    sizes.extend_from_slice(ppems);
+    sizes.sort();
+    sizes.dedup();
+
+    // each record is padded to a multiple of four bytes
+    let record_size = (2 + font.glyphs.len()).div_ceil(4) * 4;
+    let mut data = Vec::with_capacity(8 + sizes.len() * record_size);
+    data.extend_from_slice(&0u16.to_be_bytes());
+    data.extend_from_slice(&(sizes.len() as i16).to_be_bytes());
+    data.extend_from_slice(&(record_size as i32).to_be_bytes());
+    for ppem in sizes {
+        let mut widths = Vec::with_capacity(font.glyphs.len());
+        for glyph in &font.glyphs {
+            let upem = font.units_per_em as u64;
+            let width = (glyph.advance as u64 * ppem as u64 + upem / 2) / upem;
+            widths.push(u8::try_from(width).map_err(|_| {
+                Error::new(format!(
+                    "Glyph {} is {} pixels wide at {} pixels per em, which is too wide for device metrics.",
+                    glyph.name, width, ppem
+                ))
+            })?);
+        }
+        data.push(ppem);
+        data.push(widths.iter().copied().max().unwrap_or(0));
+        data.extend_from_slice(&widths);
+        data.resize(data.len() + record_size - 2 - widths.len(), 0);
+    }
+    Ok(data)
+}
+
+/// Builds a linear threshold table. The glyphs have no instructions, so they scale linearly from
+/// the smallest size.
+fn build_ltsh(glyph_count: usize) -> Vec<u8> {
+    let mut data = Vec::with_capacity(4 + glyph_count);
+    data.extend_from_slice(&0u16.to_be_bytes());
+    data.extend_from_slice(&(glyph_count as u16).to_be_bytes());
+    data.resize(4 + glyph_count, 1);
+    data
+}
+
+/// Builds a `kern` feature with the kerning pairs and a `size` feature with the pixel size, for
+/// the ones that are given.
+fn build_gpos(pixel_size: Option<f32>, kerning: &[(u16, u16, i16)]) -> Result<Gpos, Error> {
+    let mut features = vec![];
+    let mut lookups = vec![];
+
+    // the pairs are sorted by their first and then their second glyph, as the coverage needs
+    if !kerning.is_empty() {
+        let mut pair_sets: Vec<(u16, Vec<PairValueRecord>)> = vec![];
+        for &(left, right, adjustment) in kerning {
+            let record = PairValueRecord::new(
+                GlyphId16::new(right),
+                ValueRecord::new().with_x_advance(adjustment),
+                ValueRecord::new(),
+            );
+            match pair_sets.last_mut() {
+                Some((first, records)) if *first == left => records.push(record),
+                _ => pair_sets.push((left, vec![record])),
+            }
+        }
+        let (first_glyphs, pair_sets): (Vec<_>, Vec<_>) = pair_sets
+            .into_iter()
+            .map(|(first, records)| (GlyphId16::new(first), PairSet::new(records)))
+            .unzip();
+        lookups.push(PositionLookup::Pair(Lookup::new(
+            LookupFlag::empty(),
+            vec![PairPos::format_1(
+                CoverageTable::format_1(first_glyphs),
+                pair_sets,
+            )],
+        )));
+        features.push(FeatureRecord::new(
+            Tag::new(b"kern"),
+            Feature::new(None, vec![0]),
+        ));
+    }
+
+    // the size the font is designed to be displayed at, which has no lookups and doesn't change
+    // any glyph
+    if let Some(pixel_size) = pixel_size {
+        // pixels at 96 dpi to decipoints
+        let design_size = (pixel_size as f64 * 7.5).round();
+        if !(1.0..=u16::MAX as f64).contains(&design_size) {
+            return Err(Error::new(format!(
+                "The pixel size must be a positive number no larger than {}.",
+                u16::MAX as f64 / 7.5
+            )));
+        }
+
+        let size = Feature::new(
+            Some(FeatureParams::Size(SizeParams::new(
+                design_size as u16,
+                0,
+                0,
+                0,
+                0,
+            ))),
+            vec![],
+        );
+        features.push(FeatureRecord::new(Tag::new(b"size"), size));
+    }
+
+    // feature records are sorted by tag, which is the order they are added in
+    let feature_indices: Vec<u16> = (0..features.len() as u16).collect();
+    let scripts = ScriptList::new(
+        [Tag::new(b"DFLT"), Tag::new(b"latn")]
+            .into_iter()
+            .map(|tag| {
+                ScriptRecord::new(
+                    tag,
+                    Script::new(Some(LangSys::new(feature_indices.clone())), vec![]),
+                )
+            })
+            .collect(),
+    );
+    Ok(Gpos::new(
+        scripts,
+        FeatureList::new(features),
+        LookupList::new(lookups),
+    ))
+}
+
+/// Builds a version 0 COLR table, which stacks the layer glyphs of each color glyph, and a CPAL
+/// table with the palettes of opaque colors, of which the first one is the default.
+fn build_color_tables(
+    palettes: &[Vec<[u8; 3]>],
+    color_layers: &[(u16, Vec<(u16, u16)>)],
+) -> (Colr, Cpal) {
+    let mut base_glyphs = vec![];
+    let mut layers = vec![];
+    for (glyph_id, glyph_layers) in color_layers {
+        base_glyphs.push(BaseGlyph::new(
+            GlyphId16::new(*glyph_id),
+            layers.len() as u16,
+            glyph_layers.len() as u16,
+        ));
+        layers.extend(
+            glyph_layers.iter().map(|&(layer_id, palette_index)| {
+                Layer::new(GlyphId16::new(layer_id), palette_index)
+            }),
+        );
+    }
+    let colr = Colr::new(
+        base_glyphs.len() as u16,
+        Some(base_glyphs),
+        Some(layers.clone()),
+        layers.len() as u16,
+    );
+
+    // the palettes are stored one after another, each as long as the first one
+    let entry_count = palettes[0].len() as u16;
+    let colors: Vec<ColorRecord> = palettes
+        .iter()
+        .flatten()
+        .map(|&[red, green, blue]| ColorRecord::new(blue, green, red, 255))
+        .collect();
+    let cpal = Cpal::new(
+        entry_count,
+        palettes.len() as u16,
+        colors.len() as u16,
+        Some(colors),
+        (0..palettes.len() as u16)
+            .map(|i| i * entry_count)
+            .collect(),
+    );
+    (colr, cpal)
+}
+
+/// Builds a `liga` feature that joins the components of each ligature, and `tnum` and `pnum`
+/// features that switch between the two styles of figures.
+fn build_gsub(figure_pairs: &[(u16, u16)], ligatures: &[(Vec<u16>, u16)]) -> Gsub {
+    let mut lookups = vec![];
+    let mut features = vec![];
+    if !ligatures.is_empty() {
+        // ligatures are grouped by their first component, and the longest ones are tried first
+        let mut sets: BTreeMap<u16, Vec<(&[u16], u16)>> = BTreeMap::new();
+        for (components, glyph_id) in ligatures {
+            sets.entry(components[0])
+                .or_default()
+                .push((&components[1..], *glyph_id));
+        }
+        let (first, sets): (Vec<_>, Vec<_>) = sets
+            .into_iter()
+            .map(|(first, mut set)| {
+                set.sort_by_key(|&(rest, _)| std::cmp::Reverse(rest.len()));
+                let set = set
+                    .into_iter()
+                    .map(|(rest, glyph_id)| {
+                        Ligature::new(
+                            GlyphId16::new(glyph_id),
+                            rest.iter().map(|&id| GlyphId16::new(id)).collect(),
+                        )
+                    })
+                    .collect();
+                (GlyphId16::new(first), LigatureSet::new(set))
+            })
+            .unzip();
+        features.push(FeatureRecord::new(
+            Tag::new(b"liga"),
+            Feature::new(None, vec![lookups.len() as u16]),
+        ));
+        lookups.push(SubstitutionLookup::Ligature(Lookup::new(
+            LookupFlag::empty(),
+            vec![LigatureSubstFormat1::new(
+                CoverageTable::format_1(first),
+                sets,
+            )],
+        )));
+    }
+
+    if !figure_pairs.is_empty() {
+        let single_subst = |mut pairs: Vec<(u16, u16)>| {
+            pairs.sort();
+            let (from, to): (Vec<_>, Vec<_>) = pairs
+                .into_iter()
+                .map(|(from, to)| (GlyphId16::new(from), GlyphId16::new(to)))
+                .unzip();
+            SubstitutionLookup::Single(Lookup::new(
+                LookupFlag::empty(),
+                vec![SingleSubst::format_2(CoverageTable::format_1(from), to)],
+            ))
+        };
+        let tabular = lookups.len() as u16;
+        lookups.push(single_subst(figure_pairs.to_vec()));
+        lookups.push(single_subst(
+            figure_pairs.iter().map(|&(p, t)| (t, p)).collect(),
+        ));
+        features.push(FeatureRecord::new(
+            Tag::new(b"pnum"),
+            Feature::new(None, vec![tabular + 1]),
+        ));
+        features.push(FeatureRecord::new(
+            Tag::new(b"tnum"),
+            Feature::new(None, vec![tabular]),
+        ));
+    }
+
+    // feature records are sorted by tag, which is the order they are added in
+    let feature_indices: Vec<u16> = (0..features.len() as u16).collect();
+    let scripts = ScriptList::new(
+        [Tag::new(b"DFLT"), Tag::new(b"latn")]
+            .into_iter()
+            .map(|tag| {
+                ScriptRecord::new(
+                    tag,
+                    Script::new(Some(LangSys::new(feature_indices.clone())), vec![]),
+                )
+            })
+            .collect(),
+    );
+    Gsub::new(
+        scripts,
+        FeatureList::new(features),
+        LookupList::new(lookups),
+    )
+}
+
+/// Reads the revision from a version string such as `Version 2.3` or `2.3`, which is 1.0 when
+/// the string has no number.
+fn font_revision(version: Option<&str>) -> Fixed {
+    let revision = version
+        .map(|version| {
+            let version = version.trim();
+            version
+                .get(.."version".len())
+                .filter(|prefix| prefix.eq_ignore_ascii_case("version"))
+                .map_or(version, |prefix| version[prefix.len()..].trim_start())
+        })
+        .and_then(|version| {
+            let end = version
+                .find(|c: char| !c.is_ascii_digit() && c != '.')
+                .unwrap_or(version.len());
+            version[..end].parse::<f64>().ok()
+        })
+        .filter(|revision| (0.0..32768.0).contains(revision))
+        .unwrap_or(1.0);
+    Fixed::from_f64(revision)
+}
+
+/// Validates an italic angle in degrees, counter-clockwise from vertical.
+fn italic_angle(value: f32) -> Result<f64, Error> {
+    if value.is_nan() || value <= -90.0 || value >= 90.0 {
+        return Err(Error::new(format!(
+            "The italic angle must be between -90 and 90 degrees, but it is {}.",
+            value
+        )));
+    }
+    Ok(value as f64)
+}
+
+/// Validates an OS/2 sFamilyClass, where the high byte is the class ID and the low byte is the
+/// subclass ID.
+fn family_class(value: i16) -> Result<i16, Error> {
+    let (class, subclass) = (value >> 8, value & 0xFF);
+    // classes 6, 11, 13 and 14 are reserved, and subclasses only go up to 15 (miscellaneous)
+    let valid = match class {
+        0 => subclass == 0,
+        1..=5 | 7..=10 | 12 => subclass <= 15,
+        _ => false,
+    };
+    if !valid {
+        return Err(Error::new(format!(
+            "{} is not a valid family class. The high byte must be a class ID from 0 to 12 other than the reserved 6 and 11, and the low byte a subclass ID from 0 to 15.",
+            value
+        )));
+    }
+    Ok(value)
+}
+
+/// Checks a selection of name IDs, which may only leave out the optional ones.
+fn check_name_ids(name_ids: &[u16]) -> Result<(), Error> {
+    let join = |ids: &[u16]| {
+        ids.iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    if let Some(id) = name_ids.iter().find(|id| !NAME_IDS.contains(id)) {
+        return Err(Error::new(format!(
+            "Name ID {} is not supported. The supported IDs are {}.",
+            id,
+            join(&NAME_IDS)
+        )));
+    }
+    if let Some(id) = REQUIRED_NAME_IDS.iter().find(|id| !name_ids.contains(id)) {
+        return Err(Error::new(format!(
+            "Name ID {} can't be left out. The name table needs at least IDs {}.",
+            id,
+            join(&REQUIRED_NAME_IDS)
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,260 @@
+use std::collections::BTreeSet;
+
+use kurbo::{BezPath, PathEl, Point};
+use write_fonts::FontBuilder;
+use write_fonts::tables::avar::{Avar, AxisValueMap, SegmentMaps};
+use write_fonts::tables::fvar::{AxisInstanceArrays, Fvar, VariationAxisRecord};
+use write_fonts::tables::glyf::SimpleGlyph;
+use write_fonts::tables::gvar::{GlyphDelta, GlyphDeltas, GlyphVariations, Gvar, Tent};
+use write_fonts::types::{F2Dot14, Fixed, GlyphId, NameId, Tag};
+
+use crate::Error;
+use crate::edge::drop_collinear;
+use crate::font::{FontData, GlyphData};
+
+/// The name ID of the weight axis, the first ID that is free for font-specific names.
+pub(crate) const WEIGHT_AXIS_NAME_ID: u16 = 256;
+
+/// Pairs the glyphs of the heavier master with the glyphs of `font`, so that each glyph can be
+/// interpolated between them. Both masters are traced from the same cells, so their glyphs line
+/// up unless a cell is drawn in only one of them. The contours of each glyph are matched by the
+/// positions of their top-left points, which stay in place when strokes get thicker.
+pub(crate) fn match_masters(font: &mut FontData, heavy: FontData) -> Result<(), Error> {
+    let codepoints: BTreeSet<u32> = font.cmap_entries.iter().map(|&(c, _)| c).collect();
+    let heavy_codepoints: BTreeSet<u32> = heavy.cmap_entries.iter().map(|&(c, _)| c).collect();
+    if let Some(codepoint) = codepoints.symmetric_difference(&heavy_codepoints).next() {
+        return Err(Error::new(format!(
+            "U+{:04X} is drawn in only one of the masters.",
+            codepoint
+        )));
+    }
+    // alternate figures have no codepoint of their own
+    if let Some(name) = font
+        .glyphs
+        .iter()
+        .zip(&heavy.glyphs)
+        .find(|(light, heavy)| light.name != heavy.name)
+        .map(|(light, _)| light.name.clone())
+        .or_else(|| {
+            let count = font.glyphs.len().min(heavy.glyphs.len());
+            font.glyphs[count..]
+                .iter()
+                .chain(&heavy.glyphs[count..])
+                .next()
+                .map(|glyph| glyph.name.clone())
+        })
+    {
+        return Err(Error::new(format!(
+            "{} is drawn in only one of the masters.",
+            name
+        )));
+    }
+
+    let mut heavy_glyphs = heavy.glyphs;
+    for (glyph_id, (light, heavy)) in font.glyphs.iter_mut().zip(&mut heavy_glyphs).enumerate() {
+        let label = font
+            .cmap_entries
+            .iter()
+            .find(|&&(_, id)| id as usize == glyph_id)
+            .map(|&(codepoint, _)| format!("U+{:04X}", codepoint))
+            .unwrap_or_else(|| light.name.clone());
+
+        let light_contours = canonical_contours(&light.path);
+        let heavy_contours = canonical_contours(&heavy.path);
+        if light_contours.len() != heavy_contours.len() {
+            return Err(Error::new(format!(
+                "{} has {} in the first master but {} in the second, so it can't be interpolated.",
+                label,
+                count(light_contours.len(), "contour"),
+                heavy_contours.len()
+            )));
+        }
+        for (i, (a, b)) in light_contours.iter().zip(&heavy_contours).enumerate() {
+            if a.len() != b.len() {
+                return Err(Error::new(format!(
+                    "Contour {} of {} has {} in the first master but {} in the second, so it can't be interpolated.",
+                    i + 1,
+                    label,
+                    count(a.len(), "point"),
+                    b.len()
+                )));
+            }
+        }
+        light.path = contours_to_path(&light_contours);
+        heavy.path = contours_to_path(&heavy_contours);
+    }
+    font.heavy_master = Some(heavy_glyphs);
+    Ok(())
+}
+
+/// Writes a count followed by the noun, which is plural unless there is one.
+fn count(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
+
+/// Splits a traced path into the corners of its contours, each starting at its top-left point,
+/// and orders the contours from the top-left as well.
+fn canonical_contours(path: &BezPath) -> Vec<Vec<Point>> {
+    let mut contours: Vec<Vec<Point>> = vec![];
+    for el in path.elements() {
+        match *el {
+            PathEl::MoveTo(p) => contours.push(vec![p]),
+            PathEl::LineTo(p) => {
+                if let Some(contour) = contours.last_mut() {
+                    contour.push(p);
+                }
+            }
+            PathEl::ClosePath => {
+                // the traced contours return to their start before closing
+                if let Some(contour) = contours
+                    .last_mut()
+                    .filter(|c| c.len() > 1 && c.first() == c.last())
+                {
+                    contour.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // only the corners are compared, so a stroke can be drawn longer or thicker in one master
+    let mut contours: Vec<Vec<Point>> = contours
+        .into_iter()
+        .map(|contour| {
+            drop_collinear(contour.iter().map(|p| (p.x, p.y)).collect())
+                .into_iter()
+                .map(Point::from)
+                .collect()
+        })
+        .collect();
+
+    let top_left = |p: &Point| (-p.y, p.x);
+    for contour in contours.iter_mut() {
+        let start = (0..contour.len())
+            .min_by(|&a, &b| {
+                top_left(&contour[a])
+                    .partial_cmp(&top_left(&contour[b]))
+                    .unwrap()
+            })
+            .unwrap_or(0);
+        contour.rotate_left(start);
+    }
+    contours.sort_by(|a, b| top_left(&a[0]).partial_cmp(&top_left(&b[0])).unwrap());
+    contours
+}
+
+fn contours_to_path(contours: &[Vec<Point>]) -> BezPath {
+    let mut path = BezPath::new();
+    for contour in contours {
+        path.move_to(contour[0]);
+        for &p in &contour[1..] {
+            path.line_to(p);
+        }
+        path.line_to(contour[0]);
+        path.close_path();
+    }
+    path
+}
+
+/// Adds the `fvar`, `avar` and `gvar` tables of a font with a weight axis from `weight`, where
+/// the glyphs are those of the first master, to `max_weight`, where they are those of the second.
+pub(crate) fn add_variation_tables(
+    builder: &mut FontBuilder,
+    font: &FontData,
+    heavy_master: &[GlyphData],
+    weight: u16,
+    max_weight: u16,
+) -> Result<(), Error> {
+    if max_weight <= weight {
+        return Err(Error::new(format!(
+            "The weight of the second master {} must be greater than the weight {} of the first.",
+            max_weight, weight
+        )));
+    }
+
+    // fvar table
+    let axis = VariationAxisRecord {
+        axis_tag: Tag::new(b"wght"),
+        min_value: Fixed::from_i32(weight as i32),
+        default_value: Fixed::from_i32(weight as i32),
+        max_value: Fixed::from_i32(max_weight as i32),
+        flags: 0,
+        axis_name_id: NameId::new(WEIGHT_AXIS_NAME_ID),
+    };
+    let fvar = Fvar::new(AxisInstanceArrays::new(vec![axis], vec![]));
+    builder
+        .add_table(&fvar)
+        .map_err(|e| Error::TableBuild(e.to_string()))?;
+
+    // avar table, which maps the axis linearly
+    let avar = Avar::new(vec![SegmentMaps::new(
+        [-1.0, 0.0, 1.0]
+            .into_iter()
+            .map(|value| AxisValueMap::new(F2Dot14::from_f32(value), F2Dot14::from_f32(value)))
+            .collect(),
+    )]);
+    builder
+        .add_table(&avar)
+        .map_err(|e| Error::TableBuild(e.to_string()))?;
+
+    // gvar table, moving every point to its place in the second master at the maximum weight
+    let mut variations = Vec::with_capacity(font.glyphs.len());
+    for (glyph_id, (light, heavy)) in font.glyphs.iter().zip(heavy_master).enumerate() {
+        let light_glyph = simple_glyph(light)?;
+        let heavy_glyph = simple_glyph(heavy)?;
+        let mut deltas: Vec<GlyphDelta> = light_glyph
+            .contours
+            .iter()
+            .flat_map(|contour| contour.iter())
+            .zip(
+                heavy_glyph
+                    .contours
+                    .iter()
+                    .flat_map(|contour| contour.iter()),
+            )
+            .map(|(a, b)| GlyphDelta::required(b.x - a.x, b.y - a.y))
+            .collect();
+
+        // the phantom points at the origin and the advance, then the unused vertical ones
+        let origin = |glyph: &SimpleGlyph, data: &GlyphData| glyph.bbox.x_min as i32 - data.lsb;
+        let origin_delta = origin(&heavy_glyph, heavy) - origin(&light_glyph, light);
+        let advance_delta = origin_delta + heavy.advance as i32 - light.advance as i32;
+        deltas.extend([
+            GlyphDelta::required(origin_delta as i16, 0),
+            GlyphDelta::required(advance_delta as i16, 0),
+            GlyphDelta::required(0, 0),
+            GlyphDelta::required(0, 0),
+        ]);
+
+        let varies = deltas.iter().any(|delta| delta.x != 0 || delta.y != 0);
+        variations.push(GlyphVariations::new(
+            GlyphId::new(glyph_id as u32),
+            if varies {
+                vec![GlyphDeltas::new(
+                    vec![Tent::new(F2Dot14::ONE, None)],
+                    deltas,
+                )]
+            } else {
+                vec![]
+            },
+        ));
+    }
+    let gvar = Gvar::new(variations, 1).map_err(|e| Error::TableBuild(format!("{:?}", e)))?;
+    builder
+        .add_table(&gvar)
+        .map_err(|e| Error::TableBuild(e.to_string()))?;
+
+    Ok(())
+}
+
+fn simple_glyph(glyph: &GlyphData) -> Result<SimpleGlyph, Error> {
+    if glyph.path.elements().is_empty() {
+        return Ok(SimpleGlyph::default());
+    }
+    SimpleGlyph::from_bezpath(&glyph.path)
+        .map_err(|e| Error::new(format!("Failed to build glyph {}: {:?}", glyph.name, e)))
+}
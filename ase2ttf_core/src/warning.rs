@@ -0,0 +1,105 @@
+use std::fmt::Display;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    CoverageMismatch, IgnoredLayer, SimplifiedGlyph, SkippedAdvance, SkippedKerningPair,
+    UnevenTrimScale, parse_codepoint,
+};
+
+/// What a [`Warning`] is about.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WarningKind {
+    /// A codepoint is drawn in some frames of its layer but left blank in others.
+    FrameCoverage,
+    /// A glyph was simplified to fit within `max_points`.
+    SimplifiedGlyph,
+    /// Trimmed advances were rounded to whole pixels, so text may be spaced unevenly.
+    UnevenTrimScale,
+    /// A kerning pair was left out, because one of its codepoints has no glyph.
+    SkippedKerningPair,
+    /// An advance from the advances file was left out, because its codepoint has no glyph.
+    SkippedAdvance,
+    /// A layer was left out, because its name starts like a codepoint but doesn't parse.
+    IgnoredLayer,
+}
+
+/// A problem that doesn't stop the font from being generated, but that the font's author most
+/// likely wants to know about.
+#[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Warning {
+    pub kind: WarningKind,
+    /// The codepoints of the glyphs that the warning is about.
+    pub codepoints: Vec<u32>,
+    pub message: String,
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl From<CoverageMismatch> for Warning {
+    fn from(mismatch: CoverageMismatch) -> Warning {
+        Warning {
+            kind: WarningKind::FrameCoverage,
+            codepoints: vec![mismatch.codepoint],
+            message: mismatch.to_string(),
+        }
+    }
+}
+
+impl From<SimplifiedGlyph> for Warning {
+    fn from(glyph: SimplifiedGlyph) -> Warning {
+        Warning {
+            kind: WarningKind::SimplifiedGlyph,
+            // simplified glyphs are named after their codepoint
+            codepoints: parse_codepoint(&glyph.name).into_iter().collect(),
+            message: glyph.to_string(),
+        }
+    }
+}
+
+impl From<UnevenTrimScale> for Warning {
+    fn from(uneven: UnevenTrimScale) -> Warning {
+        Warning {
+            kind: WarningKind::UnevenTrimScale,
+            message: uneven.to_string(),
+            codepoints: uneven.codepoints,
+        }
+    }
+}
+
+impl From<SkippedKerningPair> for Warning {
+    fn from(pair: SkippedKerningPair) -> Warning {
+        Warning {
+            kind: WarningKind::SkippedKerningPair,
+            message: pair.to_string(),
+            codepoints: pair.missing,
+        }
+    }
+}
+
+impl From<SkippedAdvance> for Warning {
+    fn from(advance: SkippedAdvance) -> Warning {
+        Warning {
+            kind: WarningKind::SkippedAdvance,
+            message: advance.to_string(),
+            codepoints: vec![advance.codepoint],
+        }
+    }
+}
+
+impl From<IgnoredLayer> for Warning {
+    fn from(layer: IgnoredLayer) -> Warning {
+        Warning {
+            kind: WarningKind::IgnoredLayer,
+            message: layer.to_string(),
+            codepoints: vec![],
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use std::io::Write;
+
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+use write_fonts::read::FontRef;
+use write_fonts::types::Tag;
+
+use crate::Error;
+
+const HEADER_SIZE: usize = 44;
+const DIRECTORY_ENTRY_SIZE: usize = 20;
+
+/// A table of an OpenType font, as it is stored in the font.
+struct SfntTable<'a> {
+    tag: Tag,
+    checksum: u32,
+    data: &'a [u8],
+}
+
+fn build_error(e: impl std::fmt::Display) -> Error {
+    Error::TableBuild(format!("Failed to pack the font: {}", e))
+}
+
+/// Reads the version and the tables of an OpenType font, sorted by tag.
+fn sfnt_tables(sfnt: &[u8]) -> Result<(u32, Vec<SfntTable<'_>>), Error> {
+    let font = FontRef::new(sfnt).map_err(build_error)?;
+    let directory = &font.table_directory;
+    let mut tables = directory
+        .table_records()
+        .iter()
+        .map(|record| {
+            let start = record.offset() as usize;
+            let data = sfnt
+                .get(start..start + record.length() as usize)
+                .ok_or_else(|| {
+                    build_error(format!("the {} table is out of bounds", record.tag()))
+                })?;
+            Ok(SfntTable {
+                tag: record.tag(),
+                checksum: record.checksum(),
+                data,
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    tables.sort_by_key(|table| table.tag);
+    Ok((directory.sfnt_version(), tables))
+}
+
+/// The size of the unpacked font, with the header, the table directory and the padded tables.
+fn total_sfnt_size(tables: &[SfntTable]) -> usize {
+    12 + 16 * tables.len()
+        + tables
+            .iter()
+            .map(|table| table.data.len().next_multiple_of(4))
+            .sum::<usize>()
+}
+
+/// Wraps an OpenType font in a WOFF 1.0 container, with each table compressed on its own. The
+/// tables themselves are copied as they are, so the font unpacks to the same bytes.
+pub(crate) fn build_woff(sfnt: &[u8]) -> Result<Vec<u8>, Error> {
+    let (flavor, tables) = sfnt_tables(sfnt)?;
+
+    let mut stored_tables = vec![];
+    for table in &tables {
+        let mut encoder = ZlibEncoder::new(vec![], Compression::best());
+        encoder.write_all(table.data).map_err(build_error)?;
+        let compressed = encoder.finish().map_err(build_error)?;
+        // a table that doesn't get smaller is stored as it is
+        stored_tables.push(if compressed.len() < table.data.len() {
+            compressed
+        } else {
+            table.data.to_vec()
+        });
+    }
+
+    // every table starts on a 4-byte boundary
+    let mut offset = HEADER_SIZE + DIRECTORY_ENTRY_SIZE * tables.len();
+    let mut directory = vec![];
+    for (table, stored) in tables.iter().zip(&stored_tables) {
+        directory.extend(table.tag.to_be_bytes());
+        directory.extend((offset as u32).to_be_bytes());
+        directory.extend((stored.len() as u32).to_be_bytes());
+        directory.extend((table.data.len() as u32).to_be_bytes());
+        directory.extend(table.checksum.to_be_bytes());
+        offset += stored.len().next_multiple_of(4);
+    }
+    let length = offset;
+
+    let mut woff = Vec::with_capacity(length);
+    woff.extend(b"wOFF");
+    woff.extend(flavor.to_be_bytes());
+    woff.extend((length as u32).to_be_bytes());
+    woff.extend((tables.len() as u16).to_be_bytes());
+    woff.extend(0u16.to_be_bytes()); // reserved
+    woff.extend((total_sfnt_size(&tables) as u32).to_be_bytes());
+    woff.extend(1u16.to_be_bytes()); // major version
+    woff.extend(0u16.to_be_bytes()); // minor version
+    // no metadata or private data
+    woff.extend([0; 20]);
+    woff.extend(directory);
+    for stored in &stored_tables {
+        woff.extend(stored);
+        woff.resize(woff.len().next_multiple_of(4), 0);
+    }
+    Ok(woff)
+}
+
+/// The tags that a WOFF2 table directory refers to by their index, in the order of the spec.
+#[cfg(feature = "woff2")]
+const WOFF2_KNOWN_TAGS: [&[u8; 4]; 63] = [
+    b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"OS/2", b"post", b"cvt ", b"fpgm",
+    b"glyf", b"loca", b"prep", b"CFF ", b"VORG", b"EBDT", b"EBLC", b"gasp", b"hdmx", b"kern",
+    b"LTSH", b"PCLT", b"VDMX", b"vhea", b"vmtx", b"BASE", b"GDEF", b"GPOS", b"GSUB", b"EBSC",
+    b"JSTF", b"MATH", b"CBDT", b"CBLC", b"COLR", b"CPAL", b"SVG ", b"sbix", b"acnt", b"avar",
+    b"bdat", b"bloc", b"bsln", b"cvar", b"fdsc", b"feat", b"fmtx", b"fvar", b"gvar", b"hsty",
+    b"just", b"lcar", b"mort", b"morx", b"opbd", b"prop", b"trak", b"Zapf", b"Silf", b"Glat",
+    b"Gloc", b"Feat", b"Sill",
+];
+
+/// Wraps an OpenType font in a WOFF2 container, with all tables compressed together with Brotli.
+/// Every table keeps the null transform, including `glyf` and `loca`, so the font unpacks to the
+/// same tables.
+#[cfg(feature = "woff2")]
+pub(crate) fn build_woff2(sfnt: &[u8]) -> Result<Vec<u8>, Error> {
+    const HEADER_SIZE: usize = 48;
+    // the transform version that leaves glyf and loca as they are. It is 0 for other tables
+    const GLYF_NULL_TRANSFORM: u8 = 3 << 6;
+
+    let (flavor, tables) = sfnt_tables(sfnt)?;
+
+    let mut directory = vec![];
+    for table in &tables {
+        let tag = table.tag.to_be_bytes();
+        let mut flags = match WOFF2_KNOWN_TAGS.iter().position(|&known| *known == tag) {
+            Some(index) => index as u8,
+            None => 63,
+        };
+        if &tag == b"glyf" || &tag == b"loca" {
+            flags |= GLYF_NULL_TRANSFORM;
+        }
+        directory.push(flags);
+        if flags & 63 == 63 {
+            directory.extend(tag);
+        }
+        push_uint_base128(&mut directory, table.data.len() as u32);
+    }
+
+    // the tables are compressed as one stream, without padding between them
+    let mut compressor = brotli::CompressorWriter::new(vec![], 4096, 11, 22);
+    for table in &tables {
+        compressor.write_all(table.data).map_err(build_error)?;
+    }
+    let compressed = compressor.into_inner();
+
+    let length = (HEADER_SIZE + directory.len() + compressed.len()).next_multiple_of(4);
+    let mut woff2 = Vec::with_capacity(length);
+    woff2.extend(b"wOF2");
+    woff2.extend(flavor.to_be_bytes());
+    woff2.extend((length as u32).to_be_bytes());
+    woff2.extend((tables.len() as u16).to_be_bytes());
+    woff2.extend(0u16.to_be_bytes()); // reserved
+    woff2.extend((total_sfnt_size(&tables) as u32).to_be_bytes());
+    woff2.extend((compressed.len() as u32).to_be_bytes());
+    woff2.extend(1u16.to_be_bytes()); // major version
+    woff2.extend(0u16.to_be_bytes()); // minor version
+    // no metadata or private data
+    woff2.extend([0; 20]);
+    woff2.extend(directory);
+    woff2.extend(compressed);
+    woff2.resize(length, 0);
+    Ok(woff2)
+}
+
+/// Writes a number in the variable-length encoding of WOFF2, seven bits at a time with the most
+/// significant ones first.
+#[cfg(feature = "woff2")]
+fn push_uint_base128(data: &mut Vec<u8>, value: u32) {
+    let mut bytes = vec![(value & 0x7F) as u8];
+    let mut rest = value >> 7;
+    while rest != 0 {
+        bytes.push((rest & 0x7F) as u8 | 0x80);
+        rest >>= 7;
+    }
+    data.extend(bytes.into_iter().rev());
+}
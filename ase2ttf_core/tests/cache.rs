@@ -0,0 +1,47 @@
+use ase2ttf_core::font_cache_key;
+
+mod common;
+
+use common::AseBuilder;
+
+fn sheet(art: &str) -> Vec<u8> {
+    let mut ase = AseBuilder::new(4, 4);
+    let layer = ase.layer("U+0041");
+    ase.draw(0, layer, 0, 0, art);
+    ase.build()
+}
+
+#[test]
+fn same_inputs_give_same_key() {
+    let ase = sheet("##");
+    let key = font_cache_key(&ase, &common::params(4));
+    assert_eq!(key, font_cache_key(&ase, &common::params(4)));
+    assert_eq!(key.len(), 16);
+    assert!(key.chars().all(|c| c.is_ascii_hexdigit()));
+}
+
+#[test]
+fn key_changes_with_file_and_params() {
+    let ase = sheet("##");
+    let key = font_cache_key(&ase, &common::params(4));
+
+    assert_ne!(key, font_cache_key(&sheet("#"), &common::params(4)));
+
+    let mut params = common::params(4);
+    params.trim = Some(false);
+    assert_ne!(key, font_cache_key(&ase, &params));
+
+    // an explicit default is still a different input
+    let mut params = common::params(4);
+    params.baseline = Some(2);
+    assert_ne!(key, font_cache_key(&ase, &params));
+
+    // strings are length-prefixed, so moving text between fields changes the key
+    let mut a = common::params(4);
+    a.family = Some("ab".to_string());
+    a.subfamily = Some("c".to_string());
+    let mut b = common::params(4);
+    b.family = Some("a".to_string());
+    b.subfamily = Some("bc".to_string());
+    assert_ne!(font_cache_key(&ase, &a), font_cache_key(&ase, &b));
+}
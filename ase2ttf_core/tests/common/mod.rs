@@ -0,0 +1,324 @@
+//! Builds small Aseprite files in memory so tests don't need binary fixtures.
+#![allow(dead_code)]
+
+use ase2ttf_core::Params;
+
+pub const INK: [u8; 4] = [0, 0, 0, 255];
+pub const EMPTY: [u8; 4] = [0, 0, 0, 0];
+
+/// Params for a sheet of square glyphs with everything else left at its default.
+pub fn params(glyph_size: u32) -> Params {
+    Params::builder()
+        .file_path("test.aseprite")
+        .glyph_size(glyph_size, glyph_size)
+        .build()
+}
+
+pub struct Layer {
+    pub name: String,
+    pub visible: bool,
+    pub group: bool,
+    pub child_level: u16,
+    pub opacity: u8,
+}
+
+struct Cel {
+    frame: u16,
+    layer: u16,
+    x: i16,
+    y: i16,
+    width: u16,
+    height: u16,
+    pixels: Vec<[u8; 4]>,
+}
+
+/// How the pixels of the built file are stored.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ColorMode {
+    Rgba,
+    /// A gray value and an alpha for each pixel.
+    Grayscale,
+    /// An index into a palette for each pixel, where index 0 is transparent.
+    Indexed,
+}
+
+pub struct AseBuilder {
+    width: u16,
+    height: u16,
+    frames: u16,
+    color_mode: ColorMode,
+    layers: Vec<Layer>,
+    cels: Vec<Cel>,
+    tags: Vec<(String, u16, u16)>,
+    slices: Vec<Slice>,
+}
+
+/// A slice with a single key on the first frame.
+pub struct Slice {
+    pub name: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub pivot: Option<(i32, i32)>,
+}
+
+impl AseBuilder {
+    pub fn new(width: u16, height: u16) -> AseBuilder {
+        AseBuilder {
+            width,
+            height,
+            frames: 1,
+            color_mode: ColorMode::Rgba,
+            layers: vec![],
+            cels: vec![],
+            tags: vec![],
+            slices: vec![],
+        }
+    }
+
+    pub fn frames(mut self, frames: u16) -> AseBuilder {
+        self.frames = frames;
+        self
+    }
+
+    pub fn color_mode(mut self, color_mode: ColorMode) -> AseBuilder {
+        self.color_mode = color_mode;
+        self
+    }
+
+    /// Adds a visible image layer and returns its index.
+    pub fn layer(&mut self, name: &str) -> u16 {
+        self.add_layer(Layer {
+            name: name.to_string(),
+            visible: true,
+            group: false,
+            child_level: 0,
+            opacity: 255,
+        })
+    }
+
+    pub fn add_layer(&mut self, layer: Layer) -> u16 {
+        self.layers.push(layer);
+        (self.layers.len() - 1) as u16
+    }
+
+    /// Places a cel drawn as ASCII art, where `#` is opaque and anything else is transparent.
+    pub fn draw(&mut self, frame: u16, layer: u16, x: i16, y: i16, art: &str) {
+        let rows: Vec<&str> = art
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .collect();
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        let mut pixels = vec![];
+        for row in &rows {
+            for i in 0..width {
+                pixels.push(if row.as_bytes().get(i) == Some(&b'#') {
+                    INK
+                } else {
+                    EMPTY
+                });
+            }
+        }
+        self.pixels(frame, layer, x, y, width as u16, rows.len() as u16, pixels);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn pixels(
+        &mut self,
+        frame: u16,
+        layer: u16,
+        x: i16,
+        y: i16,
+        width: u16,
+        height: u16,
+        pixels: Vec<[u8; 4]>,
+    ) {
+        assert_eq!(pixels.len(), width as usize * height as usize);
+        self.cels.push(Cel {
+            frame,
+            layer,
+            x,
+            y,
+            width,
+            height,
+            pixels,
+        });
+    }
+
+    pub fn tag(&mut self, name: &str, from: u16, to: u16) {
+        self.tags.push((name.to_string(), from, to));
+    }
+
+    pub fn slice(&mut self, slice: Slice) {
+        self.slices.push(slice);
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        // the colors of the drawn pixels, after the transparent color at index 0
+        let mut palette = vec![EMPTY];
+        if self.color_mode == ColorMode::Indexed {
+            for pixel in self.cels.iter().flat_map(|cel| &cel.pixels) {
+                if pixel[3] != 0 && !palette.contains(pixel) {
+                    palette.push(*pixel);
+                }
+            }
+        }
+
+        let mut frames = vec![];
+        for frame in 0..self.frames {
+            let mut chunks: Vec<Vec<u8>> = vec![];
+            if frame == 0 && self.color_mode == ColorMode::Indexed {
+                let mut data = vec![];
+                put_u32(&mut data, palette.len() as u32);
+                put_u32(&mut data, 0);
+                put_u32(&mut data, palette.len() as u32 - 1);
+                data.extend([0; 8]);
+                for color in &palette {
+                    put_u16(&mut data, 0);
+                    data.extend(color);
+                }
+                chunks.push(chunk(0x2019, data));
+            }
+            if frame == 0 {
+                for layer in &self.layers {
+                    let mut data = vec![];
+                    let flags: u16 = if layer.visible { 3 } else { 2 };
+                    put_u16(&mut data, flags);
+                    put_u16(&mut data, if layer.group { 1 } else { 0 });
+                    put_u16(&mut data, layer.child_level);
+                    put_u16(&mut data, 0);
+                    put_u16(&mut data, 0);
+                    put_u16(&mut data, 0); // normal blend mode
+                    data.push(layer.opacity);
+                    data.extend([0; 3]);
+                    put_string(&mut data, &layer.name);
+                    chunks.push(chunk(0x2004, data));
+                }
+                if !self.tags.is_empty() {
+                    let mut data = vec![];
+                    put_u16(&mut data, self.tags.len() as u16);
+                    data.extend([0; 8]);
+                    for (name, from, to) in &self.tags {
+                        put_u16(&mut data, *from);
+                        put_u16(&mut data, *to);
+                        data.push(0);
+                        put_u16(&mut data, 0);
+                        data.extend([0; 6]);
+                        data.extend([0; 3]);
+                        data.push(0);
+                        put_string(&mut data, name);
+                    }
+                    chunks.push(chunk(0x2018, data));
+                }
+                for slice in &self.slices {
+                    let mut data = vec![];
+                    put_u32(&mut data, 1);
+                    put_u32(&mut data, if slice.pivot.is_some() { 2 } else { 0 });
+                    put_u32(&mut data, 0);
+                    put_string(&mut data, &slice.name);
+                    put_u32(&mut data, 0);
+                    put_u32(&mut data, slice.x as u32);
+                    put_u32(&mut data, slice.y as u32);
+                    put_u32(&mut data, slice.width);
+                    put_u32(&mut data, slice.height);
+                    if let Some((x, y)) = slice.pivot {
+                        put_u32(&mut data, x as u32);
+                        put_u32(&mut data, y as u32);
+                    }
+                    chunks.push(chunk(0x2022, data));
+                }
+            }
+            for cel in self.cels.iter().filter(|cel| cel.frame == frame) {
+                let mut data = vec![];
+                put_u16(&mut data, cel.layer);
+                put_u16(&mut data, cel.x as u16);
+                put_u16(&mut data, cel.y as u16);
+                data.push(255);
+                put_u16(&mut data, 0); // raw image data
+                data.extend([0; 7]);
+                put_u16(&mut data, cel.width);
+                put_u16(&mut data, cel.height);
+                for pixel in &cel.pixels {
+                    match self.color_mode {
+                        ColorMode::Rgba => data.extend(pixel),
+                        ColorMode::Grayscale => {
+                            let gray = pixel[..3].iter().map(|&c| c as u32).sum::<u32>() / 3;
+                            data.extend([gray as u8, pixel[3]]);
+                        }
+                        ColorMode::Indexed => {
+                            let index = if pixel[3] == 0 {
+                                0
+                            } else {
+                                palette.iter().position(|color| color == pixel).unwrap()
+                            };
+                            data.push(index as u8);
+                        }
+                    }
+                }
+                chunks.push(chunk(0x2005, data));
+            }
+
+            let body: Vec<u8> = chunks.concat();
+            let mut data = vec![];
+            put_u32(&mut data, 16 + body.len() as u32);
+            put_u16(&mut data, 0xF1FA);
+            put_u16(&mut data, chunks.len().min(0xFFFF) as u16);
+            put_u16(&mut data, 100);
+            data.extend([0; 2]);
+            put_u32(&mut data, chunks.len() as u32);
+            data.extend(body);
+            frames.push(data);
+        }
+
+        let body: Vec<u8> = frames.concat();
+        let mut data = vec![];
+        put_u32(&mut data, 128 + body.len() as u32);
+        put_u16(&mut data, 0xA5E0);
+        put_u16(&mut data, self.frames);
+        put_u16(&mut data, self.width);
+        put_u16(&mut data, self.height);
+        put_u16(
+            &mut data,
+            match self.color_mode {
+                ColorMode::Rgba => 32,
+                ColorMode::Grayscale => 16,
+                ColorMode::Indexed => 8,
+            },
+        );
+        put_u32(&mut data, 1); // layer opacity is valid
+        put_u16(&mut data, 100);
+        put_u32(&mut data, 0);
+        put_u32(&mut data, 0);
+        data.push(0); // transparent index
+        data.extend([0; 3]);
+        put_u16(&mut data, 0);
+        data.push(1);
+        data.push(1);
+        data.resize(128, 0);
+        data.extend(body);
+        data
+    }
+}
+
+fn chunk(kind: u16, data: Vec<u8>) -> Vec<u8> {
+    let mut chunk = vec![];
+    put_u32(&mut chunk, 6 + data.len() as u32);
+    put_u16(&mut chunk, kind);
+    chunk.extend(data);
+    chunk
+}
+
+fn put_u16(data: &mut Vec<u8>, value: u16) {
+    data.extend(value.to_le_bytes());
+}
+
+fn put_u32(data: &mut Vec<u8>, value: u32) {
+    data.extend(value.to_le_bytes());
+}
+
+fn put_string(data: &mut Vec<u8>, value: &str) {
+    put_u16(data, value.len() as u16);
+    data.extend(value.as_bytes());
+}
@@ -0,0 +1,101 @@
+use ase2ttf_core::{CoverageMismatch, WarningKind, check_frame_coverage, generation_warnings};
+
+mod common;
+
+use common::AseBuilder;
+
+#[test]
+fn reports_cells_missing_from_a_frame() {
+    let mut ase = AseBuilder::new(8, 4).frames(3);
+    let layer = ase.layer("U+0041");
+    ase.draw(0, layer, 0, 0, "#...#");
+    ase.draw(1, layer, 0, 0, "#");
+    ase.draw(2, layer, 0, 0, "#...#");
+
+    let mismatches = check_frame_coverage(&ase.build(), &common::params(4)).unwrap();
+    assert_eq!(
+        mismatches,
+        [CoverageMismatch {
+            codepoint: 0x42,
+            present_frames: vec![0, 2],
+            missing_frames: vec![1],
+        }]
+    );
+    assert_eq!(
+        mismatches[0].to_string(),
+        "U+0042 is drawn in frames 0, 2 but missing in frame 1"
+    );
+}
+
+#[test]
+fn regions_are_checked_like_the_glyphs() {
+    // a region is cut from the flattened frame, with its own cells and codepoints
+    let mut ase = AseBuilder::new(8, 4).frames(2);
+    let layer = ase.layer("art");
+    ase.draw(0, layer, 0, 0, "#...#...");
+    ase.draw(1, layer, 0, 0, "#.......");
+    let mut params = common::params(4);
+    params.config = Some(
+        r#"
+        [[regions]]
+        x = 0
+        y = 0
+        width = 8
+        height = 4
+        glyph_width = 4
+        glyph_height = 4
+        codepoint = "U+3041"
+        "#
+        .to_string(),
+    );
+    let mismatches = check_frame_coverage(&ase.build(), &params).unwrap();
+    assert_eq!(
+        mismatches,
+        [CoverageMismatch {
+            codepoint: 0x3042,
+            present_frames: vec![0],
+            missing_frames: vec![1],
+        }]
+    );
+}
+
+#[test]
+fn single_frame_is_always_consistent() {
+    let mut ase = AseBuilder::new(8, 4);
+    let layer = ase.layer("U+0041");
+    ase.draw(0, layer, 0, 0, "#");
+
+    assert!(
+        check_frame_coverage(&ase.build(), &common::params(4))
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn warnings_name_their_kind_and_codepoints() {
+    let mut ase = AseBuilder::new(6, 4).frames(2);
+    let layer = ase.layer("U+0041-");
+    ase.draw(0, layer, 0, 0, "##.#..");
+    ase.draw(1, layer, 0, 0, "##....");
+    // trimmed widths are scaled by 4 / 3, which rounds the advance of 'B'
+    let mut params = common::params(4);
+    params.glyph_width = Some(3);
+
+    let warnings = generation_warnings(&ase.build(), &params).unwrap();
+    let kinds: Vec<(WarningKind, Vec<u32>)> = warnings
+        .iter()
+        .map(|warning| (warning.kind, warning.codepoints.clone()))
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            (WarningKind::FrameCoverage, vec![0x42]),
+            (WarningKind::UnevenTrimScale, vec![0x42]),
+        ]
+    );
+    assert_eq!(
+        warnings[0].to_string(),
+        "U+0042 is drawn in frame 0 but missing in frame 1"
+    );
+}
@@ -0,0 +1,81 @@
+use ase2ttf_core::generate_fnt;
+
+mod common;
+
+use common::AseBuilder;
+
+fn sheet() -> Vec<u8> {
+    let mut ase = AseBuilder::new(8, 4);
+    let layer = ase.layer("U+0041-");
+    ase.draw(
+        0,
+        layer,
+        0,
+        0,
+        "
+        ##..####
+        ##..#..#
+        ....#..#
+        ....####
+        ",
+    );
+    ase.build()
+}
+
+#[test]
+fn fnt_draws_glyphs_from_cells() {
+    let fnt = generate_fnt(&sheet(), common::params(4)).unwrap();
+    let lines: Vec<&str> = fnt.lines().collect();
+
+    assert!(lines[0].starts_with("info face=\"test\" size=4 "));
+    assert_eq!(
+        lines[1],
+        "common lineHeight=4 base=2 scaleW=8 scaleH=4 pages=1 packed=0"
+    );
+    assert_eq!(lines[2], "page id=0 file=\"test.png\"");
+    assert_eq!(lines[3], "chars count=3");
+    assert_eq!(
+        lines[4],
+        "char id=32 x=0 y=0 width=0 height=0 xoffset=0 yoffset=0 xadvance=4 page=0 chnl=15"
+    );
+    assert_eq!(
+        lines[5],
+        "char id=65 x=0 y=0 width=4 height=4 xoffset=0 yoffset=0 xadvance=3 page=0 chnl=15"
+    );
+    assert_eq!(
+        lines[6],
+        "char id=66 x=4 y=0 width=4 height=4 xoffset=0 yoffset=0 xadvance=5 page=0 chnl=15"
+    );
+}
+
+#[test]
+fn fnt_lists_the_kerning_pairs() {
+    let mut params = common::params(4);
+    params.kerning = Some("B A 2\nA B -1\nA A 0\n".to_string());
+    let fnt = generate_fnt(&sheet(), params).unwrap();
+    let lines: Vec<&str> = fnt.lines().collect();
+
+    // the pairs follow the characters, in the order of their codepoints, without empty ones
+    assert_eq!(
+        lines[7..],
+        [
+            "kernings count=2",
+            "kerning first=65 second=66 amount=-1",
+            "kerning first=66 second=65 amount=2",
+        ]
+    );
+
+    // without kerning, there is no kernings block
+    let fnt = generate_fnt(&sheet(), common::params(4)).unwrap();
+    assert!(!fnt.contains("kerning"));
+}
+
+#[test]
+fn fnt_has_no_synthetic_bold() {
+    let mut params = common::params(4);
+    params.synthetic_bold = Some(1);
+    assert_eq!(
+        generate_fnt(&sheet(), params).err().unwrap().to_string(),
+        "BMFont output can't be used with a synthetic bold, as its glyphs are drawn from the cells of the sprite as they are."
+    );
+}
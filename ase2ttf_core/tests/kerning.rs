@@ -0,0 +1,79 @@
+use ase2ttf_core::{WarningKind, generate_ttf, generation_warnings};
+use write_fonts::read::tables::gpos::{PairPos, PositionLookup};
+use write_fonts::read::{FontRef, TableProvider};
+use write_fonts::types::Tag;
+
+mod common;
+
+use common::AseBuilder;
+
+fn sheet() -> Vec<u8> {
+    let mut ase = AseBuilder::new(8, 4);
+    let layer = ase.layer("U+0041");
+    ase.draw(0, layer, 0, 0, "##..##\n##..##");
+    ase.build()
+}
+
+#[test]
+fn kerning_pairs_are_written_to_gpos() {
+    let mut params = common::params(4);
+    params.kerning = Some("A B -1\n\nU+0042 A 2\nA B -2\n".to_string());
+    let bytes = generate_ttf(&sheet(), params).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+
+    let gpos = font.gpos().unwrap();
+    let records = gpos.feature_list().unwrap().feature_records();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].feature_tag(), Tag::new(b"kern"));
+    let lookup = gpos.lookup_list().unwrap().lookups().get(0).unwrap();
+    let PositionLookup::Pair(lookup) = lookup else {
+        panic!("expected a pair adjustment lookup");
+    };
+    let PairPos::Format1(pair_pos) = lookup.subtables().get(0).unwrap() else {
+        panic!("expected a pair adjustment of single glyphs");
+    };
+
+    // A is glyph 3 and B is glyph 4, and the later of two equal pairs is kept
+    let mut pairs = vec![];
+    let first_glyphs = pair_pos.coverage().unwrap().iter();
+    for (first, pair_set) in first_glyphs.zip(pair_pos.pair_sets().iter()) {
+        for record in pair_set.unwrap().pair_value_records().iter() {
+            let record = record.unwrap();
+            pairs.push((
+                first.to_u32(),
+                record.second_glyph().to_u32(),
+                record.value_record1().x_advance(),
+            ));
+        }
+    }
+    assert_eq!(pairs, [(3, 4, Some(-2 * 64)), (4, 3, Some(2 * 64))]);
+    // a pair looks at two glyphs at once
+    assert_eq!(font.os2().unwrap().us_max_context(), Some(2));
+}
+
+#[test]
+fn kerning_pairs_without_glyphs_are_skipped() {
+    let mut params = common::params(4);
+    params.kerning = Some("A Z 1".to_string());
+    let warnings = generation_warnings(&sheet(), &params).unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].kind, WarningKind::SkippedKerningPair);
+    assert_eq!(warnings[0].codepoints, [0x5A]);
+    assert_eq!(
+        warnings[0].message,
+        "the kerning pair U+0041 U+005A was skipped, as U+005A has no glyph"
+    );
+
+    // no pair is left, so there is no GPOS table
+    let bytes = generate_ttf(&sheet(), params).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+    assert!(font.gpos().is_err());
+    assert_eq!(font.os2().unwrap().us_max_context(), Some(0));
+
+    let mut params = common::params(4);
+    params.kerning = Some("A B".to_string());
+    assert_eq!(
+        generate_ttf(&sheet(), params).err().unwrap().to_string(),
+        "Invalid kerning file: line 1: expected a left and a right character and an adjustment, but found 'A B'."
+    );
+}
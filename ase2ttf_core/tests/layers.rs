@@ -0,0 +1,602 @@
+use ase2ttf_core::{Error, WarningKind, generate_sfd, generate_ttf, generate_ttf_with_warnings};
+
+mod common;
+
+use common::{AseBuilder, ColorMode, Layer, Slice};
+
+/// Returns the number of contours of a glyph in an SFD file.
+fn contour_count(sfd: &str, glyph: &str) -> usize {
+    let start = sfd.find(&format!("StartChar: {}\n", glyph)).unwrap();
+    let chars = &sfd[start..];
+    chars[..chars.find("EndChar").unwrap()]
+        .matches(" m 1")
+        .count()
+}
+
+#[test]
+fn fill_layer_is_merged_into_outline() {
+    let mut ase = AseBuilder::new(6, 6);
+    let outline = ase.layer("U+0041:outline");
+    ase.draw(
+        0,
+        outline,
+        0,
+        0,
+        "
+        ######
+        #....#
+        #....#
+        #....#
+        #....#
+        ######
+        ",
+    );
+    let fill = ase.layer("U+0041:fill");
+    ase.draw(
+        0,
+        fill,
+        0,
+        0,
+        "
+        ######
+        ######
+        ######
+        ######
+        ######
+        ##.###
+        ",
+    );
+
+    let sfd = generate_sfd(&ase.build(), common::params(6)).unwrap();
+
+    // one glyph with a single solid contour instead of a ring and a separate fill
+    assert_eq!(sfd.matches("StartChar:").count(), 4);
+    assert_eq!(contour_count(&sfd, "U+0041"), 1);
+}
+
+#[test]
+fn unfilled_interior_stays_a_hole() {
+    let mut ase = AseBuilder::new(6, 6);
+    let outline = ase.layer("U+0041:outline");
+    ase.draw(
+        0,
+        outline,
+        0,
+        0,
+        "
+        ######
+        #....#
+        #....#
+        #....#
+        #....#
+        ######
+        ",
+    );
+    let fill = ase.layer("U+0041:fill");
+    ase.draw(
+        0,
+        fill,
+        1,
+        1,
+        "
+        ####
+        #..#
+        #..#
+        ####
+        ",
+    );
+
+    let sfd = generate_sfd(&ase.build(), common::params(6)).unwrap();
+    assert_eq!(contour_count(&sfd, "U+0041"), 2);
+}
+
+#[test]
+fn charset_maps_the_drawn_cells_of_named_layers() {
+    let mut ase = AseBuilder::new(12, 4);
+    let layer = ase.layer("digits");
+    ase.draw(0, layer, 0, 0, "#.......#");
+    let layer = ase.layer("more");
+    ase.draw(0, layer, 0, 0, "....#");
+
+    let mut params = common::params(4);
+    params.charset = Some("012".to_string());
+    let sfd = generate_sfd(&ase.build(), params).unwrap();
+    // the blank cell between the first two is skipped, and the next layer continues the charset
+    assert!(sfd.contains("StartChar: U+0030\n"));
+    assert!(sfd.contains("StartChar: U+0031\nEncoding: 49"));
+    assert_eq!(contour_count(&sfd, "U+0032"), 1);
+
+    let mut params = common::params(4);
+    params.charset = Some("01".to_string());
+    assert_eq!(
+        generate_ttf(&ase.build(), params)
+            .err()
+            .unwrap()
+            .to_string(),
+        "The charset has 2 characters, which aren't enough for the drawn cells of layer more."
+    );
+}
+
+#[test]
+fn fill_layer_requires_outline() {
+    let mut ase = AseBuilder::new(4, 4);
+    let layer = ase.layer("U+0041");
+    ase.draw(0, layer, 0, 0, "##");
+    let fill = ase.layer("U+0042:fill");
+    ase.draw(0, fill, 0, 0, "##");
+
+    let error = generate_ttf(&ase.build(), common::params(4)).err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "The fill layer U+0042:fill has no matching outline layer."
+    );
+}
+
+#[test]
+fn range_assigns_codepoints_within_span() {
+    let mut ase = AseBuilder::new(8, 8);
+    let layer = ase.layer("U+0041-U+0043");
+    ase.draw(
+        0,
+        layer,
+        0,
+        0,
+        "
+        #...#...
+        ........
+        ........
+        ........
+        #.......
+        ",
+    );
+
+    let sfd = generate_sfd(&ase.build(), common::params(4)).unwrap();
+    assert!(sfd.contains("StartChar: U+0043\n"));
+}
+
+#[test]
+fn range_end_can_leave_out_the_prefix() {
+    let mut ase = AseBuilder::new(8, 8);
+    let layer = ase.layer("U+0041-0043");
+    ase.draw(
+        0,
+        layer,
+        0,
+        0,
+        "#...#...\n........\n........\n........\n#...#",
+    );
+
+    // the fourth cell is past the end of the range
+    let error = generate_ttf(&ase.build(), common::params(4)).err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "Layer U+0041-0043 has a glyph at U+0044, which is outside of its range."
+    );
+}
+
+#[test]
+fn range_larger_than_grid_is_an_error() {
+    // two rows were intended, but the sheet only has one
+    let mut ase = AseBuilder::new(8, 4);
+    let layer = ase.layer("U+0041-U+0044");
+    ase.draw(0, layer, 0, 0, "#...#");
+
+    let error = generate_ttf(&ase.build(), common::params(4)).err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "The range of layer U+0041-U+0044 covers 4 codepoints, but the layer has 2 cells."
+    );
+}
+
+#[test]
+fn cels_are_read_at_their_position_on_the_sprite() {
+    let mut ase = AseBuilder::new(8, 4);
+    let layer = ase.layer("U+0041-");
+    ase.draw(0, layer, 5, 1, "##\n##");
+
+    let sfd = generate_sfd(&ase.build(), common::params(4)).unwrap();
+    assert!(!sfd.contains("StartChar: U+0041\n"));
+    assert_eq!(contour_count(&sfd, "U+0042"), 1);
+}
+
+#[test]
+fn cell_margin_skips_the_lines_between_cells() {
+    let sheet = |width: u16, art: &str| {
+        let mut ase = AseBuilder::new(width, 4);
+        let layer = ase.layer("U+0041-");
+        ase.draw(0, layer, 0, 0, art);
+        ase.build()
+    };
+    // the same stems with a line drawn between the cells, right next to them
+    let packed = sheet(8, "...##...\n...##...\n...##...\n...##...");
+    let lined = sheet(9, "...###...\n...###...\n...###...\n...###...");
+
+    let mut params = common::params(4);
+    params.cell_margin = Some(1);
+    assert_eq!(
+        generate_sfd(&lined, params).unwrap(),
+        generate_sfd(&packed, common::params(4)).unwrap()
+    );
+
+    let mut params = common::params(4);
+    params.cell_margin = Some(2);
+    let error = generate_sfd(&lined, params).err().unwrap();
+    assert!(matches!(
+        error,
+        Error::DimensionMismatch { cell_margin: 2, .. }
+    ));
+    assert_eq!(
+        error.to_string(),
+        "The 9x4 sheet doesn't fit a whole number of 4x4 cells with a margin of 2 between them."
+    );
+}
+
+#[test]
+fn ink_outside_of_range_is_an_error() {
+    let mut ase = AseBuilder::new(8, 8);
+    let layer = ase.layer("U+0041-U+0043");
+    ase.draw(0, layer, 4, 4, "#");
+
+    let error = generate_ttf(&ase.build(), common::params(4)).err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "Layer U+0041-U+0043 has a glyph at U+0044, which is outside of its range."
+    );
+}
+
+/// Returns the largest y of the points of a glyph in an SFD file.
+fn top(sfd: &str, glyph: &str) -> f64 {
+    let start = sfd.find(&format!("StartChar: {}\n", glyph)).unwrap();
+    let chars = &sfd[start..];
+    chars[..chars.find("EndChar").unwrap()]
+        .lines()
+        .filter(|line| line.ends_with(" m 1") || line.ends_with(" l 1"))
+        .map(|line| line.split_whitespace().nth(1).unwrap().parse().unwrap())
+        .fold(f64::MIN, f64::max)
+}
+
+#[test]
+fn cap_height_reference_aligns_layers() {
+    let mut ase = AseBuilder::new(8, 4);
+    let upper = ase.layer("U+0041");
+    ase.draw(0, upper, 0, 1, "##..##\n##..##\n##..##");
+    // drawn one pixel lower than the other layer
+    let lower = ase.layer("U+0043");
+    ase.draw(0, lower, 0, 2, "##\n##");
+
+    let sfd = generate_sfd(&ase.build(), common::params(4)).unwrap();
+    assert_eq!(top(&sfd, "U+0041") - top(&sfd, "U+0043"), 64.0);
+
+    let mut params = common::params(4);
+    params.cap_height_reference = Some(0x41);
+    let sfd = generate_sfd(&ase.build(), params).unwrap();
+    assert_eq!(top(&sfd, "U+0041"), top(&sfd, "U+0043"));
+    assert_eq!(top(&sfd, "U+0041"), top(&sfd, "U+0042"));
+
+    let mut params = common::params(4);
+    params.cap_height_reference = Some(0x48);
+    let error = generate_ttf(&ase.build(), params).err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "The cap height reference U+0048 has no glyph."
+    );
+}
+
+fn layer(name: &str, group: bool, child_level: u16) -> Layer {
+    Layer {
+        name: name.to_string(),
+        visible: true,
+        group,
+        child_level,
+        opacity: 255,
+    }
+}
+
+#[test]
+fn layers_in_groups_are_read() {
+    let mut ase = AseBuilder::new(4, 4);
+    // a group whose name would be a fill layer without an outline, with a nested group inside
+    ase.add_layer(layer("U+0041:fill", true, 0));
+    let child = ase.add_layer(layer("U+0042", false, 1));
+    ase.draw(0, child, 0, 0, "##");
+    ase.add_layer(layer("Latin", true, 1));
+    let nested = ase.add_layer(layer("U+0043", false, 2));
+    ase.draw(0, nested, 0, 0, "#");
+
+    let sfd = generate_sfd(&ase.build(), common::params(4)).unwrap();
+    assert!(!sfd.contains("StartChar: U+0041\n"));
+    assert!(sfd.contains("StartChar: U+0042\n"));
+    assert!(sfd.contains("StartChar: U+0043\n"));
+}
+
+#[test]
+fn groups_named_after_a_codepoint_composite_their_layers() {
+    let mut ase = AseBuilder::new(8, 4);
+    ase.add_layer(layer("U+0041-", true, 0));
+    let shadow = ase.add_layer(layer("shadow", false, 1));
+    ase.draw(0, shadow, 1, 1, "##..##");
+    let fill = ase.add_layer(layer("fill", false, 1));
+    ase.draw(0, fill, 0, 0, "##..##");
+
+    let sfd = generate_sfd(&ase.build(), common::params(4)).unwrap();
+    // the shadow and fill of each cell are traced as one outline
+    assert_eq!(contour_count(&sfd, "U+0041"), 1);
+    assert_eq!(contour_count(&sfd, "U+0042"), 1);
+}
+
+#[test]
+fn indexed_and_grayscale_files_are_read_like_rgba() {
+    let sfd = |color_mode: ColorMode| {
+        let mut ase = AseBuilder::new(8, 4).color_mode(color_mode);
+        let layer = ase.layer("U+0041-");
+        ase.draw(0, layer, 0, 0, "##..#\n#...##");
+        generate_sfd(&ase.build(), common::params(4)).unwrap()
+    };
+
+    let rgba = sfd(ColorMode::Rgba);
+    assert_eq!(contour_count(&rgba, "U+0041"), 1);
+    assert_eq!(sfd(ColorMode::Indexed), rgba);
+    assert_eq!(sfd(ColorMode::Grayscale), rgba);
+}
+
+#[test]
+fn hidden_and_faint_layers_are_left_out() {
+    let mut ase = AseBuilder::new(4, 4);
+    let hidden = ase.add_layer(Layer {
+        visible: false,
+        ..layer("U+0041", false, 0)
+    });
+    ase.draw(0, hidden, 0, 0, "#");
+    ase.add_layer(Layer {
+        visible: false,
+        ..layer("Drafts", true, 0)
+    });
+    let in_hidden_group = ase.add_layer(layer("U+0042", false, 1));
+    ase.draw(0, in_hidden_group, 0, 0, "#");
+    let faint = ase.add_layer(Layer {
+        opacity: 64,
+        ..layer("U+0043", false, 0)
+    });
+    ase.draw(0, faint, 0, 0, "#");
+    let shown = ase.layer("U+0044");
+    ase.draw(0, shown, 0, 0, "#");
+    let bytes = ase.build();
+
+    let sfd = generate_sfd(&bytes, common::params(4)).unwrap();
+    assert!(!sfd.contains("StartChar: U+0041\n"));
+    assert!(!sfd.contains("StartChar: U+0042\n"));
+    assert!(!sfd.contains("StartChar: U+0043\n"));
+    assert!(sfd.contains("StartChar: U+0044\n"));
+
+    let mut params = common::params(4);
+    params.include_hidden = Some(true);
+    let sfd = generate_sfd(&bytes, params).unwrap();
+    assert!(sfd.contains("StartChar: U+0041\n"));
+    assert!(sfd.contains("StartChar: U+0042\n"));
+}
+
+#[test]
+fn regions_have_their_own_cell_size() {
+    let mut ase = AseBuilder::new(12, 8);
+    let layer = ase.layer("Sheet");
+    ase.draw(0, layer, 0, 0, "####.##\n####.##\n####.##\n####.##");
+    // a layer holds a single cel per frame
+    let tall = ase.layer("Sheet 2");
+    ase.draw(0, tall, 8, 0, "###\n###\n###\n###\n###\n###\n###\n###");
+
+    let mut params = common::params(8);
+    params.config = Some(
+        r#"
+        [[regions]]
+        x = 0
+        y = 0
+        width = 8
+        height = 4
+        glyph_width = 4
+        glyph_height = 4
+        codepoint = "U+0041"
+
+        [[regions]]
+        x = 8
+        y = 0
+        width = 4
+        height = 8
+        glyph_width = 4
+        glyph_height = 8
+        codepoint = "U+3042"
+        "#
+        .to_string(),
+    );
+    let sfd = generate_sfd(&ase.build(), params).unwrap();
+
+    // both grids stand on the baseline, two pixels above the bottom of their cells
+    assert_eq!(top(&sfd, "U+0041"), 2.0 * 64.0);
+    assert_eq!(top(&sfd, "U+0042"), 2.0 * 64.0);
+    assert_eq!(top(&sfd, "U+3042"), 6.0 * 64.0);
+}
+
+#[test]
+fn overlapping_regions_are_an_error() {
+    let mut ase = AseBuilder::new(8, 8);
+    let layer = ase.layer("Sheet");
+    ase.draw(0, layer, 0, 0, "#");
+
+    let mut params = common::params(8);
+    params.config = Some(
+        r#"
+        [[regions]]
+        x = 0
+        y = 0
+        width = 8
+        height = 4
+        glyph_width = 4
+        glyph_height = 4
+        codepoint = "U+0041"
+
+        [[regions]]
+        x = 4
+        y = 0
+        width = 4
+        height = 8
+        glyph_width = 4
+        glyph_height = 8
+        codepoint = "U+3042"
+        "#
+        .to_string(),
+    );
+    let error = generate_ttf(&ase.build(), params).err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "Invalid config: regions 0 and 1 overlap."
+    );
+}
+
+#[test]
+fn combining_layer_has_no_advance() {
+    let mut ase = AseBuilder::new(4, 4);
+    let layer = ase.layer("U+20D0:combining");
+    ase.draw(0, layer, 0, 0, "###");
+
+    let sfd = generate_sfd(&ase.build(), common::params(4)).unwrap();
+    let start = sfd.find("StartChar: U+20D0\n").unwrap();
+    assert!(sfd[start..].contains("Width: 0\n"));
+}
+
+#[test]
+fn slice_pivot_is_the_glyph_origin() {
+    let mut ase = AseBuilder::new(8, 4);
+    let layer = ase.layer("U+0041");
+    ase.draw(0, layer, 0, 0, "##....##\n##....##");
+    // the pivot pixel is at (5, 2), in the second cell
+    ase.slice(Slice {
+        name: "anchor".to_string(),
+        x: 4,
+        y: 0,
+        width: 4,
+        height: 4,
+        pivot: Some((1, 2)),
+    });
+
+    let mut params = common::params(4);
+    params.trim = Some(false);
+    params.baseline = Some(0);
+    let sfd = generate_sfd(&ase.build(), params).unwrap();
+
+    // without a pivot, the glyph keeps the cell and the baseline
+    assert_eq!(top(&sfd, "U+0041"), 256.0);
+    // the top-left corner of the pivot pixel is the origin
+    assert_eq!(top(&sfd, "U+0042"), 128.0);
+    let b = &sfd[sfd.find("StartChar: U+0042\n").unwrap()..];
+    let b = &b[..b.find("EndChar").unwrap()];
+    assert!(b.contains("Width: 192\n"));
+    let points: Vec<(f64, f64)> = b
+        .lines()
+        .filter(|line| line.ends_with(" m 1") || line.ends_with(" l 1"))
+        .map(|line| {
+            let mut values = line.split_whitespace().map(|v| v.parse().unwrap());
+            (values.next().unwrap(), values.next().unwrap())
+        })
+        .collect();
+    assert_eq!(points.iter().map(|p| p.0).fold(f64::MAX, f64::min), 64.0);
+    assert_eq!(points.iter().map(|p| p.1).fold(f64::MAX, f64::min), 0.0);
+}
+
+#[test]
+fn bleed_reads_shared_edges() {
+    // 'A' and 'B' share the column between them, and 'C' only touches the edge of 'B'
+    let mut ase = AseBuilder::new(12, 4);
+    let layer = ase.layer("U+0041");
+    ase.draw(
+        0,
+        layer,
+        0,
+        0,
+        "
+        .######.....
+        .#..#..#....
+        .#..#..#....
+        .######.....
+        ",
+    );
+
+    let bytes = ase.build();
+    let sfd = generate_sfd(&bytes, common::params(4)).unwrap();
+    assert_eq!(contour_count(&sfd, "U+0041"), 1);
+
+    let mut params = common::params(4);
+    params.bleed = Some(1);
+    let sfd = generate_sfd(&bytes, params).unwrap();
+    // the shared column closes 'A' into a box, which stays on the baseline
+    assert_eq!(contour_count(&sfd, "U+0041"), 2);
+    assert_eq!(top(&sfd, "U+0041"), 128.0);
+    // 'B' reaches one pixel left of its cell
+    let b = &sfd[sfd.find("StartChar: U+0042\n").unwrap()..];
+    let b = &b[..b.find("EndChar").unwrap()];
+    assert!(b.contains("\n -64 ") || b.contains("\n-64 "));
+    // a cell with ink only in its bleed is left out
+    assert!(!sfd.contains("StartChar: U+0043\n"));
+}
+
+#[test]
+fn overlapping_layers_are_reported() {
+    let mut ase = AseBuilder::new(8, 4);
+    let layer = ase.layer("U+0040-");
+    ase.draw(0, layer, 0, 0, "#...#");
+    let layer = ase.layer("U+0041");
+    ase.draw(0, layer, 0, 0, "##");
+
+    let error = generate_ttf(&ase.build(), common::params(4)).err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "U+0041 is drawn by both U+0040- and U+0041. Each codepoint can only have one glyph."
+    );
+}
+
+#[test]
+fn layers_named_like_a_broken_codepoint_are_reported() {
+    let mut ase = AseBuilder::new(8, 4);
+    let layer = ase.layer("U+0041");
+    ase.draw(0, layer, 0, 0, "##");
+    let layer = ase.layer("U+XYZ");
+    ase.draw(0, layer, 0, 0, "....##");
+    let layer = ase.layer("sketch");
+    ase.draw(0, layer, 0, 0, "#");
+
+    let (bytes, warnings) = generate_ttf_with_warnings(&ase.build(), common::params(4)).unwrap();
+    assert!(!bytes.is_empty());
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].kind, WarningKind::IgnoredLayer);
+    assert_eq!(
+        warnings[0].message,
+        "the layer U+XYZ was skipped, as its name is not a codepoint such as U+0041"
+    );
+}
+
+#[test]
+fn tags_assign_codepoints_to_frames() {
+    let mut ase = AseBuilder::new(4, 4).frames(3);
+    let layer = ase.layer("Glyphs");
+    ase.draw(0, layer, 0, 0, "##");
+    ase.draw(1, layer, 0, 0, "#.#");
+    ase.draw(2, layer, 0, 0, "####\n....\n####");
+    // the second frame continues the codepoints of the first, and other tags are ignored
+    ase.tag("U+0041", 0, 1);
+    ase.tag("Sketches", 2, 2);
+
+    let mut params = common::params(4);
+    params.use_tags = Some(true);
+    let sfd = generate_sfd(&ase.build(), params).unwrap();
+    assert_eq!(contour_count(&sfd, "U+0041"), 1);
+    assert_eq!(contour_count(&sfd, "U+0042"), 2);
+    assert_eq!(sfd.matches("StartChar:").count(), 5);
+
+    // without a tag named after a codepoint there is nothing to build
+    let mut ase = AseBuilder::new(4, 4);
+    let layer = ase.layer("U+0041");
+    ase.draw(0, layer, 0, 0, "##");
+    ase.tag("Sketches", 0, 0);
+    let mut params = common::params(4);
+    params.use_tags = Some(true);
+    let error = generate_ttf(&ase.build(), params).err().unwrap();
+    assert!(matches!(error, Error::NoValidTags));
+}
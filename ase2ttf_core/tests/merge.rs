@@ -0,0 +1,108 @@
+use ase2ttf_core::{generate_ttf, merge_ttf};
+use write_fonts::read::{FontRef, TableProvider};
+use write_fonts::types::{GlyphId, NameId};
+
+mod common;
+
+use common::AseBuilder;
+
+/// Returns the glyph that a codepoint is mapped to.
+fn glyph_of(font: &FontRef, codepoint: u32) -> Option<u32> {
+    font.cmap()
+        .unwrap()
+        .map_codepoint(codepoint)
+        .map(|glyph_id| glyph_id.to_u32())
+}
+
+#[test]
+fn merged_glyphs_replace_and_extend_the_font() {
+    let mut ase = AseBuilder::new(8, 4);
+    let layer = ase.layer("U+0041-");
+    ase.draw(0, layer, 0, 0, "###.#\n###.#");
+    let mut params = common::params(4);
+    params.family = Some("Base".to_string());
+    let existing = generate_ttf(&ase.build(), params).unwrap();
+
+    // a second session redraws 'B' wider and adds 'C'
+    let mut ase = AseBuilder::new(8, 4);
+    let layer = ase.layer("U+0042-");
+    ase.draw(0, layer, 0, 0, "###.##\n###.##");
+    let merged = merge_ttf(&existing, &ase.build(), common::params(4)).unwrap();
+
+    let font = FontRef::new(&merged).unwrap();
+    assert_eq!(font.maxp().unwrap().num_glyphs(), 6);
+    assert_eq!(glyph_of(&font, 0x41), Some(3));
+    assert_eq!(glyph_of(&font, 0x42), Some(4));
+    assert_eq!(glyph_of(&font, 0x43), Some(5));
+
+    // 'A' is kept, and 'B' has the outline of the new sheet
+    let glyf = font.glyf().unwrap();
+    let loca = font.loca(None).unwrap();
+    let width = |id: u32| {
+        let glyph = loca.get_glyf(GlyphId::new(id), &glyf).unwrap().unwrap();
+        glyph.x_max() - glyph.x_min()
+    };
+    assert_eq!(width(3), 192);
+    assert_eq!(width(4), 192);
+    assert_eq!(width(5), 128);
+
+    // the metadata of the existing font is preserved
+    let name = font.name().unwrap();
+    let family = name
+        .name_record()
+        .iter()
+        .find(|record| record.name_id() == NameId::FAMILY_NAME)
+        .unwrap()
+        .string(name.string_data())
+        .unwrap()
+        .to_string();
+    assert_eq!(family, "Base");
+}
+
+#[test]
+fn units_per_em_must_match() {
+    let mut ase = AseBuilder::new(4, 4);
+    let layer = ase.layer("U+0041-");
+    ase.draw(0, layer, 0, 0, "##");
+    let existing = generate_ttf(&ase.build(), common::params(4)).unwrap();
+
+    let mut ase = AseBuilder::new(8, 8);
+    let layer = ase.layer("U+0042-");
+    ase.draw(0, layer, 0, 0, "##");
+    let error = merge_ttf(&existing, &ase.build(), common::params(8))
+        .err()
+        .unwrap();
+    assert_eq!(
+        error.to_string(),
+        "The existing font has 256 units per em, but the new glyphs have 512."
+    );
+}
+
+#[test]
+fn merged_characters_update_the_ranges_and_extents() {
+    let mut ase = AseBuilder::new(4, 4);
+    let layer = ase.layer("U+0041");
+    ase.draw(0, layer, 0, 0, "##");
+    let existing = generate_ttf(&ase.build(), common::params(4)).unwrap();
+
+    // a Cyrillic letter that is wider than the Latin one
+    let mut ase = AseBuilder::new(4, 4);
+    let layer = ase.layer("U+0416");
+    ase.draw(0, layer, 0, 0, "####");
+    let merged = merge_ttf(&existing, &ase.build(), common::params(4)).unwrap();
+
+    let font = FontRef::new(&merged).unwrap();
+    let os2 = font.os2().unwrap();
+    assert_eq!(os2.us_first_char_index(), 0x20);
+    assert_eq!(os2.us_last_char_index(), 0x416);
+    // Basic Latin and Cyrillic
+    assert_eq!(os2.ul_unicode_range_1(), 1 << 0 | 1 << 9);
+    // Latin 1 and Cyrillic
+    assert_eq!(os2.ul_code_page_range_1(), Some(1 << 0 | 1 << 2));
+
+    let hhea = font.hhea().unwrap();
+    assert_eq!(hhea.min_left_side_bearing().to_i16(), 0);
+    assert_eq!(hhea.x_max_extent().to_i16(), 256);
+    // the pixel of spacing after the wide letter
+    assert_eq!(hhea.min_right_side_bearing().to_i16(), 64);
+}
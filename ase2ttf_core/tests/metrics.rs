@@ -0,0 +1,39 @@
+use ase2ttf_core::{GlyphMetrics, font_metrics};
+
+mod common;
+
+use common::AseBuilder;
+
+#[test]
+fn reports_vertical_metrics_and_advances() {
+    let mut ase = AseBuilder::new(4, 4);
+    let layer = ase.layer("U+0041");
+    ase.draw(0, layer, 0, 0, "##\n##");
+
+    let mut params = common::params(4);
+    params.line_gap = Some(1);
+    let metrics = font_metrics(&ase.build(), &params).unwrap();
+
+    assert_eq!(metrics.units_per_em, 256);
+    assert_eq!(metrics.ascender, 128);
+    assert_eq!(metrics.descender, -128);
+    assert_eq!(metrics.line_gap, 64);
+    assert_eq!(metrics.baseline, 2);
+    // without H and x, both are estimated from the ascender of 2 pixels
+    assert_eq!(metrics.cap_height, Some(64));
+    assert_eq!(metrics.x_height, Some(64));
+    assert_eq!(metrics.glyphs.len(), 4);
+    assert_eq!(
+        metrics.glyphs[3],
+        GlyphMetrics {
+            name: "U+0041".to_string(),
+            codepoint: Some(0x41),
+            advance: 3 * 64,
+        }
+    );
+    assert_eq!(metrics.glyphs[0].codepoint, None);
+
+    let table = metrics.to_string();
+    assert!(table.starts_with("units per em     256\nascender         128  (2 px)\n"));
+    assert!(table.contains("\nU+0041   U+0041         192  (3 px)\n"));
+}
@@ -0,0 +1,79 @@
+use ase2ttf_core::{generate_sfd, generate_ttf};
+
+mod common;
+
+use common::AseBuilder;
+
+fn sheet() -> Vec<u8> {
+    let mut ase = AseBuilder::new(8, 4);
+    let layer = ase.layer("U+0041");
+    ase.draw(
+        0,
+        layer,
+        0,
+        0,
+        "
+        #.......
+        ##......
+        ###.....
+        ",
+    );
+    ase.build()
+}
+
+/// Returns the smallest and largest x of the points of a glyph in an SFD file.
+fn x_range(sfd: &str, glyph: &str) -> (f64, f64) {
+    let start = sfd.find(&format!("StartChar: {}\n", glyph)).unwrap();
+    let chars = &sfd[start..];
+    let xs: Vec<f64> = chars[..chars.find("EndChar").unwrap()]
+        .lines()
+        .filter(|line| line.ends_with(" m 1") || line.ends_with(" l 1"))
+        .map(|line| line.split_whitespace().next().unwrap().parse().unwrap())
+        .collect();
+    (
+        xs.iter().copied().fold(f64::MAX, f64::min),
+        xs.iter().copied().fold(f64::MIN, f64::max),
+    )
+}
+
+#[test]
+fn mirror_flips_glyphs_within_the_cell() {
+    let mut params = common::params(4);
+    params.trim = Some(false);
+    let sfd = generate_sfd(&sheet(), params).unwrap();
+    assert_eq!(x_range(&sfd, "U+0041"), (0.0, 3.0 * 64.0));
+
+    let mut params = common::params(4);
+    params.trim = Some(false);
+    params.mirror = Some(true);
+    let sfd = generate_sfd(&sheet(), params).unwrap();
+    assert_eq!(x_range(&sfd, "U+0041"), (64.0, 4.0 * 64.0));
+    assert!(!sfd.contains("StartChar: U+E041\n"));
+}
+
+#[test]
+fn mirror_pua_adds_mirrored_copies() {
+    let mut params = common::params(4);
+    params.trim = Some(false);
+    params.mirror_pua = Some(true);
+    let sfd = generate_sfd(&sheet(), params).unwrap();
+
+    assert_eq!(x_range(&sfd, "U+0041"), (0.0, 3.0 * 64.0));
+    assert_eq!(x_range(&sfd, "U+E041"), (64.0, 4.0 * 64.0));
+    assert!(sfd.contains("StartChar: U+E041\nEncoding: 57409 57409 4\n"));
+}
+
+#[test]
+fn mirror_pua_rejects_codepoints_that_do_not_fit() {
+    let mut ase = AseBuilder::new(4, 4);
+    let layer = ase.layer("U+3042");
+    ase.draw(0, layer, 0, 0, "#");
+
+    let mut params = common::params(4);
+    params.mirror_pua = Some(true);
+    let error = generate_ttf(&ase.build(), params).err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "U+3042 can't be mirrored into the Private Use Area. Only codepoints up to U+18FF can be mirrored."
+    );
+}
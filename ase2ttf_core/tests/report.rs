@@ -0,0 +1,98 @@
+use ase2ttf_core::{LayerReport, WarningKind, analyze, generate_ttf};
+
+mod common;
+
+use common::AseBuilder;
+
+#[test]
+fn reports_the_cells_of_each_layer() {
+    let mut ase = AseBuilder::new(8, 8);
+    let layer = ase.layer("U+0041-");
+    ase.draw(
+        0,
+        layer,
+        0,
+        0,
+        "
+        ........
+        .##.....
+        .##.....
+        ........
+        ........
+        .....#..
+        ",
+    );
+    let layer = ase.layer("U+XYZ");
+    ase.draw(0, layer, 0, 0, "#");
+
+    let report = analyze(&ase.build(), &common::params(4)).unwrap();
+    assert_eq!(
+        report.layers,
+        [LayerReport {
+            name: "U+0041-".to_string(),
+            cells: 4,
+            glyphs: vec![0x41, 0x44],
+            blank_cells: vec![0x42, 0x43],
+            ink_bounds: Some((1, 1, 2, 2)),
+        }]
+    );
+    assert_eq!(report.ignored_layers, ["U+XYZ"]);
+
+    let table = report.to_string();
+    assert!(table.starts_with("layer    cells  glyphs  blank  codepoints       ink\n"));
+    assert!(table.contains("\nU+0041-      4       2      2  U+0041-U+0044    x 1-2, y 1-2\n"));
+    assert!(table.ends_with("\nignored layer U+XYZ: its name is not a codepoint\n"));
+}
+
+#[test]
+fn reports_a_sheet_without_any_valid_layer() {
+    let mut ase = AseBuilder::new(4, 4);
+    let layer = ase.layer("U+XYZ");
+    ase.draw(0, layer, 0, 0, "#");
+    let layer = ase.layer("U+");
+    ase.draw(0, layer, 0, 0, ".#");
+    let bytes = ase.build();
+
+    // no font can be built, but the sheet can still be looked into
+    assert!(generate_ttf(&bytes, common::params(4)).is_err());
+    let report = analyze(&bytes, &common::params(4)).unwrap();
+    assert_eq!(report.layers, []);
+    assert_eq!(report.ignored_layers, ["U+XYZ", "U+"]);
+    assert_eq!(report.warnings.len(), 2);
+    assert!(
+        report
+            .warnings
+            .iter()
+            .all(|warning| warning.kind == WarningKind::IgnoredLayer)
+    );
+}
+
+#[test]
+fn reports_the_aliases_of_the_config() {
+    let mut ase = AseBuilder::new(8, 4);
+    let layer = ase.layer("U+002D-");
+    ase.draw(0, layer, 0, 0, "....####\n##......");
+    let bytes = ase.build();
+    let report = |config: &str| {
+        let mut params = common::params(4);
+        params.config = Some(config.to_string());
+        analyze(&bytes, &params).unwrap()
+    };
+
+    let aliased =
+        report("[aliases]\n\"U+002D\" = [\"U+2212\", \"U+2010\"]\n\"U+002E\" = [\"U+2024\"]");
+    assert_eq!(aliased.aliases, 3);
+    assert!(
+        aliased
+            .to_string()
+            .contains("\n3 aliases share the glyph of another codepoint\n")
+    );
+
+    let single = report("[aliases]\n\"U+002D\" = [\"U+2212\"]");
+    assert!(
+        single
+            .to_string()
+            .contains("\n1 alias shares the glyph of another codepoint\n")
+    );
+    assert!(!report("").to_string().contains("alias"));
+}
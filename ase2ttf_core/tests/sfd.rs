@@ -0,0 +1,95 @@
+use ase2ttf_core::generate_sfd;
+
+mod common;
+
+use common::AseBuilder;
+
+#[test]
+fn sfd_contains_traced_glyphs() {
+    let mut ase = AseBuilder::new(8, 4);
+    let layer = ase.layer("U+0041-");
+    ase.draw(
+        0,
+        layer,
+        0,
+        0,
+        "
+        ##..####
+        ##..####
+        ....####
+        ....####
+        ",
+    );
+
+    let sfd = generate_sfd(&ase.build(), common::params(4)).unwrap();
+
+    assert!(sfd.starts_with("SplineFontDB: 3.2\n"));
+    assert!(sfd.trim_end().ends_with("EndSplineFont"));
+    assert_eq!(sfd.matches("StartChar:").count(), 5);
+
+    // 'A' is the first glyph after .notdef / null / space
+    let a = &sfd[sfd.find("StartChar: U+0041").unwrap()..];
+    let a = &a[..a.find("EndChar").unwrap()];
+    assert!(a.contains("Encoding: 65 65 3\n"));
+    assert_eq!(a.matches(" m 1").count(), 1);
+
+    // .notdef has no codepoint
+    assert!(sfd.contains("StartChar: .notdef\nEncoding: 1114112 -1 0\n"));
+}
+
+#[test]
+fn aliases_are_alternate_unicodes() {
+    let mut ase = AseBuilder::new(4, 4);
+    let layer = ase.layer("U+0041");
+    ase.draw(0, layer, 0, 0, "##");
+
+    let mut params = common::params(4);
+    params.config = Some("[aliases]\n\"U+0041\" = [\"U+0391\", \"U+0410\"]".to_string());
+    let sfd = generate_sfd(&ase.build(), params).unwrap();
+
+    assert!(sfd.contains("Encoding: 65 65 3\nAltUni2: 000391.ffffffff.0 000410.ffffffff.0\n"));
+    assert_eq!(sfd.matches("AltUni2:").count(), 1);
+}
+
+#[test]
+fn family_is_required_without_file_path() {
+    let mut ase = AseBuilder::new(4, 4);
+    let layer = ase.layer("U+0041");
+    ase.draw(0, layer, 0, 0, "##");
+    let bytes = ase.build();
+
+    let mut params = common::params(4);
+    params.file_path = Some(String::new());
+    params.family = Some("Pixel Sans".to_string());
+    let sfd = generate_sfd(&bytes, params).unwrap();
+    assert!(sfd.contains("FamilyName: Pixel Sans\n"));
+
+    let mut params = common::params(4);
+    params.file_path = None;
+    let error = generate_sfd(&bytes, params).err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "A family name is required when no file path is given."
+    );
+}
+
+#[test]
+fn localized_names_are_written_in_utf7() {
+    let mut ase = AseBuilder::new(4, 4);
+    let layer = ase.layer("U+0041");
+    ase.draw(0, layer, 0, 0, "##");
+
+    let mut params = common::params(4);
+    params.config =
+        Some("[[names]]\nlanguage = 0x0411\nfamily = \"ドット\"\nsubfamily = \"標準\"".to_string());
+    let sfd = generate_sfd(&ase.build(), params).unwrap();
+
+    // name IDs 0 to 17, with the family, the subfamily, the full name and the typographic names
+    let mut strings = vec!["\"\""; 18];
+    strings[1] = "\"+MMkwwzDI- +ahlulg-\"";
+    strings[2] = "\"+ahlulg-\"";
+    strings[4] = "\"+MMkwwzDI-\"";
+    strings[16] = "\"+MMkwwzDI-\"";
+    strings[17] = "\"+ahlulg-\"";
+    assert!(sfd.contains(&format!("\nLangName: 1041 {}\n", strings.join(" "))));
+}
@@ -0,0 +1,87 @@
+use ase2ttf_core::{export_glyphs_svg, generate_svg};
+
+mod common;
+
+use common::AseBuilder;
+
+#[test]
+fn svg_font_contains_traced_glyphs() {
+    let mut ase = AseBuilder::new(8, 4);
+    let layer = ase.layer("U+0041-");
+    ase.draw(
+        0,
+        layer,
+        0,
+        0,
+        "
+        ##..####
+        ##..####
+        ....####
+        ....####
+        ",
+    );
+
+    let svg = generate_svg(&ase.build(), common::params(4)).unwrap();
+
+    assert!(svg.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<svg "));
+    assert!(svg.trim_end().ends_with("</svg>"));
+    assert!(svg.contains(
+        "<font-face font-family=\"test\" units-per-em=\"256\" ascent=\"128\" descent=\"-128\"/>"
+    ));
+    assert_eq!(svg.matches("<missing-glyph ").count(), 1);
+    assert_eq!(svg.matches("<glyph ").count(), 4);
+
+    let a = &svg[svg.find("<glyph glyph-name=\"U+0041\"").unwrap()..];
+    let a = &a[..a.find("/>").unwrap()];
+    assert!(a.contains(" unicode=\"&#x41;\" horiz-adv-x=\"192\" d=\"M"));
+    assert_eq!(a.matches('M').count(), 1);
+
+    // the null glyph has no character that can be written in XML
+    assert!(svg.contains("<glyph glyph-name=\"null\" horiz-adv-x=\"256\"/>"));
+}
+
+#[test]
+fn aliases_repeat_the_glyph() {
+    let mut ase = AseBuilder::new(4, 4);
+    let layer = ase.layer("U+0041");
+    ase.draw(0, layer, 0, 0, "##");
+
+    let mut params = common::params(4);
+    params.config = Some("[aliases]\n\"U+0041\" = [\"U+0391\"]".to_string());
+    let svg = generate_svg(&ase.build(), params).unwrap();
+
+    let a = svg.lines().find(|line| line.contains("&#x41;")).unwrap();
+    let alpha = svg.lines().find(|line| line.contains("&#x391;")).unwrap();
+    assert_eq!(a.replace("&#x41;", "&#x391;"), alpha);
+    assert_eq!(svg.matches("glyph-name=\"U+0041\"").count(), 2);
+}
+
+#[test]
+fn glyphs_are_exported_as_separate_svgs() {
+    let mut ase = AseBuilder::new(4, 4);
+    let layer = ase.layer("U+004F");
+    ase.draw(
+        0,
+        layer,
+        0,
+        0,
+        "
+        ####
+        #..#
+        #..#
+        ####
+        ",
+    );
+
+    let svgs = export_glyphs_svg(&ase.build(), &common::params(4)).unwrap();
+
+    let o = &svgs[&0x4F];
+    assert!(
+        o.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 -128 320 256\">\n")
+    );
+    assert!(o.trim_end().ends_with("</svg>"));
+    // the outline and its hole
+    assert_eq!(o.matches('M').count(), 2);
+
+    assert!(svgs[&0x20].contains(" d=\"\"/>"));
+}
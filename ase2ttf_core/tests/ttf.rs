@@ -0,0 +1,2077 @@
+use std::collections::HashMap;
+use std::io::Read;
+
+use ase2ttf_core::{
+    EmbeddingLevel, Error, LocalizedName, Params, SimplifiedGlyph, WarningKind, generate_ttf,
+    generate_ttf_from_path, generation_warnings, read_ase_file, simplified_glyphs,
+    uneven_trim_scale,
+};
+use flate2::read::ZlibDecoder;
+use write_fonts::read::tables::cmap::CmapSubtable;
+use write_fonts::read::tables::glyf::{Anchor, Glyph};
+use write_fonts::read::tables::gsub::SubstitutionSubtables;
+use write_fonts::read::{FontRef, TableProvider};
+use write_fonts::types::{GlyphId, GlyphId16, Tag};
+
+mod common;
+
+use common::AseBuilder;
+
+const MACINTOSH: u16 = 1;
+
+fn sheet() -> Vec<u8> {
+    let mut ase = AseBuilder::new(8, 4);
+    let layer = ase.layer("U+0041-");
+    ase.draw(
+        0,
+        layer,
+        0,
+        0,
+        "
+        ##..####
+        ##..#..#
+        ....#..#
+        ....####
+        ",
+    );
+    ase.build()
+}
+
+#[test]
+fn maps_layer_cells_to_codepoints() {
+    let bytes = generate_ttf(&sheet(), common::params(4)).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+
+    let cmap = font.cmap().unwrap();
+    assert_eq!(cmap.map_codepoint('A').map(|id| id.to_u32()), Some(3));
+    assert_eq!(cmap.map_codepoint('B').map(|id| id.to_u32()), Some(4));
+    assert_eq!(font.maxp().unwrap().num_glyphs(), 5);
+
+    // the Macintosh records are emitted by default
+    assert!(
+        cmap.encoding_records()
+            .iter()
+            .any(|record| record.platform_id() as u16 == MACINTOSH)
+    );
+    assert!(
+        font.name()
+            .unwrap()
+            .name_record()
+            .iter()
+            .any(|record| record.platform_id() == MACINTOSH)
+    );
+    assert_eq!(font.post().unwrap().num_glyphs(), Some(5));
+}
+
+#[test]
+fn web_optimized_drops_macintosh_records() {
+    let mut params = common::params(4);
+    params.web_optimized = Some(true);
+    let bytes = generate_ttf(&sheet(), params).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+
+    let cmap = font.cmap().unwrap();
+    assert_eq!(cmap.encoding_records().len(), 2);
+    assert!(
+        cmap.encoding_records()
+            .iter()
+            .all(|record| record.platform_id() as u16 != MACINTOSH)
+    );
+    assert_eq!(cmap.map_codepoint('B').map(|id| id.to_u32()), Some(4));
+
+    let name = font.name().unwrap();
+    assert!(!name.name_record().is_empty());
+    assert!(
+        name.name_record()
+            .iter()
+            .all(|record| record.platform_id() != MACINTOSH)
+    );
+
+    let post = font.post().unwrap();
+    assert_eq!(post.version().to_major_minor(), (3, 0));
+    assert_eq!(post.num_glyphs(), None);
+}
+
+#[test]
+fn tabular_figures_add_numeral_features() {
+    let mut ase = AseBuilder::new(8, 4);
+    let digits = ase.layer("U+0030");
+    ase.draw(
+        0,
+        digits,
+        0,
+        0,
+        "
+        .#...##.
+        .#....#.
+        .#....#.
+        .#...###
+        ",
+    );
+    let tabular = ase.layer("U+0030:tnum");
+    ase.draw(
+        0,
+        tabular,
+        0,
+        0,
+        "
+        .#...##.
+        .#....#.
+        .#....#.
+        .#...###
+        ",
+    );
+    let bytes = generate_ttf(&ase.build(), common::params(4)).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+
+    // the alternates are unencoded
+    assert_eq!(font.maxp().unwrap().num_glyphs(), 7);
+    let cmap = font.cmap().unwrap();
+    assert_eq!(cmap.map_codepoint('0').map(|id| id.to_u32()), Some(3));
+    assert_eq!(cmap.map_codepoint('1').map(|id| id.to_u32()), Some(4));
+
+    // the default figures are trimmed, the tabular ones keep the cell width
+    let hmtx = font.hmtx().unwrap();
+    assert_eq!(hmtx.advance(GlyphId::new(3)).unwrap(), 2 * 64);
+    assert_eq!(hmtx.advance(GlyphId::new(5)).unwrap(), 4 * 64);
+    assert_eq!(hmtx.advance(GlyphId::new(6)).unwrap(), 4 * 64);
+
+    let gsub = font.gsub().unwrap();
+    let features: Vec<String> = gsub
+        .feature_list()
+        .unwrap()
+        .feature_records()
+        .iter()
+        .map(|record| record.feature_tag().to_string())
+        .collect();
+    assert_eq!(features, ["pnum", "tnum"]);
+    assert_eq!(gsub.lookup_list().unwrap().lookup_count(), 2);
+}
+
+#[test]
+fn single_figure_set_has_no_gsub() {
+    let bytes = generate_ttf(&sheet(), common::params(4)).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+    assert!(font.gsub().is_err());
+}
+
+#[test]
+fn static_font_has_no_variation_tables() {
+    let bytes = generate_ttf(&sheet(), common::params(4)).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+    for tag in [b"fvar", b"avar", b"gvar", b"STAT"] {
+        assert!(font.table_data(Tag::new(tag)).is_none());
+    }
+}
+
+#[test]
+fn variable_font_needs_several_masters() {
+    let mut params = common::params(4);
+    params.variable = Some(true);
+
+    let error = generate_ttf(&sheet(), params).err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "A variable font needs at least two masters, one per frame, but the file has 1."
+    );
+}
+
+#[test]
+fn pixel_size_adds_size_feature() {
+    let bytes = generate_ttf(&sheet(), common::params(4)).unwrap();
+    assert!(FontRef::new(&bytes).unwrap().gpos().is_err());
+
+    let mut params = common::params(4);
+    params.pixel_size = Some(16.0);
+    let bytes = generate_ttf(&sheet(), params).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+
+    let gpos = font.gpos().unwrap();
+    let features = gpos.feature_list().unwrap();
+    let record = &features.feature_records()[0];
+    assert_eq!(record.feature_tag(), Tag::new(b"size"));
+    assert_eq!(gpos.lookup_list().unwrap().lookup_count(), 0);
+
+    let mut params = common::params(4);
+    params.pixel_size = Some(0.0);
+    assert!(generate_ttf(&sheet(), params).is_err());
+}
+
+#[test]
+fn margins_enlarge_the_em() {
+    let mut params = common::params(4);
+    params.baseline = Some(1);
+    params.margin_top = Some(1);
+    params.margin_bottom = Some(2);
+    let bytes = generate_ttf(&sheet(), params).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+
+    assert_eq!(font.head().unwrap().units_per_em(), 7 * 64);
+    let hhea = font.hhea().unwrap();
+    assert_eq!(hhea.ascender().to_i16(), 4 * 64);
+    assert_eq!(hhea.descender().to_i16(), -3 * 64);
+    let os2 = font.os2().unwrap();
+    assert_eq!(os2.s_typo_ascender(), 4 * 64);
+    assert_eq!(os2.s_typo_descender(), -3 * 64);
+    assert_eq!(os2.us_win_descent(), 3 * 64);
+}
+
+#[test]
+fn average_char_width_is_the_mean_advance() {
+    let mut params = common::params(4);
+    params.trim = Some(true);
+    params.space_width = Some(1);
+    let bytes = generate_ttf(&sheet(), params).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+
+    // .notdef and null take 4 pixels, the space 1, and the trimmed glyphs 3 and 5
+    let advances = [4, 4, 1, 3, 5].map(|pixels| pixels * 64);
+    assert_eq!(
+        font.os2().unwrap().x_avg_char_width(),
+        (advances.iter().sum::<i16>() as f64 / 5.0).round() as i16
+    );
+}
+
+#[test]
+fn line_gap_is_written_to_hhea_and_os2() {
+    let mut params = common::params(4);
+    params.line_gap = Some(-1);
+    let bytes = generate_ttf(&sheet(), params).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+    assert_eq!(font.hhea().unwrap().line_gap().to_i16(), -64);
+    assert_eq!(font.os2().unwrap().s_typo_line_gap(), -64);
+}
+
+#[test]
+fn vendor_id_is_padded_with_spaces() {
+    let vendor_id = |vendor_id: Option<&str>| {
+        let mut params = common::params(4);
+        params.vendor_id = vendor_id.map(str::to_string);
+        let bytes = generate_ttf(&sheet(), params)?;
+        let font = FontRef::new(&bytes).unwrap();
+        Ok::<_, Error>(font.os2().unwrap().ach_vend_id())
+    };
+
+    assert_eq!(vendor_id(None).unwrap(), Tag::new(b"    "));
+    assert_eq!(vendor_id(Some("AB")).unwrap(), Tag::new(b"AB  "));
+    assert_eq!(vendor_id(Some("ABCD")).unwrap(), Tag::new(b"ABCD"));
+    assert_eq!(
+        vendor_id(Some("ABCDE")).err().unwrap().to_string(),
+        "The vendor ID 'ABCDE' must be 1 to 4 printable ASCII characters."
+    );
+}
+
+#[test]
+fn embedding_sets_the_fs_type() {
+    let fs_type = |embedding: Option<EmbeddingLevel>| {
+        let mut params = common::params(4);
+        params.embedding = embedding;
+        let bytes = generate_ttf(&sheet(), params).unwrap();
+        FontRef::new(&bytes).unwrap().os2().unwrap().fs_type()
+    };
+
+    assert_eq!(fs_type(None), 0);
+    assert_eq!(fs_type(Some(EmbeddingLevel::Restricted)), 0x0002);
+    assert_eq!(fs_type(Some(EmbeddingLevel::PreviewAndPrint)), 0x0004);
+    assert_eq!(fs_type(Some(EmbeddingLevel::Editable)), 0x0008);
+}
+
+#[test]
+fn family_class_is_written_to_os2() {
+    let mut params = common::params(4);
+    // sans serif, simple
+    params.family_class = Some((8 << 8) | 6);
+    let bytes = generate_ttf(&sheet(), params).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+    assert_eq!(font.os2().unwrap().s_family_class(), 0x0806);
+
+    for invalid in [6 << 8, (8 << 8) | 16, 15 << 8, 1, -1] {
+        let mut params = common::params(4);
+        params.family_class = Some(invalid);
+        assert!(generate_ttf(&sheet(), params).is_err());
+    }
+}
+
+#[test]
+fn cap_height_is_measured_from_reference() {
+    let mut params = common::params(4);
+    params.cap_height_reference = Some(0x41);
+    let bytes = generate_ttf(&sheet(), params).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+
+    // the top of 'A' is the top of the cell, two pixels above the baseline
+    let os2 = font.os2().unwrap();
+    assert_eq!(os2.version(), 4);
+    assert_eq!(os2.s_cap_height(), Some(2 * 64));
+}
+
+#[test]
+fn cap_height_and_x_height_are_measured_from_h_and_x() {
+    let mut ase = AseBuilder::new(4, 4);
+    let layer = ase.layer("U+0048");
+    ase.draw(0, layer, 0, 0, "#.#\n###\n#.#");
+    let layer = ase.layer("U+0078");
+    ase.draw(0, layer, 0, 1, "#.#\n.#.");
+    let bytes = generate_ttf(&ase.build(), common::params(4)).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+
+    // 'H' reaches the top of the cell and 'x' starts a pixel below it
+    let os2 = font.os2().unwrap();
+    assert_eq!(os2.s_cap_height(), Some(2 * 64));
+    assert_eq!(os2.sx_height(), Some(64));
+}
+
+#[test]
+fn downscale_traces_a_smaller_grid() {
+    let bytes = generate_ttf(&sheet(), common::params(4)).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+    assert_eq!(font.maxp().unwrap().max_contours(), Some(2));
+
+    // the hole of 'B' is smaller than a cell of the 2 x 2 grid
+    let mut params = common::params(4);
+    params.downscale = Some(2);
+    let bytes = generate_ttf(&sheet(), params).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+    assert_eq!(font.maxp().unwrap().max_contours(), Some(1));
+    // the metrics are still measured in the pixels of the drawing
+    assert_eq!(font.head().unwrap().units_per_em(), 4 * 64);
+
+    let mut params = common::params(4);
+    params.downscale = Some(3);
+    let error = generate_ttf(&sheet(), params).err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "The glyph width and height must be multiples of the downscale ratio 3."
+    );
+}
+
+#[test]
+fn localized_names_are_added_for_windows() {
+    let mut params = common::params(4);
+    params.family = Some("Dot".to_string());
+    params.config = Some(
+        r#"
+        [[names]]
+        language = 0x0411
+        family = "ドット"
+        "#
+        .to_string(),
+    );
+    let bytes = generate_ttf(&sheet(), params).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+    let name = font.name().unwrap();
+    let japanese: Vec<(u16, String)> = name
+        .name_record()
+        .iter()
+        .filter(|record| record.language_id() == 0x0411)
+        .map(|record| {
+            (
+                record.name_id().to_u16(),
+                record.string(name.string_data()).unwrap().to_string(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        japanese,
+        [
+            (1, "ドット".to_string()),
+            (2, "Regular".to_string()),
+            (4, "ドット".to_string()),
+            (16, "ドット".to_string()),
+            (17, "Regular".to_string()),
+        ]
+    );
+
+    let mut params = common::params(4);
+    params.config = Some("[[names]]\nlanguage = 0x0409\nfamily = \"Dot\"".to_string());
+    assert_eq!(
+        generate_ttf(&sheet(), params).err().unwrap().to_string(),
+        "Invalid config: the names of language 0x0409 are the English ones, which are set by the family and subfamily."
+    );
+}
+
+#[test]
+fn localized_names_of_the_params_replace_the_config() {
+    let localized = |family: &str, subfamily: Option<&str>| LocalizedName {
+        family: family.to_string(),
+        subfamily: subfamily.map(str::to_string),
+    };
+    let mut params = common::params(4);
+    params.config = Some(
+        "[[names]]\nlanguage = 0x0411\nfamily = \"ドット\"\n\n[[names]]\nlanguage = 0x040C\nfamily = \"Point\""
+            .to_string(),
+    );
+    params.localized_names = Some(HashMap::from([(0x0411, localized("点字", Some("標準")))]));
+    let bytes = generate_ttf(&sheet(), params).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+    let name = font.name().unwrap();
+    let families: Vec<(u16, String)> = name
+        .name_record()
+        .iter()
+        .filter(|record| record.name_id().to_u16() == 1)
+        .filter(|record| !matches!(record.language_id(), 0 | 0x0409))
+        .map(|record| {
+            (
+                record.language_id(),
+                record.string(name.string_data()).unwrap().to_string(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        families,
+        [
+            (0x040C, "Point".to_string()),
+            (0x0411, "点字 標準".to_string()),
+        ]
+    );
+
+    let mut params = common::params(4);
+    params.localized_names = Some(HashMap::from([(0x0409, localized("Dot", None))]));
+    assert_eq!(
+        generate_ttf(&sheet(), params).err().unwrap().to_string(),
+        "The names of language 0x0409 are the English ones, which are set by the family and subfamily."
+    );
+}
+
+#[test]
+fn name_ids_select_name_records() {
+    let mut params = common::params(4);
+    params.name_ids = Some(vec![1, 2, 6]);
+    let bytes = generate_ttf(&sheet(), params).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+
+    let mut ids: Vec<u16> = font
+        .name()
+        .unwrap()
+        .name_record()
+        .iter()
+        .map(|record| record.name_id().to_u16())
+        .collect();
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids, [1, 2, 6]);
+
+    let mut params = common::params(4);
+    params.name_ids = Some(vec![1, 6]);
+    let error = generate_ttf(&sheet(), params).err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "Name ID 2 can't be left out. The name table needs at least IDs 1, 2."
+    );
+
+    let mut params = common::params(4);
+    params.name_ids = Some(vec![1, 2, 7]);
+    assert!(generate_ttf(&sheet(), params).is_err());
+}
+
+#[test]
+fn params_builder_sets_the_same_fields_as_new() {
+    let mut params = common::params(4);
+    params.trim = Some(true);
+    params.family = Some("Pixel".to_string());
+    params.created_timestamp = Some(0);
+    let built = Params::builder()
+        .file_path("test.aseprite")
+        .glyph_size(4, 4)
+        .created_timestamp(0)
+        .trim(true)
+        .family("Pixel")
+        .build();
+    assert_eq!(
+        generate_ttf(&sheet(), built).unwrap(),
+        generate_ttf(&sheet(), params).unwrap()
+    );
+}
+
+#[test]
+fn fonts_can_be_generated_from_a_path() {
+    let path = std::env::temp_dir().join("ase2ttf_from_path.aseprite");
+    std::fs::write(&path, sheet()).unwrap();
+    let mut params = common::params(4);
+    params.file_path = None;
+    params.created_timestamp = Some(0);
+    let bytes = generate_ttf_from_path(&path, params).unwrap();
+    // callers that need the bytes for more than the font read them the same way
+    let (ase_bytes, params) = read_ase_file(&path, common::params(4)).unwrap();
+    assert_eq!(ase_bytes, sheet());
+    assert_eq!(params.file_path.as_deref(), path.to_str());
+    std::fs::remove_file(&path).unwrap();
+
+    // the family is named after the file that was read
+    let font = FontRef::new(&bytes).unwrap();
+    let name = font.name().unwrap();
+    let family = name
+        .name_record()
+        .iter()
+        .find(|record| record.name_id().to_u16() == 1)
+        .unwrap()
+        .string(name.string_data())
+        .unwrap()
+        .to_string();
+    assert_eq!(family, "ase2ttf_from_path");
+
+    let error = generate_ttf_from_path(&path, common::params(4))
+        .err()
+        .unwrap();
+    assert!(matches!(error, Error::FileRead { .. }));
+}
+
+#[test]
+fn reserved_glyphs_can_be_configured() {
+    let mut params = common::params(4);
+    params.baseline = Some(0);
+    params.config = Some(
+        "
+        [reserved.notdef]
+        advance = 4
+        visible = true
+
+        [reserved.space]
+        advance = 2
+        "
+        .to_string(),
+    );
+    let bytes = generate_ttf(&sheet(), params).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+
+    let h_metrics = font.hmtx().unwrap().h_metrics();
+    assert_eq!(h_metrics[0].advance(), 4 * 64);
+    assert_eq!(h_metrics[1].advance(), 4 * 64);
+    assert_eq!(h_metrics[2].advance(), 2 * 64);
+
+    // a hollow box, one pixel narrower than the advance
+    let glyf = font.glyf().unwrap();
+    let notdef = font
+        .loca(None)
+        .unwrap()
+        .get_glyf(GlyphId::new(0), &glyf)
+        .unwrap()
+        .unwrap();
+    assert_eq!(notdef.number_of_contours(), 2);
+    assert_eq!(notdef.x_max(), 3 * 64);
+
+    let mut params = common::params(4);
+    params.config = Some("[reserved.space]\nvisible = true".to_string());
+    assert!(generate_ttf(&sheet(), params).is_err());
+}
+
+#[test]
+fn space_width_sets_the_advance_of_the_space() {
+    let mut params = common::params(4);
+    params.space_width = Some(2);
+    let bytes = generate_ttf(&sheet(), params).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+    let h_metrics = font.hmtx().unwrap().h_metrics();
+    assert_eq!(h_metrics[2].advance(), 2 * 64);
+    assert_eq!(h_metrics[0].advance(), 4 * 64);
+
+    // the parameter takes the place of the advance in the config
+    let mut params = common::params(4);
+    params.space_width = Some(1);
+    params.config = Some("[reserved.space]\nadvance = 3".to_string());
+    let bytes = generate_ttf(&sheet(), params).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+    assert_eq!(font.hmtx().unwrap().h_metrics()[2].advance(), 64);
+}
+
+#[test]
+fn advances_file_sets_the_advance_of_each_codepoint() {
+    let mut params = common::params(4);
+    params.trim = Some(true);
+    params.advances = Some("0041 6\n\nU+0042 1\n0043 2\n".to_string());
+    let warnings = generation_warnings(&sheet(), &params).unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].kind, WarningKind::SkippedAdvance);
+    assert_eq!(warnings[0].codepoints, [0x43]);
+    assert_eq!(
+        warnings[0].message,
+        "the advance of U+0043 was skipped, as it has no glyph"
+    );
+
+    // A is glyph 3 and B is glyph 4
+    let bytes = generate_ttf(&sheet(), params).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+    let h_metrics = font.hmtx().unwrap().h_metrics();
+    assert_eq!(h_metrics[3].advance(), 6 * 64);
+    assert_eq!(h_metrics[4].advance(), 64);
+
+    let mut params = common::params(4);
+    params.advances = Some("Q 4".to_string());
+    assert_eq!(
+        generate_ttf(&sheet(), params).err().unwrap().to_string(),
+        "Invalid advances file: line 1: 'Q' is not a codepoint such as 0041 or U+0041."
+    );
+}
+
+#[test]
+fn device_metrics_add_hdmx_and_ltsh() {
+    let bytes = generate_ttf(&sheet(), common::params(4)).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+    assert!(font.table_data(Tag::new(b"hdmx")).is_none());
+
+    let mut params = common::params(4);
+    params.device_metrics = Some(vec![8, 4]);
+    let bytes = generate_ttf(&sheet(), params).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+
+    // the native size of 4 pixels per em is listed once, followed by 8
+    let hdmx = font.table_data(Tag::new(b"hdmx")).unwrap();
+    let hdmx = hdmx.as_bytes();
+    assert_eq!(&hdmx[..8], [0, 0, 0, 2, 0, 0, 0, 8]);
+    // size, max width, then the widths of .notdef, null, space, A and B
+    assert_eq!(&hdmx[8..15], [4, 5, 4, 4, 4, 3, 5]);
+    assert_eq!(&hdmx[16..23], [8, 10, 8, 8, 8, 6, 10]);
+
+    let ltsh = font.table_data(Tag::new(b"LTSH")).unwrap();
+    assert_eq!(ltsh.as_bytes(), [0, 0, 0, 5, 1, 1, 1, 1, 1]);
+
+    let mut params = common::params(4);
+    params.device_metrics = Some(vec![0]);
+    assert!(generate_ttf(&sheet(), params).is_err());
+}
+
+#[test]
+fn italic_angle_is_recorded_without_shearing() {
+    let upright = generate_ttf(&sheet(), common::params(4)).unwrap();
+    let upright = FontRef::new(&upright).unwrap();
+    assert_eq!(upright.post().unwrap().italic_angle().to_f64(), 0.0);
+
+    let mut params = common::params(4);
+    params.italic_angle = Some(-12.0);
+    let bytes = generate_ttf(&sheet(), params).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+
+    assert_eq!(font.post().unwrap().italic_angle().to_f64(), -12.0);
+    assert_eq!(font.os2().unwrap().fs_selection().bits() & 1, 1);
+    assert_eq!(font.head().unwrap().mac_style().bits() & 2, 2);
+    let hhea = font.hhea().unwrap();
+    assert_eq!(hhea.caret_slope_rise(), 256);
+    assert_eq!(hhea.caret_slope_run(), 54);
+
+    // the outlines are unchanged
+    let bounds = |font: &FontRef, id: u32| {
+        let glyf = font.glyf().unwrap();
+        let glyph = font
+            .loca(None)
+            .unwrap()
+            .get_glyf(GlyphId::new(id), &glyf)
+            .unwrap()
+            .unwrap();
+        (glyph.x_min(), glyph.y_min(), glyph.x_max(), glyph.y_max())
+    };
+    for id in [3, 4] {
+        assert_eq!(bounds(&font, id), bounds(&upright, id));
+    }
+
+    let mut params = common::params(4);
+    params.italic_angle = Some(90.0);
+    assert!(generate_ttf(&sheet(), params).is_err());
+}
+
+#[test]
+fn glyph_bounds_fit_the_ink_vertically() {
+    let bytes = generate_ttf(&sheet(), common::params(4)).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+    let glyf = font.glyf().unwrap();
+    let loca = font.loca(None).unwrap();
+    let bounds = |id: u32| {
+        let glyph = loca.get_glyf(GlyphId::new(id), &glyf).unwrap().unwrap();
+        (glyph.x_min(), glyph.y_min(), glyph.x_max(), glyph.y_max())
+    };
+
+    // blank rows take no part in the outline, while the baseline stays two pixels up the cell
+    assert_eq!(bounds(3), (0, 0, 2 * 64, 2 * 64));
+    assert_eq!(bounds(4), (0, -2 * 64, 4 * 64, 2 * 64));
+}
+
+#[test]
+fn faint_pixels_below_the_alpha_threshold_are_left_empty() {
+    const FAINT: [u8; 4] = [0, 0, 0, 64];
+    let mut ase = AseBuilder::new(4, 4);
+    let layer = ase.layer("U+0041-");
+    #[rustfmt::skip]
+    let pixels = vec![
+        common::EMPTY, common::EMPTY, common::EMPTY, common::EMPTY,
+        common::EMPTY, common::EMPTY, common::EMPTY, common::EMPTY,
+        common::INK, common::INK, FAINT, common::EMPTY,
+        common::INK, common::INK, FAINT, common::EMPTY,
+    ];
+    ase.pixels(0, layer, 0, 0, 4, 4, pixels);
+    let bytes = ase.build();
+    let x_max = |alpha_threshold: Option<u8>| {
+        let mut params = common::params(4);
+        params.alpha_threshold = alpha_threshold;
+        let font_bytes = generate_ttf(&bytes, params).unwrap();
+        let font = FontRef::new(&font_bytes).unwrap();
+        let glyf = font.glyf().unwrap();
+        let loca = font.loca(None).unwrap();
+        let glyph = loca.get_glyf(GlyphId::new(3), &glyf).unwrap().unwrap();
+        glyph.x_max()
+    };
+
+    // the anti-aliased column doesn't thicken the glyph, unless the threshold lets it in
+    assert_eq!(x_max(None), 2 * 64);
+    assert_eq!(x_max(Some(64)), 3 * 64);
+}
+
+#[test]
+fn max_points_simplifies_contours() {
+    let mut ase = AseBuilder::new(8, 8);
+    let layer = ase.layer("U+0041-");
+    ase.draw(
+        0,
+        layer,
+        0,
+        0,
+        "
+        #.......
+        ##......
+        ###.....
+        ####....
+        #####...
+        ######..
+        #######.
+        ########
+        ",
+    );
+    let bytes = ase.build();
+
+    let mut params = common::params(8);
+    params.max_points = Some(8);
+    let simplified = simplified_glyphs(&bytes, &params).unwrap();
+    assert_eq!(simplified.len(), 1);
+    assert_eq!(simplified[0].name, "U+0041");
+    // one point per corner of the staircase
+    assert_eq!(simplified[0].original_points, 18);
+    assert!(simplified[0].points <= 8);
+
+    let font = generate_ttf(&bytes, params).unwrap();
+    let font = FontRef::new(&font).unwrap();
+    assert!(font.maxp().unwrap().max_points().unwrap() <= 8);
+
+    // glyphs within the budget are left alone
+    let mut params = common::params(4);
+    params.max_points = Some(24);
+    assert_eq!(
+        simplified_glyphs(&sheet(), &params).unwrap(),
+        Vec::<SimplifiedGlyph>::new()
+    );
+
+    // the rectangles of 'B' are traced with their corners only
+    let mut params = common::params(4);
+    params.max_points = Some(8);
+    assert_eq!(
+        simplified_glyphs(&sheet(), &params).unwrap(),
+        Vec::<SimplifiedGlyph>::new()
+    );
+
+    // every contour keeps at least three points
+    let mut params = common::params(4);
+    params.max_points = Some(5);
+    let error = generate_ttf(&sheet(), params).err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "U+0042 has 2 contours, which can't fit within 5 points."
+    );
+}
+
+#[test]
+fn combining_marks_have_no_advance() {
+    let mut ase = AseBuilder::new(8, 4);
+    let layer = ase.layer("U+0300");
+    ase.draw(0, layer, 0, 0, ".#....#.");
+    let mut params = common::params(4);
+    params.config = Some("[glyphs.\"U+0301\"]\ncombining = false".to_string());
+    let bytes = generate_ttf(&ase.build(), params).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+
+    let h_metrics = font.hmtx().unwrap().h_metrics();
+    assert_eq!(h_metrics[3].advance(), 0);
+    assert_eq!(h_metrics[4].advance(), 2 * 64);
+
+    // the mark is moved back over the glyph before it, by the advance it would have trimmed
+    let glyf = font.glyf().unwrap();
+    let grave = font
+        .loca(None)
+        .unwrap()
+        .get_glyf(GlyphId::new(3), &glyf)
+        .unwrap()
+        .unwrap();
+    assert_eq!((grave.x_min(), grave.x_max()), (-64, 0));
+}
+
+#[test]
+fn config_overrides_the_values_of_a_glyph() {
+    let bounds = |bytes: &[u8]| {
+        let font = FontRef::new(bytes).unwrap();
+        let glyf = font.glyf().unwrap();
+        let a = font
+            .loca(None)
+            .unwrap()
+            .get_glyf(GlyphId::new(3), &glyf)
+            .unwrap()
+            .unwrap();
+        (a.x_min(), a.y_min(), a.x_max(), a.y_max())
+    };
+    let automatic = bounds(&generate_ttf(&sheet(), common::params(4)).unwrap());
+
+    let mut params = common::params(4);
+    params.config = Some(
+        "[glyphs.\"U+0041\"]\nadvance = 6\nleft_bearing = 2\nbaseline_offset = -1\nname = \"A\""
+            .to_string(),
+    );
+    let bytes = generate_ttf(&sheet(), params).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+
+    let h_metrics = font.hmtx().unwrap().h_metrics();
+    assert_eq!(h_metrics[3].advance(), 6 * 64);
+    assert_eq!(h_metrics[3].side_bearing(), 2 * 64);
+    // the ink is moved right to the bearing and down by a pixel
+    assert_eq!(
+        bounds(&bytes),
+        (
+            2 * 64,
+            automatic.1 - 64,
+            automatic.2 - automatic.0 + 2 * 64,
+            automatic.3 - 64
+        )
+    );
+    let post = font.post().unwrap();
+    assert_eq!(post.glyph_name(GlyphId16::new(3)), Some("A"));
+
+    // an entry that no cell is drawn for is most likely a typo
+    let mut params = common::params(4);
+    params.config = Some("[glyphs.\"U+0043\"]\nadvance = 6".to_string());
+    let error = generate_ttf(&sheet(), params).err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "The config has glyph entries for codepoints that are not in the file: U+0043"
+    );
+}
+
+#[test]
+fn combining_marks_stack_on_trimmed_letters() {
+    let mut ase = AseBuilder::new(6, 6);
+    let letter = ase.layer("U+0061");
+    ase.draw(0, letter, 0, 0, "......\n......\n###...\n###...");
+    let mark = ase.layer("U+0304");
+    ase.draw(0, mark, 0, 0, "###...");
+    let bounds = |trim: bool| {
+        let mut params = common::params(6);
+        params.trim = Some(trim);
+        let bytes = generate_ttf(&ase.build(), params).unwrap();
+        let font = FontRef::new(&bytes).unwrap();
+        let cmap = font.cmap().unwrap();
+        let hmtx = font.hmtx().unwrap();
+        let glyf = font.glyf().unwrap();
+        let loca = font.loca(None).unwrap();
+        let glyph_bounds = |c: char| {
+            let id = cmap.map_codepoint(c).unwrap();
+            let glyph = loca.get_glyf(id, &glyf).unwrap().unwrap();
+            (glyph.x_min(), glyph.x_max())
+        };
+        let letter_advance = hmtx.advance(cmap.map_codepoint('a').unwrap()).unwrap() as i16;
+        let (letter_min, letter_max) = glyph_bounds('a');
+        let (mark_min, mark_max) = glyph_bounds('\u{304}');
+        // the letter is set before the mark, so the mark is drawn one letter advance later
+        (
+            (letter_min, letter_max),
+            (mark_min + letter_advance, mark_max + letter_advance),
+        )
+    };
+
+    // the macron drawn over the letter in its cell covers the letter once set after it, whether
+    // the letter takes its trimmed advance or the whole cell
+    for trim in [true, false] {
+        let (letter, mark) = bounds(trim);
+        assert_eq!(letter, (0, 3 * 64));
+        assert_eq!(mark, letter);
+    }
+}
+
+#[test]
+fn baseline_must_be_within_the_glyph_height() {
+    for baseline in [0, 4] {
+        let mut params = common::params(4);
+        params.baseline = Some(baseline);
+        assert!(generate_ttf(&sheet(), params).is_ok());
+    }
+
+    for baseline in [-1, 5] {
+        let mut params = common::params(4);
+        params.baseline = Some(baseline);
+        let error = generate_ttf(&sheet(), params).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "The baseline {} must be between 0 and the glyph height 4.",
+                baseline
+            )
+        );
+    }
+}
+
+#[test]
+fn trimmed_advances_follow_a_fractional_scale() {
+    let mut ase = AseBuilder::new(6, 4);
+    let layer = ase.layer("U+0041-");
+    ase.draw(0, layer, 0, 0, "##.#..");
+    let bytes = ase.build();
+    // the glyphs are narrower than they are tall, so trimmed widths are scaled by 4 / 3
+    let params = |clamp| {
+        let mut params = common::params(4);
+        params.glyph_width = Some(3);
+        params.clamp_trim_scale = Some(clamp);
+        params
+    };
+
+    let font = generate_ttf(&bytes, params(false)).unwrap();
+    let h_metrics = FontRef::new(&font).unwrap().hmtx().unwrap().h_metrics();
+    assert_eq!(h_metrics[3].advance(), 4 * 64);
+    assert_eq!(h_metrics[4].advance(), 3 * 64);
+    let uneven = uneven_trim_scale(&bytes, &params(false)).unwrap().unwrap();
+    assert_eq!(uneven.glyphs, 1);
+
+    let font = generate_ttf(&bytes, params(true)).unwrap();
+    let h_metrics = FontRef::new(&font).unwrap().hmtx().unwrap().h_metrics();
+    assert_eq!(h_metrics[3].advance(), 3 * 64);
+    assert_eq!(h_metrics[4].advance(), 2 * 64);
+    assert_eq!(uneven_trim_scale(&bytes, &params(true)).unwrap(), None);
+}
+
+/// A sheet with two frames, where the second draws the glyphs with thicker strokes.
+fn masters(heavy: &str) -> Vec<u8> {
+    let mut ase = AseBuilder::new(8, 4).frames(2);
+    let layer = ase.layer("U+0041-");
+    ase.draw(0, layer, 0, 0, "#...#\n#...#\n#...#");
+    ase.draw(1, layer, 0, 0, heavy);
+    ase.build()
+}
+
+#[test]
+fn variable_font_interpolates_two_masters() {
+    let mut params = common::params(4);
+    params.variable = Some(true);
+    params.font_weight = Some(100);
+    let bytes = generate_ttf(&masters("##..##\n##..##\n##..##"), params).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+
+    let axis = font.fvar().unwrap().axes().unwrap()[0];
+    assert_eq!(axis.axis_tag(), Tag::new(b"wght"));
+    assert_eq!(
+        (axis.min_value().to_f64(), axis.default_value().to_f64()),
+        (100.0, 100.0)
+    );
+    assert_eq!(axis.max_value().to_f64(), 900.0);
+    assert!(font.avar().is_ok());
+
+    // the bars get one pixel wider, which moves the corners on their right
+    let gvar = font.gvar().unwrap();
+    assert_eq!(gvar.glyph_count(), 5);
+    let glyf = font.glyf().unwrap();
+    let loca = font.loca(None).unwrap();
+    for id in [3, 4] {
+        let glyph = loca.get_glyf(GlyphId::new(id), &glyf).unwrap().unwrap();
+        assert_eq!(glyph.x_max() - glyph.x_min(), 64);
+        let variations = gvar
+            .glyph_variation_data(GlyphId::new(id))
+            .unwrap()
+            .unwrap();
+        let deltas: Vec<_> = variations
+            .tuples()
+            .next()
+            .unwrap()
+            .deltas()
+            .map(|delta| (delta.x_delta, delta.y_delta))
+            .collect();
+        assert!(deltas.contains(&(64, 0)));
+        assert!(deltas.iter().all(|&(_, y)| y == 0));
+    }
+}
+
+#[test]
+fn variable_font_needs_matching_contours() {
+    let mut params = common::params(4);
+    params.variable = Some(true);
+    // the heavy 'A' has a notch, so it has more corners than the bar
+    let error = generate_ttf(&masters("##..##\n#...##\n##..##"), params)
+        .err()
+        .unwrap();
+    assert_eq!(
+        error.to_string(),
+        "Contour 1 of U+0041 has 4 points in the first master but 8 in the second, so it can't be interpolated."
+    );
+
+    let mut params = common::params(4);
+    params.variable = Some(true);
+    let error = generate_ttf(&masters("#.#.##\n#.#.##\n#.#.##"), params)
+        .err()
+        .unwrap();
+    assert_eq!(
+        error.to_string(),
+        "U+0041 has 1 contour in the first master but 2 in the second, so it can't be interpolated."
+    );
+}
+
+/// The sum of the big-endian 32-bit words of the data, padded with zeros.
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4)
+        .map(|chunk| {
+            let mut word = [0; 4];
+            word[..chunk.len()].copy_from_slice(chunk);
+            u32::from_be_bytes(word)
+        })
+        .fold(0, u32::wrapping_add)
+}
+
+#[test]
+fn checksum_adjustment_balances_the_font() {
+    let mut params = common::params(4);
+    params.variable = Some(true);
+    let fonts = [
+        generate_ttf(&sheet(), common::params(4)).unwrap(),
+        generate_ttf(&masters("##..##\n##..##\n##..##"), params).unwrap(),
+    ];
+    for bytes in fonts {
+        // with the adjustment in place, the whole font sums to the magic number
+        assert_eq!(checksum(&bytes), 0xB1B0AFBA);
+
+        // and every table matches the checksum in its record, with the head table summed
+        // without the adjustment
+        let font = FontRef::new(&bytes).unwrap();
+        for record in font.table_directory.table_records() {
+            let mut data = font.table_data(record.tag()).unwrap().as_bytes().to_vec();
+            if record.tag() == Tag::new(b"head") {
+                assert_ne!(font.head().unwrap().checksum_adjustment(), 0);
+                data[8..12].fill(0);
+            }
+            assert_eq!(checksum(&data), record.checksum(), "{}", record.tag());
+        }
+    }
+}
+
+#[test]
+fn trailing_space_is_kept_in_the_advance() {
+    let mut ase = AseBuilder::new(8, 4);
+    let layer = ase.layer("U+0041-");
+    // 'A' leaves two columns after its ink, and 'B' reaches the edge of its cell
+    ase.draw(0, layer, 0, 0, ".#....##");
+    let bytes = ase.build();
+
+    let font = generate_ttf(&bytes, common::params(4)).unwrap();
+    let hmtx = FontRef::new(&font).unwrap().hmtx().unwrap();
+    assert_eq!(hmtx.advance(GlyphId::new(3)).unwrap(), 2 * 64);
+    assert_eq!(hmtx.advance(GlyphId::new(4)).unwrap(), 3 * 64);
+
+    let mut params = common::params(4);
+    params.keep_trailing_space = Some(true);
+    let font = generate_ttf(&bytes, params).unwrap();
+    let font = FontRef::new(&font).unwrap();
+    let hmtx = font.hmtx().unwrap();
+    assert_eq!(hmtx.advance(GlyphId::new(3)).unwrap(), 3 * 64);
+    assert_eq!(hmtx.advance(GlyphId::new(4)).unwrap(), 3 * 64);
+
+    // the leading columns are dropped, so both glyphs start at the origin
+    let glyf = font.glyf().unwrap();
+    let loca = font.loca(None).unwrap();
+    for id in [3, 4] {
+        let glyph = loca.get_glyf(GlyphId::new(id), &glyf).unwrap().unwrap();
+        assert_eq!(glyph.x_min(), 0);
+    }
+}
+
+#[test]
+fn aliases_share_the_glyph_of_a_codepoint() {
+    let mut params = common::params(4);
+    params.config = Some("[aliases]\n\"U+0041\" = [\"U+0391\", \"U+0410\"]".to_string());
+    let bytes = generate_ttf(&sheet(), params).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+
+    let cmap = font.cmap().unwrap();
+    for codepoint in [0x41u32, 0x391, 0x410] {
+        assert_eq!(cmap.map_codepoint(codepoint).map(|id| id.to_u32()), Some(3));
+    }
+    assert_eq!(font.maxp().unwrap().num_glyphs(), 5);
+
+    let mut params = common::params(4);
+    params.config = Some("[aliases]\n\"U+0043\" = [\"U+2212\"]".to_string());
+    let error = generate_ttf(&sheet(), params).err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "Invalid config: U+0043 has aliases but no glyph."
+    );
+
+    let mut params = common::params(4);
+    params.config = Some("[aliases]\n\"U+0041\" = [\"U+0042\"]".to_string());
+    let error = generate_ttf(&sheet(), params).err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "U+0042 already has a glyph, so it can't be an alias of U+0041."
+    );
+}
+
+#[test]
+fn blank_cells_can_be_given_empty_glyphs() {
+    let mut ase = AseBuilder::new(16, 4);
+    let layer = ase.layer("U+0041-");
+    ase.draw(0, layer, 0, 0, "##......##......");
+    let bytes = ase.build();
+
+    let font = generate_ttf(&bytes, common::params(4)).unwrap();
+    let cmap = FontRef::new(&font).unwrap().cmap().unwrap();
+    assert_eq!(cmap.map_codepoint('B'), None);
+
+    let mut params = common::params(4);
+    params.emit_blank_glyphs = Some(true);
+    let font = generate_ttf(&bytes, params).unwrap();
+    let font = FontRef::new(&font).unwrap();
+    let cmap = font.cmap().unwrap();
+    // the blank cell after the last glyph is the rest of the sheet
+    assert_eq!(cmap.map_codepoint('C').map(|id| id.to_u32()), Some(4));
+    assert_eq!(cmap.map_codepoint('B').map(|id| id.to_u32()), Some(5));
+    assert_eq!(cmap.map_codepoint('D'), None);
+
+    let blank = GlyphId::new(5);
+    assert_eq!(font.hmtx().unwrap().advance(blank), Some(4 * 64));
+    assert_eq!(
+        font.post().unwrap().glyph_name(blank.try_into().unwrap()),
+        Some("U+0042")
+    );
+    let loca = font.loca(None).unwrap();
+    assert!(
+        loca.get_glyf(blank, &font.glyf().unwrap())
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[test]
+fn codepoints_above_the_bmp_get_a_format_12_subtable() {
+    let mut ase = AseBuilder::new(4, 4);
+    let layer = ase.layer("U+0041");
+    ase.draw(0, layer, 0, 0, "#");
+    let layer = ase.layer("U+1F600");
+    ase.draw(0, layer, 0, 0, "##");
+    let bytes = generate_ttf(&ase.build(), common::params(4)).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+
+    let cmap = font.cmap().unwrap();
+    assert_eq!(cmap.map_codepoint('A').map(|id| id.to_u32()), Some(3));
+    assert_eq!(
+        cmap.map_codepoint(0x1F600u32).map(|id| id.to_u32()),
+        Some(4)
+    );
+    let encodings: Vec<(u16, u16, u16)> = cmap
+        .encoding_records()
+        .iter()
+        .map(|record| {
+            let subtable = record.subtable(cmap.offset_data()).unwrap();
+            (
+                record.platform_id() as u16,
+                record.encoding_id(),
+                subtable.format(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        encodings,
+        [(0, 3, 4), (0, 4, 12), (1, 0, 4), (3, 1, 4), (3, 10, 12)]
+    );
+}
+
+#[test]
+fn cmap_segments_are_sorted_whatever_the_layer_order() {
+    let mut ase = AseBuilder::new(4, 4);
+    let layer = ase.layer("U+0061");
+    ase.draw(0, layer, 0, 0, "#");
+    let layer = ase.layer("U+0041");
+    ase.draw(0, layer, 0, 0, "##");
+    let bytes = generate_ttf(&ase.build(), common::params(4)).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+
+    let cmap = font.cmap().unwrap();
+    let record = &cmap.encoding_records()[0];
+    let CmapSubtable::Format4(subtable) = record.subtable(cmap.offset_data()).unwrap() else {
+        panic!("expected a format 4 subtable");
+    };
+    let start_codes: Vec<u16> = subtable.start_code().iter().map(|c| c.get()).collect();
+    assert_eq!(start_codes, [0x0000, 0x0020, 0x0041, 0x0061, 0xFFFF]);
+    assert_eq!(cmap.map_codepoint('a').map(|id| id.to_u32()), Some(3));
+    assert_eq!(cmap.map_codepoint('A').map(|id| id.to_u32()), Some(4));
+}
+
+#[test]
+fn units_per_em_scales_every_metric() {
+    let default = generate_ttf(&sheet(), common::params(4)).unwrap();
+    let default = FontRef::new(&default).unwrap();
+    let mut params = common::params(4);
+    params.units_per_em = Some(1000);
+    let bytes = generate_ttf(&sheet(), params).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+
+    // 4 pixels per em, so each pixel is 250 units instead of 64
+    let scaled = |units: i32| units / 64 * 250;
+    assert_eq!(font.head().unwrap().units_per_em(), 1000);
+    assert_eq!(
+        font.hhea().unwrap().ascender().to_i16() as i32,
+        scaled(default.hhea().unwrap().ascender().to_i16() as i32)
+    );
+    let glyph = GlyphId::new(4);
+    assert_eq!(
+        font.hmtx().unwrap().advance(glyph).unwrap() as i32,
+        scaled(default.hmtx().unwrap().advance(glyph).unwrap() as i32)
+    );
+    let glyf = font.glyf().unwrap();
+    let outline = font
+        .loca(None)
+        .unwrap()
+        .get_glyf(glyph, &glyf)
+        .unwrap()
+        .unwrap();
+    assert_eq!((outline.x_min(), outline.x_max()), (0, 1000));
+
+    let mut params = common::params(4);
+    params.units_per_em = Some(1001);
+    let error = generate_ttf(&sheet(), params).err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "The units per em 1001 must be a multiple of the em size of 4 pixels."
+    );
+}
+
+#[test]
+fn errors_can_be_matched_by_kind() {
+    let mut params = common::params(4);
+    params.glyph_width = Some(3);
+    let error = generate_ttf(&sheet(), params).err().unwrap();
+    assert!(matches!(
+        error,
+        Error::DimensionMismatch {
+            width: 8,
+            height: 4,
+            glyph_width: 3,
+            glyph_height: 4,
+            cell_margin: 0,
+        }
+    ));
+    assert_eq!(
+        error.to_string(),
+        "The height and width of the aseprite file must be multiples of glyph-width and glyph-height respectively."
+    );
+
+    let mut ase = AseBuilder::new(4, 4);
+    let layer = ase.layer("Background");
+    ase.draw(0, layer, 0, 0, "#");
+    let error = generate_ttf(&ase.build(), common::params(4)).err().unwrap();
+    assert!(matches!(error, Error::NoValidLayers));
+
+    let error = generate_ttf(b"not an aseprite file", common::params(4))
+        .err()
+        .unwrap();
+    assert!(matches!(error, Error::AseRead(_)));
+    assert!(std::error::Error::source(&error).is_some());
+}
+
+#[test]
+fn spacing_widens_every_advance() {
+    let default = generate_ttf(&sheet(), common::params(4)).unwrap();
+    let default = FontRef::new(&default).unwrap();
+    let mut params = common::params(4);
+    params.spacing = Some(2);
+    let bytes = generate_ttf(&sheet(), params).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+
+    let (default_hmtx, hmtx) = (default.hmtx().unwrap(), font.hmtx().unwrap());
+    // .notdef, null, space, 'A' and 'B'
+    for id in 0..5 {
+        let glyph = GlyphId::new(id);
+        assert_eq!(
+            hmtx.advance(glyph).unwrap(),
+            default_hmtx.advance(glyph).unwrap() + 2 * 64
+        );
+    }
+    assert_eq!(
+        font.hhea().unwrap().advance_width_max().to_u16(),
+        hmtx.advance(GlyphId::new(4)).unwrap()
+    );
+}
+
+#[test]
+fn corner_radius_rounds_the_corners_with_curves() {
+    let mut params = common::params(4);
+    params.corner_radius = Some(0.5);
+    let bytes = generate_ttf(&sheet(), params).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+
+    // the square of 'A' has four corners, each becoming a curve through two on-curve points
+    let glyf = font.glyf().unwrap();
+    let loca = font.loca(None).unwrap();
+    let Some(Glyph::Simple(square)) = loca.get_glyf(GlyphId::new(3), &glyf).unwrap() else {
+        panic!("expected a simple glyph");
+    };
+    assert_eq!(square.num_points(), 12);
+    let off_curve = square.points().filter(|point| !point.on_curve).count();
+    assert_eq!(off_curve, 4);
+    assert_eq!(font.maxp().unwrap().max_points(), Some(24));
+
+    let mut params = common::params(4);
+    params.corner_radius = Some(-1.0);
+    let error = generate_ttf(&sheet(), params).err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "The corner radius must be 0 or more, but it is -1."
+    );
+}
+
+#[test]
+fn straight_edges_are_traced_with_their_corners_only() {
+    let mut ase = AseBuilder::new(4, 4);
+    let layer = ase.layer("U+0041");
+    ase.draw(0, layer, 0, 1, "###.\n###.");
+    let bytes = generate_ttf(&ase.build(), common::params(4)).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+
+    let glyf = font.glyf().unwrap();
+    let loca = font.loca(None).unwrap();
+    let Some(Glyph::Simple(rectangle)) = loca.get_glyf(GlyphId::new(3), &glyf).unwrap() else {
+        panic!("expected a simple glyph");
+    };
+    assert_eq!(rectangle.num_points(), 4);
+    assert_eq!(font.maxp().unwrap().max_points(), Some(4));
+}
+
+#[test]
+fn glyph_ids_follow_the_cells_of_the_sheet() {
+    // enough cells to be split across threads, each drawn with its own width
+    let mut ase = AseBuilder::new(64, 64);
+    let layer = ase.layer("U+4E00");
+    let mut art = String::new();
+    for cell_row in 0..8 {
+        for y in 0..8 {
+            for cell in cell_row * 8..cell_row * 8 + 8 {
+                let width = if y == 0 { cell % 4 + 1 } else { 0 };
+                art += &format!("{:.<8}", "#".repeat(width));
+            }
+            art += "\n";
+        }
+    }
+    ase.draw(0, layer, 0, 0, &art);
+    let bytes = generate_ttf(&ase.build(), common::params(8)).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+
+    let cmap = font.cmap().unwrap();
+    let glyf = font.glyf().unwrap();
+    let loca = font.loca(None).unwrap();
+    for cell in 0..64u32 {
+        let id = cmap.map_codepoint(0x4E00 + cell).unwrap();
+        assert_eq!(id.to_u32(), 3 + cell);
+        let Some(Glyph::Simple(glyph)) = loca.get_glyf(id, &glyf).unwrap() else {
+            panic!("expected a simple glyph");
+        };
+        assert_eq!(glyph.x_max() - glyph.x_min(), (cell % 4 + 1) as i16 * 64);
+    }
+}
+
+#[test]
+fn head_bounds_the_ink_of_every_glyph() {
+    let mut ase = AseBuilder::new(8, 4);
+    let layer = ase.layer("U+0041");
+    ase.draw(
+        0,
+        layer,
+        0,
+        0,
+        "
+        ##......
+        .....#..
+        ",
+    );
+    let bytes = generate_ttf(&ase.build(), common::params(4)).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+
+    // A is drawn in the top row and B in the row on the baseline, so no ink reaches below it
+    let head = font.head().unwrap();
+    assert_eq!(
+        (head.x_min(), head.y_min(), head.x_max(), head.y_max()),
+        (0, 0, 2 * 64, 2 * 64)
+    );
+}
+
+#[test]
+fn char_range_follows_the_cmap() {
+    let bytes = generate_ttf(&sheet(), common::params(4)).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+
+    // the space is always mapped, so the range starts there
+    let os2 = font.os2().unwrap();
+    assert_eq!(os2.us_first_char_index(), 0x0020);
+    assert_eq!(os2.us_last_char_index(), 0x0042);
+}
+
+#[test]
+fn subfamily_sets_the_style_flags() {
+    let style = |subfamily: Option<&str>| {
+        let mut params = common::params(4);
+        params.subfamily = subfamily.map(str::to_string);
+        let bytes = generate_ttf(&sheet(), params).unwrap();
+        let font = FontRef::new(&bytes).unwrap();
+        let os2 = font.os2().unwrap();
+        (
+            os2.us_weight_class(),
+            os2.fs_selection().bits(),
+            font.head().unwrap().mac_style().bits(),
+        )
+    };
+
+    // REGULAR is 0x40 in fsSelection, BOLD is 0x20 and ITALIC is 0x01, next to the
+    // USE_TYPO_METRICS bit 0x80 of every font
+    assert_eq!(style(None), (400, 0xC0, 0));
+    assert_eq!(style(Some("Bold")), (700, 0xA0, 1));
+    assert_eq!(style(Some("Italic")), (400, 0x81, 2));
+    assert_eq!(style(Some("Bold Italic")), (700, 0xA1, 3));
+    assert_eq!(style(Some("Light")), (300, 0xC0, 0));
+}
+
+#[test]
+fn font_version_sets_the_revision() {
+    let revision = |version: Option<&str>| {
+        let mut params = common::params(4);
+        params.font_version = version.map(str::to_string);
+        let bytes = generate_ttf(&sheet(), params).unwrap();
+        let font = FontRef::new(&bytes).unwrap();
+        font.head().unwrap().font_revision().to_f64()
+    };
+
+    assert!((revision(Some("Version 2.3")) - 2.3).abs() < 0.0001);
+    assert!((revision(Some("2.3")) - 2.3).abs() < 0.0001);
+    assert_eq!(revision(Some("version 3")), 3.0);
+    assert_eq!(revision(Some("beta")), 1.0);
+    assert_eq!(revision(None), 1.0);
+}
+
+#[test]
+fn created_timestamp_makes_the_build_reproducible() {
+    let build = || {
+        let mut params = common::params(4);
+        params.created_timestamp = Some(1_700_000_000);
+        generate_ttf(&sheet(), params).unwrap()
+    };
+    let bytes = build();
+    assert_eq!(bytes, build());
+
+    let font = FontRef::new(&bytes).unwrap();
+    let head = font.head().unwrap();
+    assert_eq!(head.created().as_secs(), 1_700_000_000);
+    assert_eq!(head.modified().as_secs(), 1_700_000_000);
+}
+
+#[test]
+fn ascent_and_descent_split_the_cell() {
+    let ascender = |ascent: Option<i16>, descent: Option<i16>| {
+        let mut params = common::params(4);
+        params.ascent = ascent;
+        params.descent = descent;
+        let bytes = generate_ttf(&sheet(), params)?;
+        let font = FontRef::new(&bytes).unwrap();
+        let hhea = font.hhea().unwrap();
+        Ok::<_, Error>((hhea.ascender().to_i16(), hhea.descender().to_i16()))
+    };
+
+    assert_eq!(ascender(None, Some(1)).unwrap(), (3 * 64, -64));
+    assert_eq!(ascender(Some(1), None).unwrap(), (64, -3 * 64));
+    assert_eq!(ascender(Some(3), Some(1)).unwrap(), (3 * 64, -64));
+    assert_eq!(
+        ascender(Some(3), Some(2)).err().unwrap().to_string(),
+        "The ascent 3 and the descent 2 must add up to the glyph height 4."
+    );
+
+    // the ascent can fill the whole cell, but not more
+    assert_eq!(ascender(Some(0), None).unwrap(), (0, -4 * 64));
+    assert_eq!(ascender(Some(4), None).unwrap(), (4 * 64, 0));
+    for ascent in [-1, 5] {
+        assert_eq!(
+            ascender(Some(ascent), None).err().unwrap().to_string(),
+            format!(
+                "The ascent {} must be between 0 and the glyph height 4.",
+                ascent
+            )
+        );
+    }
+}
+
+#[test]
+fn hhea_and_os2_agree_for_a_wide_cell() {
+    // the em follows the width of the cell, but the ascender only covers its height
+    let mut params = common::params(4);
+    params.glyph_width = Some(8);
+    params.descent = Some(1);
+    let bytes = generate_ttf(&sheet(), params).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+    let hhea = font.hhea().unwrap();
+    let os2 = font.os2().unwrap();
+
+    assert_eq!(font.head().unwrap().units_per_em(), 8 * 64);
+    assert_eq!(hhea.ascender().to_i16(), 3 * 64);
+    assert_eq!(hhea.ascender().to_i16(), os2.s_typo_ascender());
+    assert_eq!(hhea.ascender().to_i16(), os2.us_win_ascent() as i16);
+    assert_eq!(hhea.descender().to_i16(), os2.s_typo_descender());
+    assert_eq!(hhea.descender().to_i16(), -(os2.us_win_descent() as i16));
+}
+
+#[test]
+fn ligature_layers_join_their_components() {
+    let ligatures = |name: &str| {
+        let mut ase = AseBuilder::new(8, 4);
+        let letters = ase.layer("U+0068");
+        ase.draw(
+            0,
+            letters,
+            0,
+            0,
+            "
+            #.....#.
+            ###.....
+            #.#...#.
+            #.#...#.
+            ",
+        );
+        let ligature = ase.layer(name);
+        ase.draw(0, ligature, 0, 0, "#.#.\n###.\n#.##\n#.##");
+        generate_ttf(&ase.build(), common::params(4))
+    };
+
+    let bytes = ligatures("h_i").unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+    // the ligature is unencoded, after the glyphs of its components
+    assert_eq!(font.maxp().unwrap().num_glyphs(), 6);
+    let gsub = font.gsub().unwrap();
+    let features: Vec<String> = gsub
+        .feature_list()
+        .unwrap()
+        .feature_records()
+        .iter()
+        .map(|record| record.feature_tag().to_string())
+        .collect();
+    assert_eq!(features, ["liga"]);
+    let lookup = gsub.lookup_list().unwrap().lookups().get(0).unwrap();
+    let SubstitutionSubtables::Ligature(subtables) = lookup.subtables().unwrap() else {
+        panic!("expected a ligature lookup");
+    };
+    let subtable = subtables.get(0).unwrap();
+    let first: Vec<u32> = subtable
+        .coverage()
+        .unwrap()
+        .iter()
+        .map(|id| id.to_u32())
+        .collect();
+    assert_eq!(first, [3]);
+    let ligature = subtable
+        .ligature_sets()
+        .get(0)
+        .unwrap()
+        .ligatures()
+        .get(0)
+        .unwrap();
+    assert_eq!(ligature.ligature_glyph().to_u32(), 5);
+    let rest: Vec<u32> = ligature
+        .component_glyph_ids()
+        .iter()
+        .map(|id| id.get().to_u32())
+        .collect();
+    assert_eq!(rest, [4]);
+    assert_eq!(font.os2().unwrap().us_max_context(), Some(2));
+
+    assert_eq!(
+        ligatures("h_j").err().unwrap().to_string(),
+        "The ligature h_j joins U+006A, which has no glyph."
+    );
+}
+
+#[test]
+fn identical_cells_can_share_a_glyph() {
+    let mut ase = AseBuilder::new(12, 4);
+    let latin = ase.layer("U+0041");
+    ase.draw(
+        0,
+        latin,
+        0,
+        0,
+        "
+        .##.####.##.
+        #..##...#..#
+        ####.##.####
+        #..##..##..#
+        ",
+    );
+    let cyrillic = ase.layer("U+0410");
+    ase.draw(
+        0,
+        cyrillic,
+        0,
+        0,
+        "
+        .##.
+        #..#
+        ####
+        #..#
+        ",
+    );
+    let bytes = ase.build();
+    let glyph_ids = |share_glyphs: Option<bool>| {
+        let mut params = common::params(4);
+        params.share_glyphs = share_glyphs;
+        let font = generate_ttf(&bytes, params).unwrap();
+        let font = FontRef::new(&font).unwrap();
+        let cmap = font.cmap().unwrap();
+        let glyph_ids: Vec<u32> = ['A', 'B', 'C', 'А']
+            .into_iter()
+            .map(|c| cmap.map_codepoint(c).unwrap().to_u32())
+            .collect();
+        (glyph_ids, font.maxp().unwrap().num_glyphs())
+    };
+
+    assert_eq!(glyph_ids(None), (vec![3, 4, 5, 6], 7));
+    // C and Cyrillic А are drawn like A
+    assert_eq!(glyph_ids(Some(true)), (vec![3, 4, 3, 3], 5));
+}
+
+#[test]
+fn repeated_outlines_become_composite_glyphs() {
+    let build = |composite_threshold: Option<u16>| {
+        let mut ase = AseBuilder::new(12, 4);
+        let layer = ase.layer("U+0041");
+        ase.draw(
+            0,
+            layer,
+            0,
+            0,
+            "
+            ##.......###
+            ##..........
+            .....##..###
+            .....##.....
+            ",
+        );
+        let mut params = common::params(4);
+        params.composite_threshold = composite_threshold;
+        generate_ttf(&ase.build(), params).unwrap()
+    };
+
+    let bytes = build(Some(1));
+    let font = FontRef::new(&bytes).unwrap();
+    let glyf = font.glyf().unwrap();
+    let loca = font.loca(None).unwrap();
+    // B is A moved down, and C is a shape of its own
+    let Some(Glyph::Composite(moved)) = loca.get_glyf(GlyphId::new(4), &glyf).unwrap() else {
+        panic!("expected a composite glyph");
+    };
+    let components: Vec<_> = moved.components().collect();
+    assert_eq!(components.len(), 1);
+    assert_eq!(components[0].glyph.to_u32(), 3);
+    assert_eq!(components[0].anchor, Anchor::Offset { x: 64, y: -2 * 64 });
+    assert!(matches!(
+        loca.get_glyf(GlyphId::new(5), &glyf).unwrap(),
+        Some(Glyph::Simple(_))
+    ));
+    let maxp = font.maxp().unwrap();
+    assert_eq!(maxp.max_component_elements(), Some(1));
+    assert_eq!(maxp.max_component_depth(), Some(1));
+
+    // the threshold is the fewest points worth sharing, and composites are off by default
+    for threshold in [None, Some(0), Some(5)] {
+        let bytes = build(threshold);
+        let font = FontRef::new(&bytes).unwrap();
+        let glyf = font.glyf().unwrap();
+        let loca = font.loca(None).unwrap();
+        assert!(matches!(
+            loca.get_glyf(GlyphId::new(4), &glyf).unwrap(),
+            Some(Glyph::Simple(_))
+        ));
+        assert_eq!(font.maxp().unwrap().max_component_depth(), Some(0));
+    }
+}
+
+#[test]
+fn color_mode_draws_each_color_as_a_layer() {
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+    let mut ase = AseBuilder::new(8, 4);
+    let layer = ase.layer("U+0041");
+    // a two-tone A and a blue B
+    let rows = ["rr...b..", "rr...b..", "bb...b..", "........"];
+    let pixels = rows
+        .iter()
+        .flat_map(|row| row.chars())
+        .map(|c| match c {
+            'r' => RED,
+            'b' => BLUE,
+            _ => common::EMPTY,
+        })
+        .collect();
+    ase.pixels(0, layer, 0, 0, 8, 4, pixels);
+    let mut params = common::params(4);
+    params.color = Some(true);
+    let bytes = generate_ttf(&ase.build(), params).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+
+    // the layers come after A and B. The colors of a glyph are sorted, and the palette lists
+    // them in the order they first appear
+    assert_eq!(font.maxp().unwrap().num_glyphs(), 8);
+    let colr = font.colr().unwrap();
+    let base_glyphs: Vec<(u32, u16, u16)> = colr
+        .base_glyph_records()
+        .unwrap()
+        .unwrap()
+        .iter()
+        .map(|record| {
+            (
+                record.glyph_id().to_u32(),
+                record.first_layer_index(),
+                record.num_layers(),
+            )
+        })
+        .collect();
+    assert_eq!(base_glyphs, [(3, 0, 2), (4, 2, 1)]);
+    let layers: Vec<(u32, u16)> = colr
+        .layer_records()
+        .unwrap()
+        .unwrap()
+        .iter()
+        .map(|record| (record.glyph_id().to_u32(), record.palette_index()))
+        .collect();
+    assert_eq!(layers, [(5, 0), (6, 1), (7, 0)]);
+
+    let cpal = font.cpal().unwrap();
+    let colors: Vec<[u8; 3]> = cpal
+        .color_records_array()
+        .unwrap()
+        .unwrap()
+        .iter()
+        .map(|color| [color.red(), color.green(), color.blue()])
+        .collect();
+    assert_eq!(colors, [[0, 0, 255], [255, 0, 0]]);
+
+    // without the color mode, there are no layers
+    let bytes = generate_ttf(&ase.build(), common::params(4)).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+    assert_eq!(font.maxp().unwrap().num_glyphs(), 5);
+    assert!(font.colr().is_err());
+}
+
+#[test]
+fn config_palettes_follow_the_default_one() {
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+    let mut ase = AseBuilder::new(4, 4);
+    let layer = ase.layer("U+0041");
+    #[rustfmt::skip]
+    let pixels = vec![
+        RED, RED, common::EMPTY, common::EMPTY,
+        BLUE, BLUE, common::EMPTY, common::EMPTY,
+        common::EMPTY, common::EMPTY, common::EMPTY, common::EMPTY,
+        common::EMPTY, common::EMPTY, common::EMPTY, common::EMPTY,
+    ];
+    ase.pixels(0, layer, 0, 0, 4, 4, pixels);
+    let bytes = ase.build();
+    let build = |config: &str| {
+        let mut params = common::params(4);
+        params.color = Some(true);
+        params.config = Some(config.to_string());
+        generate_ttf(&bytes, params)
+    };
+
+    let font_bytes = build(
+        r##"
+        [[palettes]]
+        colors = { "#FF0000" = "#00FFFF", "#0000ff" = "#FFFF00" }
+
+        [[palettes]]
+        colors = { "#0000FF" = "#000000" }
+        "##,
+    )
+    .unwrap();
+    let font = FontRef::new(&font_bytes).unwrap();
+    let cpal = font.cpal().unwrap();
+    assert_eq!(cpal.num_palettes(), 3);
+    assert_eq!(cpal.num_palette_entries(), 2);
+    assert_eq!(cpal.color_record_indices(), [0, 2, 4]);
+    let colors: Vec<[u8; 3]> = cpal
+        .color_records_array()
+        .unwrap()
+        .unwrap()
+        .iter()
+        .map(|color| [color.red(), color.green(), color.blue()])
+        .collect();
+    // the colors that a palette leaves out are drawn as they are
+    assert_eq!(
+        colors,
+        [
+            [0, 0, 255],
+            [255, 0, 0],
+            [255, 255, 0],
+            [0, 255, 255],
+            [0, 0, 0],
+            [255, 0, 0],
+        ]
+    );
+
+    assert_eq!(
+        build("[[palettes]]\ncolors = { \"#00FF00\" = \"#000000\" }")
+            .err()
+            .unwrap()
+            .to_string(),
+        "Invalid config: palette 1 replaces #00FF00, which no glyph is drawn in."
+    );
+    assert_eq!(
+        build("[[palettes]]\ncolors = { \"red\" = \"#000000\" }")
+            .err()
+            .unwrap()
+            .to_string(),
+        "Invalid config: 'red' is not a color. Palette colors must be written as \"#RRGGBB\"."
+    );
+}
+
+#[test]
+fn embedded_bitmaps_hold_the_pixels_of_each_glyph() {
+    let mut params = common::params(4);
+    params.embed_bitmaps = Some(true);
+    let bytes = generate_ttf(&sheet(), params).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+
+    let sbix = font.sbix().unwrap();
+    let strike = sbix.strikes().get(0).unwrap();
+    assert_eq!(strike.ppem(), 4);
+    // the space has no pixels
+    assert!(strike.glyph_data(GlyphId::new(2)).unwrap().is_none());
+
+    // B is cropped to its ink, with the bottom-left corner two pixels below the baseline
+    let b = strike.glyph_data(GlyphId::new(4)).unwrap().unwrap();
+    assert_eq!(b.graphic_type(), Tag::new(b"png "));
+    assert_eq!((b.origin_offset_x(), b.origin_offset_y()), (0, -2));
+    let png = b.data();
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    assert_eq!(&png[12..16], b"IHDR");
+    assert_eq!(&png[16..24], [0, 0, 0, 4, 0, 0, 0, 4]);
+    let idat_length = u32::from_be_bytes(png[33..37].try_into().unwrap()) as usize;
+    assert_eq!(&png[37..41], b"IDAT");
+    let mut rows = vec![];
+    ZlibDecoder::new(&png[41..41 + idat_length])
+        .read_to_end(&mut rows)
+        .unwrap();
+    let alpha: Vec<u8> = rows
+        .chunks(1 + 4 * 4)
+        .flat_map(|row| row[1..].chunks(4).map(|pixel| pixel[3]))
+        .collect();
+    assert_eq!(
+        alpha,
+        [
+            255, 255, 255, 255, //
+            255, 0, 0, 255, //
+            255, 0, 0, 255, //
+            255, 255, 255, 255,
+        ]
+    );
+
+    let a = strike.glyph_data(GlyphId::new(3)).unwrap().unwrap();
+    assert_eq!((a.origin_offset_x(), a.origin_offset_y()), (0, 0));
+    assert_eq!(&a.data()[16..24], [0, 0, 0, 2, 0, 0, 0, 2]);
+}
+
+#[test]
+fn embedded_bitmaps_are_cropped_at_the_alpha_threshold() {
+    const FAINT: [u8; 4] = [0, 0, 0, 64];
+    let mut ase = AseBuilder::new(4, 4);
+    let layer = ase.layer("U+0041-");
+    #[rustfmt::skip]
+    let pixels = vec![
+        common::EMPTY, common::EMPTY, common::EMPTY, common::EMPTY,
+        common::EMPTY, common::EMPTY, common::EMPTY, common::EMPTY,
+        common::INK, common::INK, FAINT, common::EMPTY,
+        common::INK, common::INK, FAINT, common::EMPTY,
+    ];
+    ase.pixels(0, layer, 0, 0, 4, 4, pixels);
+    let bytes = ase.build();
+    let size = |alpha_threshold: Option<u8>| {
+        let mut params = common::params(4);
+        params.embed_bitmaps = Some(true);
+        params.alpha_threshold = alpha_threshold;
+        let font_bytes = generate_ttf(&bytes, params).unwrap();
+        let font = FontRef::new(&font_bytes).unwrap();
+        let sbix = font.sbix().unwrap();
+        let strike = sbix.strikes().get(0).unwrap();
+        let a = strike.glyph_data(GlyphId::new(3)).unwrap().unwrap();
+        a.data()[16..24].to_vec()
+    };
+
+    // the faint column is only kept when the outline is traced from it as well
+    assert_eq!(size(None), [0, 0, 0, 2, 0, 0, 0, 2]);
+    assert_eq!(size(Some(64)), [0, 0, 0, 3, 0, 0, 0, 2]);
+}
+
+#[test]
+fn postscript_name_can_differ_from_the_family() {
+    let postscript_name = |family: &str, postscript_name: Option<&str>| {
+        let mut params = common::params(4);
+        params.family = Some(family.to_string());
+        params.postscript_name = postscript_name.map(str::to_string);
+        let bytes = generate_ttf(&sheet(), params)?;
+        let font = FontRef::new(&bytes).unwrap();
+        let name = font.name().unwrap();
+        let record = name
+            .name_record()
+            .iter()
+            .find(|record| record.name_id().to_u16() == 6)
+            .unwrap();
+        Ok::<_, Error>(record.string(name.string_data()).unwrap().to_string())
+    };
+
+    assert_eq!(postscript_name("Dot Font", None).unwrap(), "Dot-Font");
+    assert_eq!(postscript_name("Dot (Bold)", None).unwrap(), "Dot-Bold");
+    assert_eq!(postscript_name("<%>", None).unwrap(), "Untitled");
+    assert_eq!(
+        postscript_name("Dot Font", Some("DotFont-Regular")).unwrap(),
+        "DotFont-Regular"
+    );
+    assert_eq!(
+        postscript_name("Dot Font", Some("Dot Font"))
+            .err()
+            .unwrap()
+            .to_string(),
+        "The PostScript name 'Dot Font' must be 1 to 63 printable ASCII characters without spaces or any of [](){}<>/%."
+    );
+    assert!(postscript_name("Dot", Some(&"D".repeat(64))).is_err());
+}
+
+#[test]
+fn unicode_ranges_follow_the_mapped_blocks() {
+    let mut ase = AseBuilder::new(4, 4);
+    for name in ["U+00E9", "U+3042", "U+1F000"] {
+        let layer = ase.layer(name);
+        ase.draw(0, layer, 0, 0, "#");
+    }
+    let bytes = generate_ttf(&ase.build(), common::params(4)).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+    let os2 = font.os2().unwrap();
+
+    // the space is Basic Latin, é is Latin-1 Supplement, あ is Hiragana and 🀀 is past the BMP and
+    // in Mahjong Tiles
+    assert_eq!(
+        [
+            os2.ul_unicode_range_1(),
+            os2.ul_unicode_range_2(),
+            os2.ul_unicode_range_3(),
+            os2.ul_unicode_range_4(),
+        ],
+        [0b11, 1 << (49 - 32) | 1 << (57 - 32), 0, 1 << (122 - 96)]
+    );
+}
+
+#[test]
+fn code_pages_follow_the_mapped_letters() {
+    let code_pages = |names: &[&str]| {
+        let mut ase = AseBuilder::new(4, 4);
+        for name in names {
+            let layer = ase.layer(name);
+            ase.draw(0, layer, 0, 0, "#");
+        }
+        let bytes = generate_ttf(&ase.build(), common::params(4)).unwrap();
+        let font = FontRef::new(&bytes).unwrap();
+        let os2 = font.os2().unwrap();
+        [
+            os2.ul_code_page_range_1().unwrap(),
+            os2.ul_code_page_range_2().unwrap(),
+        ]
+    };
+
+    // neither the space nor an arrow are written in any code page
+    assert_eq!(code_pages(&["U+2190"]), [0, 0]);
+    // A is Latin 1, Ř is Latin 2 and Ж is Cyrillic
+    assert_eq!(code_pages(&["U+0041", "U+0158", "U+0416"]), [0b111, 0]);
+    // あ is Japanese, 한 is Korean and ═ is the US code page of the IBM PC
+    assert_eq!(
+        code_pages(&["U+3042", "U+D55C", "U+2550"]),
+        [1 << 17 | 1 << 19, 1 << (63 - 32)]
+    );
+}
+
+#[test]
+fn trailing_equal_advances_share_one_metric() {
+    let mut params = common::params(4);
+    params.trim = Some(false);
+    let bytes = generate_ttf(&sheet(), params).unwrap();
+    let font = FontRef::new(&bytes).unwrap();
+    let hmtx = font.hmtx().unwrap();
+
+    // every glyph is a cell wide, so one long metric holds the advance of them all
+    assert_eq!(font.hhea().unwrap().number_of_h_metrics(), 1);
+    assert_eq!(hmtx.h_metrics().len(), 1);
+    assert_eq!(hmtx.left_side_bearings().len(), 4);
+    for id in 0..5 {
+        assert_eq!(hmtx.advance(GlyphId::new(id)), Some(4 * 64));
+    }
+    assert_eq!(hmtx.side_bearing(GlyphId::new(4)), Some(0));
+}
+
+#[test]
+fn synthetic_bold_widens_the_ink() {
+    let build = |synthetic_bold: Option<u32>| {
+        let mut params = common::params(4);
+        params.trim = Some(false);
+        params.synthetic_bold = synthetic_bold;
+        generate_ttf(&sheet(), params).unwrap()
+    };
+    let regular = build(None);
+    let regular = FontRef::new(&regular).unwrap();
+    let bold = build(Some(1));
+    let bold = FontRef::new(&bold).unwrap();
+
+    // the square of A grows one pixel to the right, and keeps its height
+    let bounds = |font: &FontRef| {
+        let glyf = font.glyf().unwrap();
+        let loca = font.loca(None).unwrap();
+        let glyph = loca.get_glyf(GlyphId::new(3), &glyf).unwrap().unwrap();
+        (glyph.x_min(), glyph.x_max(), glyph.y_min(), glyph.y_max())
+    };
+    assert_eq!(bounds(&regular), (0, 2 * 64, 0, 2 * 64));
+    assert_eq!(bounds(&bold), (0, 3 * 64, 0, 2 * 64));
+    assert_eq!(bold.hmtx().unwrap().advance(GlyphId::new(3)), Some(5 * 64));
+
+    // the same style as a subfamily of Bold
+    let os2 = bold.os2().unwrap();
+    assert_eq!(os2.us_weight_class(), 700);
+    assert_eq!(os2.fs_selection().bits(), 0xA0);
+    assert_eq!(bold.head().unwrap().mac_style().bits(), 1);
+    let name = bold.name().unwrap();
+    let subfamily = name
+        .name_record()
+        .iter()
+        .find(|record| record.name_id().to_u16() == 2)
+        .unwrap();
+    assert_eq!(
+        subfamily.string(name.string_data()).unwrap().to_string(),
+        "Bold"
+    );
+
+    // embedded bitmaps would show the cells at their regular weight
+    let mut params = common::params(4);
+    params.synthetic_bold = Some(1);
+    params.embed_bitmaps = Some(true);
+    assert_eq!(
+        generate_ttf(&sheet(), params).err().unwrap().to_string(),
+        "Embedded bitmaps can't be used with a synthetic bold, as they hold the pixels of the cells as they are drawn."
+    );
+}
+
+#[test]
+fn oversized_cells_are_reported() {
+    let mut ase = AseBuilder::new(256, 256);
+    let layer = ase.layer("U+0041");
+    ase.draw(0, layer, 0, 0, "##");
+    let error = generate_ttf(&ase.build(), common::params(256))
+        .err()
+        .unwrap();
+    assert_eq!(
+        error.to_string(),
+        "The 256x256 glyph cell has too many pixels."
+    );
+}
@@ -0,0 +1,182 @@
+use std::io::Read;
+
+#[cfg(feature = "woff2")]
+use ase2ttf_core::generate_woff2;
+use ase2ttf_core::{Params, generate_ttf, generate_woff};
+use flate2::read::ZlibDecoder;
+
+mod common;
+
+use common::AseBuilder;
+
+fn sheet() -> Vec<u8> {
+    let mut ase = AseBuilder::new(8, 4);
+    let layer = ase.layer("U+0041-");
+    ase.draw(
+        0,
+        layer,
+        0,
+        0,
+        "
+        ##..####
+        ##..#..#
+        ....#..#
+        ....####
+        ",
+    );
+    ase.build()
+}
+
+/// Params with a fixed timestamp, so that every build has the same head table.
+fn params() -> Params {
+    let mut params = common::params(4);
+    params.created_timestamp = Some(1_700_000_000);
+    params
+}
+
+fn u16_at(data: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes(data[offset..offset + 2].try_into().unwrap())
+}
+
+fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+/// Reads a number in the variable-length encoding of WOFF2 and moves past it.
+#[cfg(feature = "woff2")]
+fn read_uint_base128(data: &[u8], position: &mut usize) -> u32 {
+    let mut value = 0u32;
+    loop {
+        let byte = data[*position];
+        *position += 1;
+        value = value << 7 | (byte & 0x7F) as u32;
+        if byte & 0x80 == 0 {
+            return value;
+        }
+    }
+}
+
+/// The tables of a TrueType font sorted by tag, as they are stored in WOFF2.
+#[cfg(feature = "woff2")]
+fn sorted_tables(ttf: &[u8]) -> Vec<(&[u8], &[u8])> {
+    let mut tables: Vec<(&[u8], &[u8])> = (0..u16_at(ttf, 4) as usize)
+        .map(|i| 12 + 16 * i)
+        .map(|record| {
+            let offset = u32_at(ttf, record + 8) as usize;
+            let length = u32_at(ttf, record + 12) as usize;
+            (&ttf[record..record + 4], &ttf[offset..offset + length])
+        })
+        .collect();
+    tables.sort();
+    tables
+}
+
+#[test]
+fn woff_unpacks_to_the_ttf_tables() {
+    let ttf = generate_ttf(&sheet(), params()).unwrap();
+    let woff = generate_woff(&sheet(), params()).unwrap();
+
+    assert_eq!(&woff[0..4], b"wOFF");
+    assert_eq!(u32_at(&woff, 4), u32_at(&ttf, 0));
+    assert_eq!(u32_at(&woff, 8) as usize, woff.len());
+    let table_count = u16_at(&woff, 12) as usize;
+    assert_eq!(table_count, u16_at(&ttf, 4) as usize);
+    assert!(woff.len() < ttf.len());
+
+    // every table unpacks to the bytes of the same table in the TrueType font
+    let mut total_sfnt_size = 12 + 16 * table_count;
+    for i in 0..table_count {
+        let entry = 44 + 20 * i;
+        let tag = &woff[entry..entry + 4];
+        let offset = u32_at(&woff, entry + 4) as usize;
+        let comp_length = u32_at(&woff, entry + 8) as usize;
+        let orig_length = u32_at(&woff, entry + 12) as usize;
+        assert_eq!(offset % 4, 0);
+
+        let stored = &woff[offset..offset + comp_length];
+        let table = if comp_length < orig_length {
+            let mut table = vec![];
+            ZlibDecoder::new(stored).read_to_end(&mut table).unwrap();
+            table
+        } else {
+            stored.to_vec()
+        };
+        let record = (0..table_count)
+            .map(|j| 12 + 16 * j)
+            .find(|&record| &ttf[record..record + 4] == tag)
+            .unwrap();
+        let ttf_offset = u32_at(&ttf, record + 8) as usize;
+        assert_eq!(u32_at(&woff, entry + 16), u32_at(&ttf, record + 4));
+        assert_eq!(table, &ttf[ttf_offset..ttf_offset + orig_length]);
+        total_sfnt_size += orig_length.next_multiple_of(4);
+    }
+    assert_eq!(u32_at(&woff, 16) as usize, total_sfnt_size);
+}
+
+#[cfg(feature = "woff2")]
+#[test]
+fn woff2_lists_the_ttf_tables_untransformed() {
+    let ttf = generate_ttf(&sheet(), params()).unwrap();
+    let woff2 = generate_woff2(&sheet(), params()).unwrap();
+
+    assert_eq!(&woff2[0..4], b"wOF2");
+    assert_eq!(u32_at(&woff2, 4), u32_at(&ttf, 0));
+    assert_eq!(u32_at(&woff2, 8) as usize, woff2.len());
+    assert_eq!(woff2.len() % 4, 0);
+    let table_count = u16_at(&woff2, 12) as usize;
+    assert_eq!(table_count, u16_at(&ttf, 4) as usize);
+
+    // the directory holds the tags and lengths of the font, in the same order
+    let mut position = 48;
+    let mut total_sfnt_size = 12 + 16 * table_count;
+    for (tag, table) in sorted_tables(&ttf) {
+        let flags = woff2[position];
+        position += 1;
+        if flags & 63 == 63 {
+            assert_eq!(&woff2[position..position + 4], tag);
+            position += 4;
+        }
+        // glyf and loca are only untransformed with version 3
+        let transform = flags >> 6;
+        if tag == b"glyf" || tag == b"loca" {
+            assert_eq!(transform, 3);
+        } else {
+            assert_eq!(transform, 0);
+        }
+        let length = read_uint_base128(&woff2, &mut position) as usize;
+        assert_eq!(length, table.len());
+        total_sfnt_size += length.next_multiple_of(4);
+    }
+    assert_eq!(u32_at(&woff2, 16) as usize, total_sfnt_size);
+    let compressed_size = u32_at(&woff2, 20) as usize;
+    assert_eq!(
+        (position + compressed_size).next_multiple_of(4),
+        woff2.len()
+    );
+}
+
+#[cfg(feature = "woff2")]
+#[test]
+fn woff2_decompresses_to_the_ttf_tables() {
+    let ttf = generate_ttf(&sheet(), params()).unwrap();
+    let woff2 = generate_woff2(&sheet(), params()).unwrap();
+
+    let mut position = 48;
+    for _ in 0..u16_at(&woff2, 12) {
+        let flags = woff2[position];
+        position += 1 + if flags & 63 == 63 { 4 } else { 0 };
+        read_uint_base128(&woff2, &mut position);
+    }
+    let compressed_size = u32_at(&woff2, 20) as usize;
+    let mut tables = vec![];
+    brotli::Decompressor::new(&woff2[position..position + compressed_size], 4096)
+        .read_to_end(&mut tables)
+        .unwrap();
+
+    // the stream holds the tables one after another, without padding
+    let expected: Vec<u8> = sorted_tables(&ttf)
+        .into_iter()
+        .flat_map(|(_, table)| table.iter().copied())
+        .collect();
+    assert_eq!(tables, expected);
+}